@@ -0,0 +1,31 @@
+/// Accounting for the chips that come into and go out of a game.
+use serde::{Deserialize, Serialize};
+
+/// The chips that have come into and gone out of a game. The chips bought should always
+/// equal the chips in play plus the chips taken away and the rake. See `Game::audit`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct ChipAudit {
+    /// Chips received by players when they joined.
+    pub buy_ins: usize,
+    pub rebuys: usize,
+    pub add_ons: usize,
+    /// Chips taken away by players who left the table.
+    pub cashed_out: usize,
+    /// Chips taken by the house.
+    #[serde(default)]
+    pub rake: usize,
+    /// Chips in the players' bank rolls and the pots.
+    pub in_play: usize,
+}
+/// Implementation for ChipAudit.
+impl ChipAudit {
+    /// The total number of chips bought.
+    pub fn bought(&self) -> usize {
+        self.buy_ins + self.rebuys + self.add_ons
+    }
+
+    /// Whether every chip bought is accounted for.
+    pub fn balanced(&self) -> bool {
+        self.bought() == self.in_play + self.cashed_out + self.rake
+    }
+}
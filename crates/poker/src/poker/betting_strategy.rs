@@ -8,11 +8,17 @@ use crate::poker::{
 use rand::Rng;
 use serde::{Deserialize, Serialize};
 
-/// Struct for arguments to place_bet.
+/// Struct for arguments to place_bet. `call` is the number of chips the player
+/// needs to pay to match the highest bet and `min` is the minimum amount by which
+/// they can raise it. `max` is the maximum amount by which they can raise it, or None
+/// if there is no limit, and is zero when the bet can't be raised any more. A player
+/// who can't afford the minimum raise can still go all in.
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct BetArgs {
     pub call: usize,
     pub min: usize,
+    #[serde(default)]
+    pub max: Option<usize>,
     pub stage: Stage,
     pub cycle: u8,
     pub community_cards: Vec<Card>,
@@ -42,7 +48,7 @@ pub fn default_betting_strategy(args: BetArgs, _hole_cards: (Card, Card), bank_r
 ///
 /// + fold if necessary,
 /// + go all in if neccessary,
-/// + toss a coin to choose between betting some value not more than twice
+/// + toss a coin to choose between raising by some value not more than twice
 ///   the minimum amount and calling the bet.
 pub fn modest_betting_strategy(args: BetArgs, _hole_cards: (Card, Card), bank_roll: usize) -> Bet {
     if bank_roll == 0 {
@@ -50,13 +56,17 @@ pub fn modest_betting_strategy(args: BetArgs, _hole_cards: (Card, Card), bank_ro
     } else if bank_roll <= args.call {
         Bet::AllIn(bank_roll)
     } else {
+        // choose a value between call+min and call+min*2 or one chip less than bank_roll
+        // , whichever is lower, and no more than the limit.
+        let min = args.call + args.min;
+        let mut max = std::cmp::min(args.call + args.min * 2, bank_roll - 1);
+        if let Some(limit) = args.max {
+            max = std::cmp::min(max, args.call + limit + 1);
+        }
         // toss a coin between raising and calling.
-        if rand::random() {
-            // choose a value between min and min*2 or one chip less than bank_roll
-            // , whichever is lower.
-            let max = std::cmp::min(args.min * 2, bank_roll - 1);
+        if min < max && rand::random() {
             let mut rng = rand::rng();
-            let amount = rng.random_range(args.min..max);
+            let amount = rng.random_range(min..max);
             Bet::Raise(amount)
         } else {
             Bet::Call
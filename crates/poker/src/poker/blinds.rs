@@ -0,0 +1,573 @@
+/// Blind levels and schedules that raise the blinds as a game goes on.
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::Cell,
+    fmt::{self, Debug, Display},
+    fs,
+    path::Path,
+    time::{Duration, Instant, SystemTime, UNIX_EPOCH},
+};
+
+/// How long a blind level lasts, either a number of rounds or a number of minutes.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LevelDuration {
+    Hands(usize),
+    Minutes(u64),
+}
+
+/// A level in a blind schedule. The ante is paid by every player at the beginning
+/// of each round, in addition to the blinds.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BlindLevel {
+    pub small_blind: usize,
+    pub big_blind: usize,
+    #[serde(default)]
+    pub ante: usize,
+    #[serde(flatten)]
+    pub duration: LevelDuration,
+}
+/// Implementation of Display trait for BlindLevel.
+impl Display for BlindLevel {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}/{}", self.small_blind, self.big_blind)?;
+        if self.ante > 0 {
+            write!(f, " (ante {})", self.ante)?;
+        }
+        Ok(())
+    }
+}
+
+/// Errors found when building or restoring a blind schedule.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum BlindScheduleError {
+    /// The schedule has no levels.
+    NoLevels,
+    /// The big blind at the level with the given index is zero.
+    BigBlind(usize),
+    /// The small blind at a level is zero or more than the big blind.
+    SmallBlind {
+        level: usize,
+        small_blind: usize,
+        big_blind: usize,
+    },
+    /// A saved schedule's current level isn't one of its levels.
+    CurrentLevel(usize),
+    /// The schedule couldn't be read from TOML.
+    Toml(String),
+    /// The schedule's file couldn't be read.
+    Io(String),
+}
+/// Implementation of Display trait for BlindScheduleError.
+impl Display for BlindScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            BlindScheduleError::NoLevels => {
+                write!(f, "A blind schedule needs at least one level")
+            }
+            BlindScheduleError::BigBlind(level) => write!(
+                f,
+                "The big blind at level {} must be greater than zero",
+                level + 1
+            ),
+            BlindScheduleError::SmallBlind {
+                level,
+                small_blind,
+                big_blind,
+            } => write!(
+                f,
+                "The small blind ({}) at level {} must be greater than zero and no more than the big blind ({})",
+                small_blind,
+                level + 1,
+                big_blind
+            ),
+            BlindScheduleError::CurrentLevel(current) => write!(
+                f,
+                "The current level ({}) isn't in the blind schedule",
+                current + 1
+            ),
+            BlindScheduleError::Toml(e) => write!(f, "Can't parse the blind schedule: {}", e),
+            BlindScheduleError::Io(e) => write!(f, "Can't read the blind schedule: {}", e),
+        }
+    }
+}
+/// Implementation of Error trait for BlindScheduleError.
+impl std::error::Error for BlindScheduleError {}
+
+/// The source of the current time for schedules with levels measured in minutes, and
+/// for the times recorded in hand histories.
+pub trait Clock: Debug {
+    /// The current time.
+    fn now(&self) -> Instant;
+
+    /// The current time in seconds since the Unix epoch.
+    fn unix_time(&self) -> u64 {
+        SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs())
+    }
+}
+
+/// A clock that reads the system time.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+/// Implementation of Clock for SystemClock.
+impl Clock for SystemClock {
+    fn now(&self) -> Instant {
+        Instant::now()
+    }
+}
+
+/// A clock that only moves when it is told to, for use in tests and simulations.
+#[derive(Debug, Clone)]
+pub struct ManualClock {
+    now: Cell<Instant>,
+    // When the clock started, and the Unix time it started at.
+    started: (Instant, u64),
+}
+/// Implementation for ManualClock.
+impl ManualClock {
+    /// Construct a clock that starts at the current time.
+    pub fn new() -> Self {
+        ManualClock::at(SystemClock.unix_time())
+    }
+    /// Construct a clock that starts at the given time in seconds since the Unix epoch.
+    pub fn at(unix_time: u64) -> Self {
+        let now = Instant::now();
+        ManualClock {
+            now: Cell::new(now),
+            started: (now, unix_time),
+        }
+    }
+    /// Move the clock forward.
+    pub fn advance(&self, duration: Duration) {
+        self.now.set(self.now.get() + duration);
+    }
+}
+/// Implementation of Default trait for ManualClock.
+impl Default for ManualClock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+/// Implementation of Clock for ManualClock.
+impl Clock for ManualClock {
+    fn now(&self) -> Instant {
+        self.now.get()
+    }
+    fn unix_time(&self) -> u64 {
+        let (started, unix_time) = self.started;
+        unix_time + self.now.get().duration_since(started).as_secs()
+    }
+}
+/// Allow a clock to be shared with the schedule, so tests can keep a handle on it.
+impl<C: Clock> Clock for std::rc::Rc<C> {
+    fn now(&self) -> Instant {
+        (**self).now()
+    }
+    fn unix_time(&self) -> u64 {
+        (**self).unix_time()
+    }
+}
+
+/// The progress of a blind schedule, which can be saved and used to restore it. See
+/// `BlindSchedule::state`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ScheduleState {
+    pub levels: Vec<BlindLevel>,
+    /// The index of the current level.
+    pub current: usize,
+    /// The number of rounds played in the current level.
+    pub hands_in_level: usize,
+    /// How long the current level has lasted, if it has started.
+    pub elapsed: Option<Duration>,
+}
+
+/// A schedule of blind levels. The schedule moves to the next level when the current
+/// one has lasted for its duration, and stays at the last level once it is reached.
+#[derive(Debug, Deserialize)]
+pub struct BlindSchedule {
+    levels: Vec<BlindLevel>,
+    #[serde(skip)]
+    current: usize,
+    #[serde(skip)]
+    hands_in_level: usize,
+    #[serde(skip)]
+    level_started: Option<Instant>,
+    #[serde(skip, default = "default_clock")]
+    clock: Box<dyn Clock>,
+}
+
+/// The clock used by deserialised schedules.
+fn default_clock() -> Box<dyn Clock> {
+    Box::new(SystemClock)
+}
+
+/// Implementation for BlindSchedule.
+impl BlindSchedule {
+    /// Construct a schedule from a list of levels, using the system clock. Each level's
+    /// blinds are checked as they are in `GameConfig::validate`.
+    pub fn build(levels: Vec<BlindLevel>) -> Result<Self, BlindScheduleError> {
+        if levels.is_empty() {
+            return Err(BlindScheduleError::NoLevels);
+        }
+        for (i, l) in levels.iter().enumerate() {
+            if l.big_blind == 0 {
+                return Err(BlindScheduleError::BigBlind(i));
+            }
+            if l.small_blind == 0 || l.small_blind > l.big_blind {
+                return Err(BlindScheduleError::SmallBlind {
+                    level: i,
+                    small_blind: l.small_blind,
+                    big_blind: l.big_blind,
+                });
+            }
+        }
+        Ok(BlindSchedule {
+            levels,
+            current: 0,
+            hands_in_level: 0,
+            level_started: None,
+            clock: default_clock(),
+        })
+    }
+
+    /// Replace the clock used to time levels measured in minutes.
+    pub fn with_clock(mut self, clock: impl Clock + 'static) -> Self {
+        self.clock = Box::new(clock);
+        self
+    }
+
+    /// Parse a schedule from a TOML document containing an array of levels, e.g.
+    ///
+    /// ```toml
+    /// [[levels]]
+    /// small_blind = 10
+    /// big_blind = 20
+    /// hands = 10
+    ///
+    /// [[levels]]
+    /// small_blind = 20
+    /// big_blind = 40
+    /// ante = 5
+    /// minutes = 15
+    /// ```
+    pub fn from_toml(toml_str: &str) -> Result<Self, BlindScheduleError> {
+        let schedule: BlindSchedule =
+            toml::from_str(toml_str).map_err(|e| BlindScheduleError::Toml(e.to_string()))?;
+        BlindSchedule::build(schedule.levels)
+    }
+
+    /// Read a schedule from a TOML file. See `from_toml`.
+    pub fn load(path: impl AsRef<Path>) -> Result<Self, BlindScheduleError> {
+        let toml_str =
+            fs::read_to_string(path).map_err(|e| BlindScheduleError::Io(e.to_string()))?;
+        BlindSchedule::from_toml(&toml_str)
+    }
+
+    /// The levels in the schedule.
+    pub fn levels(&self) -> &[BlindLevel] {
+        &self.levels
+    }
+
+    /// The index of the current level, starting from zero.
+    pub fn level_index(&self) -> usize {
+        self.current
+    }
+
+    /// The current level.
+    pub fn current_level(&self) -> BlindLevel {
+        self.levels[self.current]
+    }
+
+    /// The progress of the schedule, e.g. to save it with the rest of a game.
+    pub fn state(&self) -> ScheduleState {
+        ScheduleState {
+            levels: self.levels.clone(),
+            current: self.current,
+            hands_in_level: self.hands_in_level,
+            elapsed: self
+                .level_started
+                .map(|started| self.clock.now().saturating_duration_since(started)),
+        }
+    }
+
+    /// Construct a schedule that carries on from a saved state, using the system clock.
+    /// The time already spent in the current level counts towards its duration.
+    pub fn from_state(state: ScheduleState) -> Result<Self, BlindScheduleError> {
+        BlindSchedule::from_state_with_clock(state, SystemClock)
+    }
+
+    /// Construct a schedule that carries on from a saved state, using the given clock.
+    /// See `from_state`.
+    pub fn from_state_with_clock(
+        state: ScheduleState,
+        clock: impl Clock + 'static,
+    ) -> Result<Self, BlindScheduleError> {
+        let mut schedule = BlindSchedule::build(state.levels)?.with_clock(clock);
+        if state.current >= schedule.levels.len() {
+            return Err(BlindScheduleError::CurrentLevel(state.current));
+        }
+        schedule.current = state.current;
+        schedule.hands_in_level = state.hands_in_level;
+        schedule.level_started = state
+            .elapsed
+            .and_then(|elapsed| schedule.clock.now().checked_sub(elapsed));
+        Ok(schedule)
+    }
+
+    /// Called at the beginning of every round. Starts the clock for the first level,
+    /// moves on to the next level if the current one has expired, and counts the round.
+    /// Returns the new level if the blinds changed.
+    pub fn start_round(&mut self) -> Option<BlindLevel> {
+        let now = self.clock.now();
+        let mut started = *self.level_started.get_or_insert(now);
+        let before = self.current;
+        while self.current + 1 < self.levels.len() {
+            let expired = match self.levels[self.current].duration {
+                LevelDuration::Hands(n) => self.hands_in_level >= n,
+                LevelDuration::Minutes(m) => {
+                    now.duration_since(started) >= Duration::from_secs(m * 60)
+                }
+            };
+            if !expired {
+                break;
+            }
+            // Levels measured in time start when the previous one should have ended,
+            // so that a long round doesn't delay the rest of the schedule.
+            if let LevelDuration::Minutes(m) = self.levels[self.current].duration {
+                started += Duration::from_secs(m * 60);
+            } else {
+                started = now;
+            }
+            self.current += 1;
+            self.hands_in_level = 0;
+        }
+        self.level_started = Some(started);
+        self.hands_in_level += 1;
+        if self.current != before {
+            Some(self.current_level())
+        } else {
+            None
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::rc::Rc;
+
+    fn level(small_blind: usize, duration: LevelDuration) -> BlindLevel {
+        BlindLevel {
+            small_blind,
+            big_blind: small_blind * 2,
+            ante: 0,
+            duration,
+        }
+    }
+
+    #[test]
+    fn test_levels_by_hands() {
+        let mut schedule = BlindSchedule::build(vec![
+            level(10, LevelDuration::Hands(2)),
+            level(20, LevelDuration::Hands(2)),
+            level(40, LevelDuration::Hands(2)),
+        ])
+        .unwrap();
+        let changes: Vec<Option<usize>> = (0..8)
+            .map(|_| schedule.start_round().map(|l| l.small_blind))
+            .collect();
+        assert!(
+            changes == vec![None, None, Some(20), None, Some(40), None, None, None],
+            "Expected the blinds to go up every two rounds and stop at the last level, was {:?}",
+            changes
+        );
+        assert!(
+            schedule.current_level().small_blind == 40,
+            "Expected to stay at the last level, was {}",
+            schedule.current_level()
+        );
+    }
+
+    #[test]
+    fn test_levels_by_minutes() {
+        let clock = Rc::new(ManualClock::new());
+        let mut schedule = BlindSchedule::build(vec![
+            level(10, LevelDuration::Minutes(10)),
+            level(20, LevelDuration::Minutes(10)),
+            level(40, LevelDuration::Minutes(10)),
+        ])
+        .unwrap()
+        .with_clock(clock.clone());
+        assert!(schedule.start_round().is_none(), "Expected the first level");
+        clock.advance(Duration::from_secs(9 * 60));
+        assert!(schedule.start_round().is_none(), "Expected the first level");
+        clock.advance(Duration::from_secs(60));
+        assert!(
+            schedule.start_round().map(|l| l.small_blind) == Some(20),
+            "Expected the second level after ten minutes"
+        );
+        // A long break skips straight to the last level.
+        clock.advance(Duration::from_secs(30 * 60));
+        assert!(
+            schedule.start_round().map(|l| l.small_blind) == Some(40),
+            "Expected the last level after forty minutes"
+        );
+    }
+
+    #[test]
+    fn test_from_toml() {
+        let schedule = BlindSchedule::from_toml(
+            r#"
+            [[levels]]
+            small_blind = 10
+            big_blind = 20
+            hands = 10
+
+            [[levels]]
+            small_blind = 20
+            big_blind = 40
+            ante = 5
+            minutes = 15
+            "#,
+        )
+        .unwrap();
+        assert!(
+            schedule.levels()
+                == [
+                    BlindLevel {
+                        small_blind: 10,
+                        big_blind: 20,
+                        ante: 0,
+                        duration: LevelDuration::Hands(10),
+                    },
+                    BlindLevel {
+                        small_blind: 20,
+                        big_blind: 40,
+                        ante: 5,
+                        duration: LevelDuration::Minutes(15),
+                    },
+                ],
+            "Unexpected levels {:?}",
+            schedule.levels()
+        );
+        let result = BlindSchedule::from_toml("levels = []");
+        assert!(
+            matches!(result, Err(BlindScheduleError::NoLevels)),
+            "Expected an empty schedule to be rejected, was {:?}",
+            result.map(|s| s.state())
+        );
+        let result = BlindSchedule::from_toml("levels = 3");
+        assert!(
+            matches!(result, Err(BlindScheduleError::Toml(_))),
+            "Expected a TOML error, was {:?}",
+            result.map(|s| s.state())
+        );
+        for (small_blind, big_blind, error) in [
+            (0, 0, BlindScheduleError::BigBlind(1)),
+            (
+                0,
+                20,
+                BlindScheduleError::SmallBlind {
+                    level: 1,
+                    small_blind: 0,
+                    big_blind: 20,
+                },
+            ),
+            (
+                30,
+                20,
+                BlindScheduleError::SmallBlind {
+                    level: 1,
+                    small_blind: 30,
+                    big_blind: 20,
+                },
+            ),
+        ] {
+            let level = BlindLevel {
+                small_blind,
+                big_blind,
+                ante: 0,
+                duration: LevelDuration::Hands(10),
+            };
+            let first = BlindLevel {
+                small_blind: 10,
+                big_blind: 20,
+                ante: 0,
+                duration: LevelDuration::Hands(10),
+            };
+            let result = BlindSchedule::build(vec![first, level]);
+            assert!(
+                result.as_ref().err() == Some(&error),
+                "Expected a level of {}/{} to be rejected with {:?}, was {:?}",
+                small_blind,
+                big_blind,
+                error,
+                result.map(|s| s.state())
+            );
+        }
+    }
+
+    #[test]
+    fn test_restore_state() {
+        let mut schedule = BlindSchedule::build(vec![
+            level(10, LevelDuration::Hands(3)),
+            level(20, LevelDuration::Hands(3)),
+        ])
+        .unwrap();
+        (0..2).for_each(|_| {
+            schedule.start_round();
+        });
+        let state = schedule.state();
+        assert!(
+            state.current == 0 && state.hands_in_level == 2 && state.elapsed.is_some(),
+            "Unexpected state {:?}",
+            state
+        );
+        let mut restored = BlindSchedule::from_state(state.clone()).unwrap();
+        let changes: Vec<Option<usize>> = (0..2)
+            .map(|_| restored.start_round().map(|l| l.small_blind))
+            .collect();
+        assert!(
+            changes == vec![None, Some(20)],
+            "Expected the restored schedule to carry on from the same round, was {:?}",
+            changes
+        );
+        let bad = ScheduleState {
+            current: 2,
+            ..state
+        };
+        let result = BlindSchedule::from_state(bad);
+        assert!(
+            matches!(result, Err(BlindScheduleError::CurrentLevel(2))),
+            "Expected a state with a missing level to be rejected, was {:?}",
+            result.map(|s| s.state())
+        );
+    }
+
+    #[test]
+    fn test_restore_state_with_clock() {
+        let clock = Rc::new(ManualClock::new());
+        let mut schedule = BlindSchedule::build(vec![
+            level(10, LevelDuration::Minutes(10)),
+            level(20, LevelDuration::Minutes(10)),
+        ])
+        .unwrap()
+        .with_clock(clock.clone());
+        schedule.start_round();
+        clock.advance(Duration::from_secs(6 * 60));
+        let mut restored =
+            BlindSchedule::from_state_with_clock(schedule.state(), clock.clone()).unwrap();
+        clock.advance(Duration::from_secs(3 * 60));
+        assert!(
+            restored.start_round().is_none(),
+            "Expected the first level after nine minutes"
+        );
+        clock.advance(Duration::from_secs(60));
+        assert!(
+            restored.start_round().map(|l| l.small_blind) == Some(20),
+            "Expected the restored schedule to keep the clock and move on after ten minutes"
+        );
+    }
+}
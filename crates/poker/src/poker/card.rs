@@ -97,6 +97,52 @@ impl Display for Card {
         write!(f, "{} of {}", self.rank, self.suit)
     }
 }
+/// Implementation for Card.
+impl Card {
+    /// The short form of the card used in hand histories, e.g. `Ah` or `Tc`.
+    pub fn short_name(&self) -> String {
+        let rank = match self.rank {
+            Rank::Rank10 => 'T',
+            Rank::Jack => 'J',
+            Rank::Queen => 'Q',
+            Rank::King => 'K',
+            Rank::Ace => 'A',
+            r => char::from(b'0' + r.value()),
+        };
+        let suit = match self.suit {
+            Suit::Clubs => 'c',
+            Suit::Diamonds => 'd',
+            Suit::Hearts => 'h',
+            Suit::Spades => 's',
+        };
+        format!("{}{}", rank, suit)
+    }
+
+    /// Read a card from its short form, e.g. `Ah` or `Tc`.
+    pub fn from_short_name(name: &str) -> Option<Card> {
+        let mut chars = name.chars();
+        let rank = match chars.next()? {
+            'T' => Rank::Rank10,
+            'J' => Rank::Jack,
+            'Q' => Rank::Queen,
+            'K' => Rank::King,
+            'A' => Rank::Ace,
+            c @ '2'..='9' => Rank::values()[c as usize - '2' as usize],
+            _ => return None,
+        };
+        let suit = match chars.next()? {
+            'c' => Suit::Clubs,
+            'd' => Suit::Diamonds,
+            'h' => Suit::Hearts,
+            's' => Suit::Spades,
+            _ => return None,
+        };
+        match chars.next() {
+            None => Some(Card { rank, suit }),
+            Some(_) => None,
+        }
+    }
+}
 
 /// A poker hand, ranked from lowest to highest. Assuming there are no wild cards allowed,
 /// and so no five of a kind.
@@ -1,5 +1,5 @@
 /// Functions for comparing and ranking collections of cards.
-use std::cmp::Ordering;
+use std::cmp::{Ordering, Reverse};
 
 use crate::poker::{
     card::{BestHand, Card, Hand},
@@ -10,7 +10,7 @@ use crate::poker::{
 /// Get the best hand from a collection of cards.
 pub fn best_hand(cards: &[Card]) -> BestHand {
     let mut cs = cards.to_owned();
-    cs.sort_by(|a, b| b.rank.cmp(&a.rank));
+    cs.sort_by_key(|c| Reverse(c.rank));
     let longest_seq = sequence::longest_sequence(&cs);
     let ranks = sequence::group_by_rank(&cs);
     let suits = sequence::group_by_suit(&cs);
@@ -103,20 +103,35 @@ pub fn compare_hands(hand_a: PlayerHand, hand_b: PlayerHand) -> Winner {
                     cards: c_b,
                 },
             ]),
-            // No draw for two 4oK
+            // For two 4oK the highest rank wins. They can only be the same rank when
+            // they are on the board, in which case the highest other card (the kicker)
+            // wins, and if the kickers are the same it's a draw.
             (Hand::FourOfAKind(r1), Hand::FourOfAKind(r2)) => {
-                if r1 > r2 {
-                    Winner::SoleWinner(PlayerHand {
+                let kicker =
+                    |cards: &[Card], r| cards.iter().map(|c| c.rank).filter(|k| *k != r).max();
+                match r1.cmp(&r2).then(kicker(&c_a, r1).cmp(&kicker(&c_b, r2))) {
+                    Ordering::Greater => Winner::SoleWinner(PlayerHand {
                         name: name_a,
                         hand: h_a,
                         cards: c_a,
-                    })
-                } else {
-                    Winner::SoleWinner(PlayerHand {
+                    }),
+                    Ordering::Less => Winner::SoleWinner(PlayerHand {
                         name: name_b,
                         hand: h_b,
                         cards: c_b,
-                    })
+                    }),
+                    Ordering::Equal => Winner::Draw(vec![
+                        PlayerHand {
+                            name: name_a,
+                            hand: h_a,
+                            cards: c_a,
+                        },
+                        PlayerHand {
+                            name: name_b,
+                            hand: h_b,
+                            cards: c_b,
+                        },
+                    ]),
                 }
             }
             // For two full houses the highest 3oK wins, or if they are
@@ -338,6 +353,69 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_compare_four_of_a_kind() {
+        let card = |rank, suit| Card { rank, suit };
+        let board = [
+            card(Rank::Rank5, Suit::Spades),
+            card(Rank::Rank5, Suit::Diamonds),
+            card(Rank::Rank5, Suit::Clubs),
+            card(Rank::Rank5, Suit::Hearts),
+            card(Rank::Rank3, Suit::Spades),
+        ];
+        let player = |name: &str, hole: [Card; 2]| {
+            let mut cards = board.to_vec();
+            cards.extend(hole);
+            PlayerHand {
+                name: name.to_string(),
+                hand: best_hand(&cards),
+                cards,
+            }
+        };
+        let ten = player(
+            "ten",
+            [
+                card(Rank::Rank2, Suit::Diamonds),
+                card(Rank::Rank10, Suit::Clubs),
+            ],
+        );
+        let nine = player(
+            "nine",
+            [
+                card(Rank::Rank4, Suit::Clubs),
+                card(Rank::Rank9, Suit::Diamonds),
+            ],
+        );
+        for (a, b) in [(ten.clone(), nine.clone()), (nine, ten)] {
+            let w = compare_hands(a, b);
+            assert!(
+                matches!(&w, Winner::SoleWinner(ph) if ph.name == "ten"),
+                "Expected the higher kicker to win, was {:?}",
+                w
+            );
+        }
+        let other_ten = player(
+            "other ten",
+            [
+                card(Rank::Rank10, Suit::Hearts),
+                card(Rank::Rank2, Suit::Clubs),
+            ],
+        );
+        let ten = player(
+            "ten",
+            [
+                card(Rank::Rank2, Suit::Diamonds),
+                card(Rank::Rank10, Suit::Clubs),
+            ],
+        );
+        let w = compare_hands(ten, other_ten);
+        assert!(
+            matches!(&w, Winner::Draw(hands) if hands.len() == 2),
+            "Expected the same kickers to draw, was {:?}",
+            w
+        );
+    }
+
     #[test]
     fn test_best_hand_high_card() {
         let h1 = Vec::from(HIGH_CARD_ACE);
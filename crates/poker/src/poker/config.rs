@@ -0,0 +1,621 @@
+/// Configuration for games, with a builder that validates the settings.
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+    time::Duration,
+};
+
+// minimum and maximum number of players in a game.
+pub const MIN_PLAYERS: u8 = 2;
+pub const MAX_PLAYERS: u8 = 10;
+
+/// The betting structure of a game, which limits the size of bets and raises.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub enum BettingStructure {
+    /// Players can bet any amount up to their whole bank roll.
+    #[default]
+    NoLimit,
+    /// Players can raise by at most the size of the pot after they have called.
+    PotLimit,
+    /// Bets and raises are of a fixed size: the small bet before the flop and on the
+    /// flop, and the big bet on the turn and river. There can be at most `cap` bets and
+    /// raises in each round of betting, counting the big blind before the flop.
+    FixedLimit {
+        small_bet: usize,
+        big_bet: usize,
+        cap: u8,
+    },
+}
+
+/// How long players can rebuy for after the start of a game.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum RebuyPeriod {
+    /// Players who go broke in the first n rounds can rebuy.
+    Hands(usize),
+    /// Players can rebuy until the end of the nth level of the blind schedule, counting
+    /// from one.
+    Level(usize),
+    /// Players can always rebuy.
+    Always,
+}
+
+/// Rules for players buying more chips when they go broke. A player who goes broke
+/// while rebuys are allowed is asked whether they want to rebuy, and is removed from
+/// the game if they don't.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RebuyRules {
+    /// The number of chips received for each rebuy.
+    pub amount: usize,
+    /// The number of times each player can rebuy, or None for no limit.
+    pub max_rebuys: Option<usize>,
+    pub period: RebuyPeriod,
+    /// The number of chips in the add-on, which every player can buy once when the
+    /// rebuy period ends, if there is one.
+    pub add_on: Option<usize>,
+}
+
+/// The house's share of each pot in a cash game.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct RakeRules {
+    /// The percentage of each pot taken, rounded down.
+    pub percent: usize,
+    /// The most taken from a hand, if there is a limit.
+    pub cap: Option<usize>,
+    /// Take nothing from hands that end before the flop.
+    pub no_flop_no_drop: bool,
+}
+/// Implementation for RakeRules.
+impl RakeRules {
+    /// The rake taken from a hand with the given total pot.
+    pub fn rake(&self, pot: usize, flop_dealt: bool) -> usize {
+        if self.no_flop_no_drop && !flop_dealt {
+            return 0;
+        }
+        let rake = pot * self.percent / 100;
+        self.cap.map_or(rake, |cap| rake.min(cap))
+    }
+}
+
+/// The player who can straddle, i.e. post a blind of twice the big blind before the
+/// cards are dealt. The straddler acts last before the flop, and the player to their
+/// left acts first.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Straddle {
+    /// The player to the left of the big blind.
+    UnderTheGun,
+    /// The dealer.
+    Button,
+}
+
+/// Rules for bomb pots, hands where every player pays an ante instead of the blinds and
+/// the betting starts on the flop.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct BombPotRules {
+    /// The ante paid by every player.
+    pub ante: usize,
+    /// How often there is a bomb pot: every nth hand is one.
+    pub every: usize,
+}
+
+/// When a cash game session ends, if it isn't stopped before then. See
+/// `Game::play_session`.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct SessionLimits {
+    /// The number of hands to play.
+    pub hands: Option<usize>,
+    /// How long to play for. The hand being played when the time runs out is finished.
+    pub duration: Option<Duration>,
+}
+
+/// Errors found when validating a game configuration.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ConfigError {
+    TableSize(u8),
+    BigBlind,
+    SmallBlind {
+        small_blind: usize,
+        big_blind: usize,
+    },
+    BuyIn {
+        buy_in: usize,
+        big_blind: usize,
+    },
+    StartingStack(String),
+    TooManyPlayers {
+        players: usize,
+        max_players: u8,
+    },
+    /// A rebuy or add-on must be for some chips.
+    RebuyAmount,
+    /// The rake can't be more than the whole pot.
+    Rake(usize),
+    /// A bomb pot must have an ante and come round every so many hands.
+    BombPot,
+    /// Fixed-limit bets must be for some chips, and allow at least one bet.
+    FixedLimit,
+}
+/// Implementation of Display trait for ConfigError.
+impl Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ConfigError::TableSize(n) => write!(
+                f,
+                "Table size must be between {} and {}, was {}",
+                MIN_PLAYERS, MAX_PLAYERS, n
+            ),
+            ConfigError::BigBlind => write!(f, "The big blind must be greater than zero"),
+            ConfigError::SmallBlind {
+                small_blind,
+                big_blind,
+            } => write!(
+                f,
+                "The small blind ({}) must be greater than zero and no more than the big blind ({})",
+                small_blind, big_blind
+            ),
+            ConfigError::BuyIn { buy_in, big_blind } => write!(
+                f,
+                "The buy in ({}) must be at least the big blind ({})",
+                buy_in, big_blind
+            ),
+            ConfigError::StartingStack(name) => {
+                write!(
+                    f,
+                    "The starting stack for {} must be greater than zero",
+                    name
+                )
+            }
+            ConfigError::TooManyPlayers {
+                players,
+                max_players,
+            } => write!(
+                f,
+                "Can't seat {} players at a table for {}",
+                players, max_players
+            ),
+            ConfigError::RebuyAmount => {
+                write!(f, "Rebuys and add-ons must be for more than zero chips")
+            }
+            ConfigError::Rake(percent) => {
+                write!(f, "The rake must be at most 100 percent, was {}", percent)
+            }
+            ConfigError::FixedLimit => write!(
+                f,
+                "Fixed-limit bets must be greater than zero and allow at least one bet"
+            ),
+            ConfigError::BombPot => write!(
+                f,
+                "Bomb pots must have an ante and be played every one or more hands"
+            ),
+        }
+    }
+}
+/// Implementation of Error trait for ConfigError.
+impl std::error::Error for ConfigError {}
+
+/// Struct for the configuration of a game. Use `GameConfig::builder` to create one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct GameConfig {
+    /// The number of seats at the table.
+    pub max_players: u8,
+    /// The number of chips a player receives when they join the game.
+    pub buy_in: usize,
+    pub small_blind: usize,
+    pub big_blind: usize,
+    /// An amount paid by every player at the beginning of each round.
+    pub ante: usize,
+    /// Starting stacks for named players, which override the buy in.
+    pub starting_stacks: HashMap<String, usize>,
+    pub betting_structure: BettingStructure,
+    /// Seed for shuffling the deck. Games with the same seed, players and bets deal
+    /// the same cards.
+    pub seed: Option<u64>,
+    /// The time a player has to act. This is enforced by actors that wait for input,
+    /// such as remote players.
+    pub time_limit: Option<Duration>,
+    /// Shuffle each hand's deck from seeds that are committed to before the deal and
+    /// revealed afterwards, so that players can verify it. See `fair`.
+    #[serde(default)]
+    pub provably_fair: bool,
+    /// Whether players who sit out when they would have paid a blind have to post the
+    /// big blind when they sit in again.
+    #[serde(default)]
+    pub post_missed_blinds: bool,
+    /// Whether players can buy more chips when they go broke, and the add-on.
+    #[serde(default)]
+    pub rebuys: Option<RebuyRules>,
+    /// When a cash game session played with `Game::play_session` ends.
+    #[serde(default)]
+    pub session: SessionLimits,
+    /// The house's share of each pot.
+    #[serde(default)]
+    pub rake: Option<RakeRules>,
+    /// Show the community cards that would have come when a hand ends before the river
+    /// because everyone else folded.
+    #[serde(default)]
+    pub rabbit_hunt: bool,
+    /// Offer to deal the rest of the board twice when the players still in the round
+    /// are all in, if they all agree.
+    #[serde(default)]
+    pub run_it_twice: bool,
+    /// Who can straddle, if anyone.
+    #[serde(default)]
+    pub straddle: Option<Straddle>,
+    /// How often bomb pots are played, if they are.
+    #[serde(default)]
+    pub bomb_pot: Option<BombPotRules>,
+}
+
+/// Implementation for GameConfig.
+impl GameConfig {
+    /// Start building a configuration.
+    pub fn builder() -> GameConfigBuilder {
+        GameConfigBuilder::default()
+    }
+
+    /// The number of chips the named player receives when they join the game.
+    pub fn starting_stack(&self, name: &str) -> usize {
+        *self.starting_stacks.get(name).unwrap_or(&self.buy_in)
+    }
+
+    /// Check that the configuration describes a game that can be played.
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if !(MIN_PLAYERS..=MAX_PLAYERS).contains(&self.max_players) {
+            return Err(ConfigError::TableSize(self.max_players));
+        }
+        if self.big_blind == 0 {
+            return Err(ConfigError::BigBlind);
+        }
+        if self.small_blind == 0 || self.small_blind > self.big_blind {
+            return Err(ConfigError::SmallBlind {
+                small_blind: self.small_blind,
+                big_blind: self.big_blind,
+            });
+        }
+        if self.buy_in < self.big_blind {
+            return Err(ConfigError::BuyIn {
+                buy_in: self.buy_in,
+                big_blind: self.big_blind,
+            });
+        }
+        if let Some((name, _)) = self.starting_stacks.iter().find(|(_, stack)| **stack == 0) {
+            return Err(ConfigError::StartingStack(name.clone()));
+        }
+        if let Some(rules) = self.rebuys
+            && (rules.amount == 0 || rules.add_on == Some(0))
+        {
+            return Err(ConfigError::RebuyAmount);
+        }
+        if let Some(rake) = self.rake
+            && rake.percent > 100
+        {
+            return Err(ConfigError::Rake(rake.percent));
+        }
+        if let BettingStructure::FixedLimit {
+            small_bet,
+            big_bet,
+            cap,
+        } = self.betting_structure
+            && (small_bet == 0 || big_bet == 0 || cap == 0)
+        {
+            return Err(ConfigError::FixedLimit);
+        }
+        if let Some(rules) = self.bomb_pot
+            && (rules.ante == 0 || rules.every == 0)
+        {
+            return Err(ConfigError::BombPot);
+        }
+        Ok(())
+    }
+}
+
+/// Builder for GameConfig. Unless they are set, the buy in is 100 times the big blind
+/// and the small blind is half of the big blind.
+#[derive(Debug, Clone)]
+pub struct GameConfigBuilder {
+    max_players: u8,
+    buy_in: Option<usize>,
+    small_blind: Option<usize>,
+    big_blind: usize,
+    ante: usize,
+    starting_stacks: HashMap<String, usize>,
+    betting_structure: BettingStructure,
+    seed: Option<u64>,
+    time_limit: Option<Duration>,
+    provably_fair: bool,
+    post_missed_blinds: bool,
+    rebuys: Option<RebuyRules>,
+    session: SessionLimits,
+    rake: Option<RakeRules>,
+    rabbit_hunt: bool,
+    run_it_twice: bool,
+    straddle: Option<Straddle>,
+    bomb_pot: Option<BombPotRules>,
+}
+/// Implementation of Default trait for GameConfigBuilder.
+impl Default for GameConfigBuilder {
+    fn default() -> Self {
+        GameConfigBuilder {
+            max_players: 6,
+            buy_in: None,
+            small_blind: None,
+            big_blind: 100,
+            ante: 0,
+            starting_stacks: HashMap::new(),
+            betting_structure: BettingStructure::default(),
+            seed: None,
+            time_limit: None,
+            provably_fair: false,
+            post_missed_blinds: false,
+            rebuys: None,
+            session: SessionLimits::default(),
+            rake: None,
+            rabbit_hunt: false,
+            run_it_twice: false,
+            straddle: None,
+            bomb_pot: None,
+        }
+    }
+}
+/// Implementation for GameConfigBuilder.
+impl GameConfigBuilder {
+    pub fn max_players(mut self, max_players: u8) -> Self {
+        self.max_players = max_players;
+        self
+    }
+
+    pub fn buy_in(mut self, buy_in: usize) -> Self {
+        self.buy_in = Some(buy_in);
+        self
+    }
+
+    pub fn small_blind(mut self, small_blind: usize) -> Self {
+        self.small_blind = Some(small_blind);
+        self
+    }
+
+    pub fn big_blind(mut self, big_blind: usize) -> Self {
+        self.big_blind = big_blind;
+        self
+    }
+
+    pub fn ante(mut self, ante: usize) -> Self {
+        self.ante = ante;
+        self
+    }
+
+    /// Give the named player a different number of chips to the buy in.
+    pub fn starting_stack(mut self, name: &str, stack: usize) -> Self {
+        self.starting_stacks.insert(name.to_string(), stack);
+        self
+    }
+
+    pub fn betting_structure(mut self, betting_structure: BettingStructure) -> Self {
+        self.betting_structure = betting_structure;
+        self
+    }
+
+    pub fn seed(mut self, seed: u64) -> Self {
+        self.seed = Some(seed);
+        self
+    }
+
+    pub fn time_limit(mut self, time_limit: Duration) -> Self {
+        self.time_limit = Some(time_limit);
+        self
+    }
+
+    pub fn provably_fair(mut self, provably_fair: bool) -> Self {
+        self.provably_fair = provably_fair;
+        self
+    }
+
+    pub fn post_missed_blinds(mut self, post_missed_blinds: bool) -> Self {
+        self.post_missed_blinds = post_missed_blinds;
+        self
+    }
+
+    pub fn rebuys(mut self, rules: RebuyRules) -> Self {
+        self.rebuys = Some(rules);
+        self
+    }
+
+    pub fn session(mut self, limits: SessionLimits) -> Self {
+        self.session = limits;
+        self
+    }
+
+    pub fn rake(mut self, rules: RakeRules) -> Self {
+        self.rake = Some(rules);
+        self
+    }
+
+    pub fn rabbit_hunt(mut self, rabbit_hunt: bool) -> Self {
+        self.rabbit_hunt = rabbit_hunt;
+        self
+    }
+
+    pub fn run_it_twice(mut self, run_it_twice: bool) -> Self {
+        self.run_it_twice = run_it_twice;
+        self
+    }
+
+    pub fn straddle(mut self, straddle: Straddle) -> Self {
+        self.straddle = Some(straddle);
+        self
+    }
+
+    pub fn bomb_pot(mut self, rules: BombPotRules) -> Self {
+        self.bomb_pot = Some(rules);
+        self
+    }
+
+    /// Validate the settings and build the configuration.
+    pub fn build(self) -> Result<GameConfig, ConfigError> {
+        let config = GameConfig {
+            max_players: self.max_players,
+            buy_in: self.buy_in.unwrap_or(100 * self.big_blind),
+            small_blind: self.small_blind.unwrap_or(self.big_blind / 2),
+            big_blind: self.big_blind,
+            ante: self.ante,
+            starting_stacks: self.starting_stacks,
+            betting_structure: self.betting_structure,
+            seed: self.seed,
+            time_limit: self.time_limit,
+            provably_fair: self.provably_fair,
+            post_missed_blinds: self.post_missed_blinds,
+            rebuys: self.rebuys,
+            session: self.session,
+            rake: self.rake,
+            rabbit_hunt: self.rabbit_hunt,
+            run_it_twice: self.run_it_twice,
+            straddle: self.straddle,
+            bomb_pot: self.bomb_pot,
+        };
+        config.validate()?;
+        Ok(config)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_defaults() {
+        let config = GameConfig::builder().big_blind(20).build().unwrap();
+        assert!(
+            config.buy_in == 2000,
+            "Expected buy in of 100 x big blind, was {}",
+            config.buy_in
+        );
+        assert!(
+            config.small_blind == 10,
+            "Expected small blind of half the big blind, was {}",
+            config.small_blind
+        );
+    }
+
+    #[test]
+    fn test_validation() {
+        let errors = vec![
+            (
+                GameConfig::builder().max_players(1).build(),
+                ConfigError::TableSize(1),
+            ),
+            (
+                GameConfig::builder().max_players(11).build(),
+                ConfigError::TableSize(11),
+            ),
+            (
+                GameConfig::builder().big_blind(0).buy_in(100).build(),
+                ConfigError::BigBlind,
+            ),
+            (
+                GameConfig::builder().big_blind(1).build(),
+                ConfigError::SmallBlind {
+                    small_blind: 0,
+                    big_blind: 1,
+                },
+            ),
+            (
+                GameConfig::builder().big_blind(20).buy_in(10).build(),
+                ConfigError::BuyIn {
+                    buy_in: 10,
+                    big_blind: 20,
+                },
+            ),
+            (
+                GameConfig::builder().starting_stack("Bob", 0).build(),
+                ConfigError::StartingStack("Bob".to_string()),
+            ),
+            (
+                GameConfig::builder()
+                    .rebuys(RebuyRules {
+                        amount: 1000,
+                        max_rebuys: None,
+                        period: RebuyPeriod::Always,
+                        add_on: Some(0),
+                    })
+                    .build(),
+                ConfigError::RebuyAmount,
+            ),
+            (
+                GameConfig::builder()
+                    .rake(RakeRules {
+                        percent: 101,
+                        cap: None,
+                        no_flop_no_drop: false,
+                    })
+                    .build(),
+                ConfigError::Rake(101),
+            ),
+            (
+                GameConfig::builder()
+                    .bomb_pot(BombPotRules { ante: 10, every: 0 })
+                    .build(),
+                ConfigError::BombPot,
+            ),
+            (
+                GameConfig::builder()
+                    .betting_structure(BettingStructure::FixedLimit {
+                        small_bet: 20,
+                        big_bet: 40,
+                        cap: 0,
+                    })
+                    .build(),
+                ConfigError::FixedLimit,
+            ),
+        ];
+        for (result, expected) in errors {
+            assert!(
+                result.as_ref().err() == Some(&expected),
+                "Expected {:?}, was {:?}",
+                expected,
+                result
+            );
+        }
+        assert!(
+            GameConfig::builder().max_players(10).build().is_ok(),
+            "Expected a table of 10 to be allowed"
+        );
+    }
+
+    #[test]
+    fn test_starting_stacks() {
+        let config = GameConfig::builder()
+            .big_blind(20)
+            .starting_stack("Bob", 500)
+            .build()
+            .unwrap();
+        assert!(
+            config.starting_stack("Bob") == 500,
+            "Expected Bob to start with 500, was {}",
+            config.starting_stack("Bob")
+        );
+        assert!(
+            config.starting_stack("Alice") == 2000,
+            "Expected Alice to start with the buy in, was {}",
+            config.starting_stack("Alice")
+        );
+    }
+
+    #[test]
+    fn test_rake() {
+        let rules = RakeRules {
+            percent: 5,
+            cap: Some(30),
+            no_flop_no_drop: true,
+        };
+        let rakes = [
+            rules.rake(199, true),
+            rules.rake(1000, true),
+            rules.rake(200, false),
+        ];
+        assert!(
+            rakes == [9, 30, 0],
+            "Expected 5 percent capped at 30 and nothing without a flop, was {:?}",
+            rakes
+        );
+    }
+}
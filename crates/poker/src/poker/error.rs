@@ -0,0 +1,56 @@
+/// Errors raised by the game engine.
+use crate::poker::game::Bet;
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+
+/// Enum for the ways in which a game can fail.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum GameError {
+    /// A player tried to join a game with no empty seats.
+    TableFull,
+    /// There weren't enough cards left in the deck to deal.
+    NotEnoughCards { needed: usize, remaining: usize },
+    /// A player made a bet that isn't allowed, e.g. checking when there is a bet to call.
+    IllegalAction {
+        player: String,
+        bet: Bet,
+        reason: String,
+    },
+    /// A player's actor failed to place a bet, e.g. because a remote client disconnected.
+    ActorFailed { player: String },
+    /// The game needs players to carry on but has none, or too few.
+    NotEnoughPlayers,
+    /// The game or round ended without a winner.
+    NoWinner,
+    /// There is no player with the given name at the table.
+    UnknownPlayer(String),
+    /// The action can only be taken between rounds.
+    RoundInProgress,
+}
+/// Implementation of Display trait for GameError.
+impl Display for GameError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            GameError::TableFull => write!(f, "Cannot add more players."),
+            GameError::NotEnoughCards { needed, remaining } => write!(
+                f,
+                "Needed {} cards but there are only {} left in the deck",
+                needed, remaining
+            ),
+            GameError::IllegalAction {
+                player,
+                bet,
+                reason,
+            } => write!(f, "Illegal bet {} by {}: {}", bet, player, reason),
+            GameError::ActorFailed { player } => {
+                write!(f, "No bet received from player {}", player)
+            }
+            GameError::NotEnoughPlayers => write!(f, "Not enough players left in the game"),
+            GameError::NoWinner => write!(f, "No winner could be determined"),
+            GameError::UnknownPlayer(name) => write!(f, "{} isn't at the table", name),
+            GameError::RoundInProgress => write!(f, "Can't do that while a round is being played"),
+        }
+    }
+}
+/// Implementation of Error trait for GameError.
+impl std::error::Error for GameError {}
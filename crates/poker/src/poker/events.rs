@@ -0,0 +1,276 @@
+/// Events describing every change to the state of a game, for subscribers such as
+/// loggers, spectators, recorders and statistics.
+use crate::poker::{
+    card::Card,
+    config::BettingStructure,
+    error::GameError,
+    fair::ShuffleSeeds,
+    game::{Bet, Stage},
+    player::{PlayerHand, Winner},
+};
+use serde::{Deserialize, Serialize};
+use std::{cell::RefCell, fmt::Debug, rc::Rc};
+use uuid::Uuid;
+
+/// Enum for who is allowed to see an event.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum Audience {
+    /// Everyone, including spectators.
+    Public,
+    /// Only the named player, e.g. their hole cards.
+    Player(String),
+    /// Nobody at the table, e.g. burned cards.
+    House,
+}
+/// Implementation for Audience.
+impl Audience {
+    /// Whether the named player is allowed to see the event.
+    pub fn includes(&self, player: &str) -> bool {
+        match self {
+            Audience::Public => true,
+            Audience::Player(name) => name == player,
+            Audience::House => false,
+        }
+    }
+}
+
+/// Enum for the events in a game, in the order they happen.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub enum GameEvent {
+    PlayerJoined {
+        player: String,
+        bank_roll: usize,
+    },
+    /// A player left the table with their chips, at the end of a round.
+    PlayerLeft {
+        player: String,
+        bank_roll: usize,
+    },
+    /// A player is sitting out from the next round.
+    PlayerSatOut {
+        player: String,
+    },
+    /// A player who was sitting out will be dealt in again from the next round.
+    PlayerSatIn {
+        player: String,
+    },
+    /// A player who went broke bought more chips, at the end of a round.
+    Rebuy {
+        player: String,
+        amount: usize,
+    },
+    /// A player bought the add-on, before a round.
+    AddOn {
+        player: String,
+        amount: usize,
+    },
+    BlindLevel {
+        level: usize,
+        small_blind: usize,
+        big_blind: usize,
+        ante: usize,
+    },
+    /// A round is starting at a time given in seconds since the Unix epoch. The players
+    /// are listed in seat order, starting to the left of the dealer, with their bank rolls.
+    RoundStarted {
+        game: Uuid,
+        round: usize,
+        started: u64,
+        dealer: String,
+        players: Vec<(String, usize)>,
+        small_blind: usize,
+        big_blind: usize,
+        ante: usize,
+        betting_structure: BettingStructure,
+    },
+    /// The commitment to the server seed for a hand, made before the deal.
+    ShuffleCommitted {
+        hand: usize,
+        commitment: String,
+    },
+    /// The seeds for a hand, revealed after it.
+    ShuffleRevealed(ShuffleSeeds),
+    /// The round is a bomb pot, in which every player pays the ante and there are no
+    /// blinds or betting before the flop.
+    BombPot {
+        ante: usize,
+    },
+    AntePosted {
+        player: String,
+        amount: usize,
+    },
+    SmallBlindPosted {
+        player: String,
+        amount: usize,
+    },
+    BigBlindPosted {
+        player: String,
+        amount: usize,
+    },
+    /// A player straddled, posting a blind of twice the big blind.
+    StraddlePosted {
+        player: String,
+        amount: usize,
+    },
+    HoleCardsDealt {
+        player: String,
+        cards: (Card, Card),
+    },
+    CardBurned(Card),
+    CommunityCardsDealt {
+        stage: Stage,
+        cards: Vec<Card>,
+    },
+    /// A bet, with the number of chips it put into the pot.
+    BetPlaced {
+        player: String,
+        bet: Bet,
+        amount: usize,
+        pot: usize,
+    },
+    /// The hole cards of the players still in the round, shown when they can't bet any
+    /// more and the rest of the board is dealt.
+    HandsRevealed(Vec<(String, (Card, Card))>),
+    /// The second board, when the players agreed to run it twice.
+    SecondBoardDealt(Vec<Card>),
+    /// A player mucked their hand at the showdown, giving up any claim to the pots.
+    HandMucked {
+        player: String,
+    },
+    /// The hands shown at the showdown, in the order they were shown.
+    Showdown(Vec<PlayerHand>),
+    /// The house's share of the pots, taken before they are awarded.
+    RakeTaken {
+        amount: usize,
+    },
+    /// Chips won from a pot, which is numbered from zero for the main pot.
+    PotAwarded {
+        pot: usize,
+        player: String,
+        amount: usize,
+    },
+    RoundWinner(Winner),
+    /// The winner on the second board, when the players ran it twice.
+    SecondBoardWinner(Winner),
+    /// The part of a bet that nobody called, returned to the player who made it when
+    /// everyone else folded.
+    UncalledBetReturned {
+        player: String,
+        amount: usize,
+    },
+    /// Everyone else folded, so the player won the pot without showing their cards.
+    RoundWonUncontested {
+        player: String,
+        pot: usize,
+    },
+    /// The community cards that would have come if the hand hadn't ended early.
+    RabbitHunt(Vec<Card>),
+    PlayerEliminated {
+        player: String,
+    },
+    /// A round was abandoned and the bets returned to the players.
+    RoundAbandoned(GameError),
+    /// The named player won the game.
+    GameWinner(String),
+}
+/// Implementation for GameEvent.
+impl GameEvent {
+    /// Who is allowed to see the event.
+    pub fn audience(&self) -> Audience {
+        match self {
+            GameEvent::HoleCardsDealt { player, .. } => Audience::Player(player.clone()),
+            GameEvent::CardBurned(_) => Audience::House,
+            _ => Audience::Public,
+        }
+    }
+}
+
+/// The Subscriber trait is implemented by anything that observes a game. Subscribers
+/// receive every event, and should check `GameEvent::audience` before passing on
+/// private information.
+pub trait Subscriber: Debug {
+    /// Receive an event.
+    fn notify(&self, event: &GameEvent);
+}
+
+/// A subscriber that keeps the events it receives. Clones share the same list of
+/// events, so a clone can be given to the game and the original used to read them.
+#[derive(Debug, Clone, Default)]
+pub struct EventLog {
+    events: Rc<RefCell<Vec<GameEvent>>>,
+}
+/// Implementation for EventLog.
+impl EventLog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The events received so far.
+    pub fn events(&self) -> Vec<GameEvent> {
+        self.events.borrow().clone()
+    }
+
+    /// Remove and return the events received so far.
+    pub fn take(&self) -> Vec<GameEvent> {
+        self.events.take()
+    }
+}
+/// Implementation of Subscriber for EventLog.
+impl Subscriber for EventLog {
+    fn notify(&self, event: &GameEvent) {
+        self.events.borrow_mut().push(event.clone());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::card::{Rank, Suit};
+
+    #[test]
+    fn test_audience() {
+        let card = Card {
+            rank: Rank::Ace,
+            suit: Suit::Spades,
+        };
+        let hole = GameEvent::HoleCardsDealt {
+            player: "Bob".to_string(),
+            cards: (card, card),
+        };
+        assert!(
+            hole.audience().includes("Bob") && !hole.audience().includes("Alice"),
+            "Expected hole cards to be seen only by their player"
+        );
+        assert!(
+            !GameEvent::CardBurned(card).audience().includes("Bob"),
+            "Expected burned cards to be hidden from the players"
+        );
+        let bet = GameEvent::BetPlaced {
+            player: "Bob".to_string(),
+            bet: Bet::Call,
+            amount: 10,
+            pot: 10,
+        };
+        assert!(
+            bet.audience() == Audience::Public,
+            "Expected bets to be public"
+        );
+    }
+
+    #[test]
+    fn test_event_log() {
+        let log = EventLog::new();
+        let subscriber: Box<dyn Subscriber> = Box::new(log.clone());
+        subscriber.notify(&GameEvent::PlayerEliminated {
+            player: "Bob".to_string(),
+        });
+        assert!(
+            log.events().len() == 1,
+            "Expected the clone to share events"
+        );
+        assert!(
+            log.take().len() == 1 && log.events().is_empty(),
+            "Expected take to empty the log"
+        );
+    }
+}
@@ -0,0 +1,255 @@
+/// Provably fair shuffling. The deck for each hand is derived from a secret server seed,
+/// any seeds supplied by the players' clients and the hand number. The game announces a
+/// commitment to the server seed (its SHA-256 hash) before the deal and reveals the seed
+/// after the hand, so that players can check that the deck wasn't chosen to suit
+/// anyone, by recomputing it with `verify`.
+///
+/// The deck is shuffled with a Fisher-Yates shuffle of the cards in the order given by
+/// `card::new_deck`, taking random numbers from a stream of SHA-256 hashes so that it
+/// can be reproduced without this library. See `shuffle` for the details.
+use crate::poker::card::{self, Card};
+use rand::Rng;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::fmt::{self, Display};
+
+/// Errors found when verifying a shuffle.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum FairnessError {
+    /// The revealed server seed doesn't match the commitment made before the deal.
+    Commitment { expected: String, actual: String },
+    /// The cards dealt weren't the ones in the deck derived from the seeds.
+    Deck,
+}
+/// Implementation of Display trait for FairnessError.
+impl Display for FairnessError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            FairnessError::Commitment { expected, actual } => write!(
+                f,
+                "The server seed hashes to {}, but the commitment was {}",
+                actual, expected
+            ),
+            FairnessError::Deck => write!(f, "The cards dealt don't match the seeds"),
+        }
+    }
+}
+/// Implementation of Error trait for FairnessError.
+impl std::error::Error for FairnessError {}
+
+/// The seeds that a hand's deck is derived from, which are revealed after the hand.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ShuffleSeeds {
+    /// The number of the hand, starting from one.
+    pub hand: usize,
+    pub server_seed: String,
+    /// Seeds supplied by the players' clients, in seat order.
+    pub client_seeds: Vec<String>,
+}
+
+/// Write bytes as lower case hex.
+fn to_hex(bytes: &[u8]) -> String {
+    bytes.iter().map(|b| format!("{:02x}", b)).collect()
+}
+
+/// Make a new random server seed of 32 bytes, written as hex.
+pub fn new_server_seed(rng: &mut impl Rng) -> String {
+    let mut bytes = [0u8; 32];
+    rng.fill(&mut bytes);
+    to_hex(&bytes)
+}
+
+/// The commitment to a server seed: its SHA-256 hash, written as hex.
+pub fn commitment(server_seed: &str) -> String {
+    to_hex(&Sha256::digest(server_seed.as_bytes()))
+}
+
+/// A stream of random numbers taken from SHA-256 hashes of a key followed by a counter.
+struct HashStream {
+    key: [u8; 32],
+    counter: u64,
+    block: Vec<u8>,
+}
+/// Implementation for HashStream.
+impl HashStream {
+    fn new(key: [u8; 32]) -> Self {
+        HashStream {
+            key,
+            counter: 0,
+            block: Vec::new(),
+        }
+    }
+
+    /// The next four bytes of the stream, as a big endian number.
+    fn next_u32(&mut self) -> u32 {
+        if self.block.is_empty() {
+            let mut hasher = Sha256::new();
+            hasher.update(self.key);
+            hasher.update(self.counter.to_be_bytes());
+            self.counter += 1;
+            self.block = hasher.finalize().to_vec();
+        }
+        let bytes: Vec<u8> = self.block.drain(..4).collect();
+        u32::from_be_bytes([bytes[0], bytes[1], bytes[2], bytes[3]])
+    }
+
+    /// A number from zero up to (but not including) `n`, rejecting numbers that would
+    /// make some results more likely than others.
+    fn below(&mut self, n: u32) -> u32 {
+        let zone = u32::MAX - u32::MAX % n;
+        loop {
+            let x = self.next_u32();
+            if x < zone {
+                return x % n;
+            }
+        }
+    }
+}
+
+/// Derive the deck for a hand from its seeds. The key is the SHA-256 hash of the server
+/// seed, each client seed and the hand number (in decimal), each followed by a newline.
+/// Random numbers are read four bytes at a time, big endian, from the hashes of the key
+/// followed by a counter (a big endian u64 starting at zero). The cards in the order
+/// given by `card::new_deck` are then shuffled by swapping the card at each position
+/// `i` from 51 down to 1 with the one at a position below `i + 1`, skipping random
+/// numbers of `u32::MAX - u32::MAX % (i + 1)` or more.
+pub fn shuffle(seeds: &ShuffleSeeds) -> Vec<Card> {
+    let mut hasher = Sha256::new();
+    for seed in std::iter::once(&seeds.server_seed).chain(&seeds.client_seeds) {
+        hasher.update(seed.as_bytes());
+        hasher.update(b"\n");
+    }
+    hasher.update(format!("{}\n", seeds.hand).as_bytes());
+    let mut stream = HashStream::new(hasher.finalize().into());
+    let mut deck = card::new_deck();
+    for i in (1..deck.len()).rev() {
+        let j = stream.below(i as u32 + 1) as usize;
+        deck.swap(i, j);
+    }
+    deck
+}
+
+/// Check the seeds revealed after a hand against the commitment made before the deal,
+/// returning the deck derived from them. Players can check their hole cards and the
+/// community cards against the deck with `verify_cards`.
+pub fn verify(commitment_hash: &str, seeds: &ShuffleSeeds) -> Result<Vec<Card>, FairnessError> {
+    let actual = commitment(&seeds.server_seed);
+    if actual != commitment_hash.to_lowercase() {
+        return Err(FairnessError::Commitment {
+            expected: commitment_hash.to_string(),
+            actual,
+        });
+    }
+    Ok(shuffle(seeds))
+}
+
+/// Check the cards seen in a hand against the deck derived from its seeds: a player's
+/// hole cards, given their seat counting from the left of the dealer in a game of
+/// `num_players`, and the community cards dealt. Hole cards are dealt from the front of
+/// the deck, the community cards follow them and burned cards are taken from the back.
+pub fn verify_cards(
+    deck: &[Card],
+    num_players: usize,
+    seat: usize,
+    hole_cards: (Card, Card),
+    community_cards: &[Card],
+) -> Result<(), FairnessError> {
+    let n = num_players;
+    let dealt = deck.len() >= 2 * n + community_cards.len()
+        && seat < n
+        && deck[2 * n - 1 - 2 * seat] == hole_cards.0
+        && deck[2 * n - 2 - 2 * seat] == hole_cards.1
+        && deck[2 * n..2 * n + community_cards.len()] == *community_cards;
+    if dealt {
+        Ok(())
+    } else {
+        Err(FairnessError::Deck)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use rand::{SeedableRng, rngs::StdRng};
+
+    fn seeds() -> ShuffleSeeds {
+        ShuffleSeeds {
+            hand: 1,
+            server_seed: "server".to_string(),
+            client_seeds: vec!["alice".to_string(), "bob".to_string()],
+        }
+    }
+
+    #[test]
+    fn test_commitment() {
+        // The SHA-256 hash of "abc".
+        let c = commitment("abc");
+        assert!(
+            c == "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad",
+            "Unexpected hash {}",
+            c
+        );
+        let mut rng = StdRng::seed_from_u64(1);
+        let seed = new_server_seed(&mut rng);
+        assert!(
+            seed.len() == 64 && seed != new_server_seed(&mut rng),
+            "Expected a new 32 byte seed each time, was {}",
+            seed
+        );
+    }
+
+    #[test]
+    fn test_shuffle() {
+        let deck = shuffle(&seeds());
+        let mut sorted = deck.clone();
+        sorted.sort();
+        let mut full = card::new_deck();
+        full.sort();
+        assert!(sorted == full, "Expected a full deck");
+        assert!(
+            deck == shuffle(&seeds()),
+            "Expected the same seeds to deal the same deck"
+        );
+        assert!(deck != card::new_deck(), "Expected the deck to be shuffled");
+        let others = [
+            ShuffleSeeds { hand: 2, ..seeds() },
+            ShuffleSeeds {
+                client_seeds: vec!["alice".to_string()],
+                ..seeds()
+            },
+            ShuffleSeeds {
+                server_seed: "server2".to_string(),
+                ..seeds()
+            },
+        ];
+        for other in others {
+            assert!(
+                shuffle(&other) != deck,
+                "Expected {:?} to deal a different deck",
+                other
+            );
+        }
+    }
+
+    #[test]
+    fn test_verify() {
+        let seeds = seeds();
+        let deck = verify(&commitment("server"), &seeds).unwrap();
+        assert!(deck == shuffle(&seeds), "Expected the deck from the seeds");
+        let result = verify(&commitment("other"), &seeds);
+        assert!(
+            matches!(result, Err(FairnessError::Commitment { .. })),
+            "Expected the commitment not to match, was {:?}",
+            result
+        );
+        let hole = (deck[3], deck[2]);
+        assert!(
+            verify_cards(&deck, 2, 0, hole, &deck[4..9]).is_ok(),
+            "Expected the first player's cards to match"
+        );
+        assert!(
+            verify_cards(&deck, 2, 1, hole, &deck[4..9]) == Err(FairnessError::Deck),
+            "Expected the second player's cards not to match"
+        );
+    }
+}
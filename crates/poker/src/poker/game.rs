@@ -1,24 +1,29 @@
 /// Datatypes and functions for the game and individual rounds.
 use crate::poker::{
+    audit::ChipAudit,
     betting_strategy::BetArgs,
+    blinds::{BlindLevel, BlindSchedule, Clock, SystemClock},
     card,
     card::Card,
-    compare, names,
+    compare,
+    config::{BettingStructure, ConfigError, GameConfig, RebuyPeriod, Straddle},
+    error::GameError,
+    events::{GameEvent, Subscriber},
+    fair::{self, ShuffleSeeds},
+    names,
     player::{Msg, Player, PlayerHand, Winner},
     rotate_vector,
+    session::{SessionHandle, SessionSummary, SessionTracker},
+    snapshot::{GameSnapshot, PlayerState, SnapshotError},
 };
-use rand::{rng, seq::SliceRandom};
+use rand::{Rng, SeedableRng, rngs::StdRng, seq::SliceRandom};
 use serde::{Deserialize, Serialize};
 use std::{
-    collections::HashMap,
+    collections::{HashMap, VecDeque},
     fmt::{self, Display},
 };
 use uuid::Uuid;
 
-// minimum and maximum number of players in a game.
-const MIN_PLAYERS: u8 = 2;
-const MAX_PLAYERS: u8 = 6;
-
 /// Enum for representing the stage of a round.
 #[derive(Debug, PartialEq, Clone, Copy, Serialize, Deserialize)]
 pub enum Stage {
@@ -46,7 +51,7 @@ impl Display for Stage {
 }
 
 /// Enum for representing a bet.
-#[derive(Debug, Copy, Clone, Serialize, Deserialize)]
+#[derive(Debug, Copy, Clone, PartialEq, Serialize, Deserialize)]
 pub enum Bet {
     Fold,
     Check,
@@ -68,12 +73,46 @@ impl Display for Bet {
 }
 
 /// Struct for a side pot.
-#[derive(Debug, Clone)]
-struct SidePot {
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct SidePot {
     players: Vec<String>,
     pot: usize,
 }
 
+/// Enum for what the game is waiting for. See `Game::advance`.
+#[derive(Debug, Clone)]
+pub enum PendingDecision {
+    /// The named player needs to bet, by calling `Game::apply`.
+    Bet { player: String, args: BetArgs },
+    /// The game is over, and was won by the named player.
+    GameOver(String),
+    /// The game is waiting for players, because fewer than two of the players at the
+    /// table are dealt in. It carries on once another player sits in or joins.
+    Waiting,
+}
+
+/// Struct for the state of a round of betting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub(crate) struct Betting {
+    // the order in which players act in this stage.
+    order: Vec<String>,
+    // the players who still need to act, in order.
+    to_act: VecDeque<String>,
+    // the highest bet so far, which every active player has to match.
+    highest: usize,
+    // the minimum raise.
+    min: usize,
+    // the number of times the bet has been raised.
+    cycle: u8,
+    // the players who have acted since the bet was last raised by a full raise.
+    #[serde(default)]
+    acted: Vec<String>,
+    // the players who can only call or fold, because the bet was raised by an all in
+    // for less than a full raise after they acted.
+    #[serde(default)]
+    capped: Vec<String>,
+}
+
 /// Struct for the game.
 #[allow(unused)]
 #[derive(Debug)]
@@ -81,15 +120,38 @@ pub struct Game {
     players: HashMap<String, Box<Player>>,
     players_order: Vec<String>,
     dealer: Option<String>,
-    buy_in: usize,
+    config: GameConfig,
     small_blind: usize,
     big_blind: usize,
+    ante: usize,
+    blind_schedule: Option<BlindSchedule>,
     pot: usize,
     side_pots: Vec<SidePot>,
     deck: Vec<Card>,
     community_cards: Vec<Card>,
-    max_players: u8,
+    rng: StdRng,
     winner: Option<Winner>,
+    game_winner: Option<String>,
+    betting: Option<Betting>,
+    shuffle_seeds: Option<ShuffleSeeds>,
+    audit: ChipAudit,
+    add_ons_offered: bool,
+    /// Whether nobody can bet any more this round, so the rest of the board is dealt
+    /// without betting.
+    runout: bool,
+    /// The second board when the players agreed to run it twice, with the cards dealt
+    /// so far.
+    second_board: Option<Vec<Card>>,
+    /// The last player to bet or raise in the current round of betting, who shows
+    /// their hand first at the showdown.
+    last_aggressor: Option<String>,
+    /// The player who straddled this round, who acts last before the flop.
+    straddle: Option<String>,
+    /// Whether this round is a bomb pot, with no blinds or betting before the flop.
+    bomb_pot: bool,
+    subscribers: Vec<Box<dyn Subscriber>>,
+    /// The clock giving the time each round starts.
+    clock: Box<dyn Clock>,
     stage: Stage,
     num_rounds: usize,
     uuid: uuid::Uuid,
@@ -97,970 +159,4109 @@ pub struct Game {
 
 /// Implementation for the Game struct.
 impl Game {
-    pub fn build(big_blind: usize, max_players: u8) -> Self {
-        if max_players > MAX_PLAYERS {
-            panic!("The maximum number of players is {}", MAX_PLAYERS);
-        }
-        if max_players < MIN_PLAYERS {
-            panic!("The minimum number of players is {}", MIN_PLAYERS);
-        }
+    /// Construct a game from a configuration, which is validated first. See
+    /// `GameConfig::builder`.
+    pub fn from_config(config: GameConfig) -> Result<Self, ConfigError> {
+        config.validate()?;
+        let rng = match config.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_os_rng(),
+        };
         let mut game = Game {
             players: HashMap::new(),
             players_order: Vec::new(),
             dealer: None,
-            buy_in: 100 * big_blind,
-            small_blind: big_blind / 2,
-            big_blind,
+            small_blind: config.small_blind,
+            big_blind: config.big_blind,
+            ante: config.ante,
+            config,
+            blind_schedule: None,
             pot: 0,
             side_pots: Vec::new(),
             deck: Vec::new(),
             community_cards: Vec::new(),
-            max_players,
+            rng,
             winner: None,
+            game_winner: None,
+            betting: None,
+            shuffle_seeds: None,
+            audit: ChipAudit::default(),
+            add_ons_offered: false,
+            runout: false,
+            second_board: None,
+            last_aggressor: None,
+            straddle: None,
+            bomb_pot: false,
+            subscribers: Vec::new(),
+            clock: Box::new(SystemClock),
             stage: Stage::Blinds,
             num_rounds: 0,
             uuid: Uuid::new_v4(),
         };
+        game.shuffle_deck();
+
+        Ok(game)
+    }
+
+    /// The configuration the game was created with.
+    pub fn config(&self) -> &GameConfig {
+        &self.config
+    }
+
+    /// Replace the deck with a stacked one, which is used for the next round instead of
+    /// a shuffled deck, e.g. to replay a recorded hand. Cards are dealt from the front of
+    /// the deck and burned from the back.
+    pub fn set_deck(&mut self, deck: Vec<Card>) {
+        self.deck = deck;
+    }
+
+    /// Replace the deck with a new shuffled one.
+    fn shuffle_deck(&mut self) {
         let mut deck = card::new_deck();
-        let mut rng = rng();
-        deck.shuffle(&mut rng);
-        game.deck = deck;
+        deck.shuffle(&mut self.rng);
+        self.deck = deck;
+    }
 
-        game
+    /// Save the complete state of the game, including any round in progress. The
+    /// players' actors and the game's subscribers aren't saved. Taking a snapshot
+    /// reseeds the deck shuffling, so that the game and a game restored from the
+    /// snapshot go on to deal the same cards.
+    pub fn snapshot(&mut self) -> GameSnapshot {
+        let rng_seed = self.rng.random();
+        self.rng = StdRng::seed_from_u64(rng_seed);
+        GameSnapshot {
+            uuid: self.uuid,
+            config: self.config.clone(),
+            players: self
+                .players_order
+                .iter()
+                .map(|name| {
+                    let p = &self.players[name];
+                    PlayerState {
+                        name: p.name.clone(),
+                        hole: p.hole,
+                        bet: p.bet,
+                        total_bet: p.total_bet,
+                        bank_roll: p.bank_roll,
+                        all_in: p.all_in,
+                        folded: p.folded,
+                        sitting_out: p.sitting_out,
+                        missed_blinds: p.missed_blinds,
+                        leaving: p.leaving,
+                        rebuys: p.rebuys,
+                    }
+                })
+                .collect(),
+            dealer: self.dealer.clone(),
+            small_blind: self.small_blind,
+            big_blind: self.big_blind,
+            ante: self.ante,
+            blind_schedule: self.blind_schedule.as_ref().map(|s| s.state()),
+            stage: self.stage,
+            num_rounds: self.num_rounds,
+            pot: self.pot,
+            deck: self.deck.clone(),
+            community_cards: self.community_cards.clone(),
+            winner: self.winner.clone(),
+            game_winner: self.game_winner.clone(),
+            rng_seed,
+            side_pots: self.side_pots.clone(),
+            betting: self.betting.clone(),
+            shuffle_seeds: self.shuffle_seeds.clone(),
+            audit: self.audit,
+            add_ons_offered: self.add_ons_offered,
+            runout: self.runout,
+            second_board: self.second_board.clone(),
+            last_aggressor: self.last_aggressor.clone(),
+            straddle: self.straddle.clone(),
+            bomb_pot: self.bomb_pot,
+        }
+    }
+
+    /// Restore a game from a snapshot, attaching the supplied players' actors to the
+    /// saved players with the same names. Every saved player must be supplied. Each
+    /// actor is sent its name and bank roll, and its hole cards if a round is in
+    /// progress. Subscribers need to be added again.
+    pub fn restore(
+        snapshot: GameSnapshot,
+        players: impl IntoIterator<Item = Player>,
+    ) -> Result<Self, SnapshotError> {
+        snapshot.config.validate().map_err(SnapshotError::Config)?;
+        let blind_schedule = snapshot
+            .blind_schedule
+            .map(BlindSchedule::from_state)
+            .transpose()
+            .map_err(SnapshotError::BlindSchedule)?;
+        let mut supplied: HashMap<String, Player> =
+            players.into_iter().map(|p| (p.name.clone(), p)).collect();
+        let mut restored = HashMap::new();
+        for state in &snapshot.players {
+            let mut p = supplied
+                .remove(&state.name)
+                .ok_or_else(|| SnapshotError::MissingPlayer(state.name.clone()))?;
+            p.set_name_and_bank_roll(&state.name, state.bank_roll);
+            p.hole = None;
+            if let Some(hole) = state.hole {
+                p.hole_cards(hole);
+            }
+            p.bet = state.bet;
+            p.total_bet = state.total_bet;
+            p.all_in = state.all_in;
+            p.folded = state.folded;
+            p.sitting_out = state.sitting_out;
+            p.missed_blinds = state.missed_blinds;
+            p.leaving = state.leaving;
+            p.rebuys = state.rebuys;
+            restored.insert(state.name.clone(), Box::new(p));
+        }
+        if let Some(name) = supplied.into_keys().next() {
+            return Err(SnapshotError::UnknownPlayer(name));
+        }
+        Ok(Game {
+            players: restored,
+            players_order: snapshot.players.into_iter().map(|p| p.name).collect(),
+            dealer: snapshot.dealer,
+            config: snapshot.config,
+            small_blind: snapshot.small_blind,
+            big_blind: snapshot.big_blind,
+            ante: snapshot.ante,
+            blind_schedule,
+            pot: snapshot.pot,
+            side_pots: snapshot.side_pots,
+            deck: snapshot.deck,
+            community_cards: snapshot.community_cards,
+            rng: StdRng::seed_from_u64(snapshot.rng_seed),
+            winner: snapshot.winner,
+            game_winner: snapshot.game_winner,
+            betting: snapshot.betting,
+            shuffle_seeds: snapshot.shuffle_seeds,
+            audit: snapshot.audit,
+            add_ons_offered: snapshot.add_ons_offered,
+            runout: snapshot.runout,
+            second_board: snapshot.second_board,
+            last_aggressor: snapshot.last_aggressor,
+            straddle: snapshot.straddle,
+            bomb_pot: snapshot.bomb_pot,
+            subscribers: Vec::new(),
+            clock: Box::new(SystemClock),
+            stage: snapshot.stage,
+            num_rounds: snapshot.num_rounds,
+            uuid: snapshot.uuid,
+        })
+    }
+
+    /// Shuffle the deck for the next round from a new server seed and any seeds supplied
+    /// by the players' actors. The commitment to the server seed is sent before the
+    /// players are asked for their seeds.
+    fn commit_shuffle(&mut self) {
+        let hand = self.num_rounds + 1;
+        let server_seed = fair::new_server_seed(&mut self.rng);
+        let commitment = fair::commitment(&server_seed);
+        self.update_players(&Msg::ShuffleCommitment {
+            hand,
+            commitment: commitment.clone(),
+        });
+        self.emit(GameEvent::ShuffleCommitted { hand, commitment });
+        let client_seeds = self
+            .players_order
+            .iter()
+            .filter_map(|name| self.players[name].actor.client_seed())
+            .collect();
+        let seeds = ShuffleSeeds {
+            hand,
+            server_seed,
+            client_seeds,
+        };
+        self.deck = fair::shuffle(&seeds);
+        self.shuffle_seeds = Some(seeds);
+    }
+
+    /// Reveal the seeds for the round's deck, if it was shuffled by `commit_shuffle`.
+    fn reveal_shuffle(&mut self) {
+        if let Some(seeds) = self.shuffle_seeds.take() {
+            self.update_players(&Msg::ShuffleRevealed(seeds.clone()));
+            self.emit(GameEvent::ShuffleRevealed(seeds));
+        }
     }
 
     /// Predicate function for the game having the full amount of players.
     fn full(&self) -> bool {
-        self.players.len() == self.max_players as usize
+        self.players.len() == self.config.max_players as usize
     }
 
-    /// Allows a player to join the game. The new player's bank roll will be equal to the buy in amount.
+    /// Allows a player to join the game. The new player's bank roll will be equal to the buy in amount,
+    /// unless the configuration gives them a different starting stack.
     /// The player's name may be changed to make it unique among existing players. The player
     /// instance is notified of the name and bank roll via Player::set_name_and_bank_roll.
-    pub fn join(&mut self, mut player: Player) -> Result<(), &'static str> {
+    /// Players can join a running game if there is an empty seat. If a round is being
+    /// played they are dealt in from the next one. The other players are sent a
+    /// `Msg::PlayerJoined`.
+    pub fn join(&mut self, mut player: Player) -> Result<(), GameError> {
         if self.full() {
-            return Err("Cannot add more players.");
+            return Err(GameError::TableFull);
         }
         let name = names::uniquify_name(&player.name, &self.players_order);
-        player.set_name_and_bank_roll(&name, self.buy_in);
+        let bank_roll = self.config.starting_stack(&name);
+        player.set_name_and_bank_roll(&name, bank_roll);
+        // A player joining during a round sits it out as though they had folded.
+        player.folded = self.stage != Stage::Blinds;
+        self.update_players(&Msg::PlayerJoined {
+            player: name.clone(),
+            bank_roll,
+        });
+        self.emit(GameEvent::PlayerJoined {
+            player: name.clone(),
+            bank_roll,
+        });
+        self.audit.buy_ins += bank_roll;
         self.players.insert(name.clone(), Box::new(player));
         self.players_order.push(name);
         Ok(())
     }
 
-    /// Play a game.
-    pub fn play(&mut self) -> Winner {
-        while self.players.len() > 1 {
-            self.play_round();
-            self.reset_after_round();
-            self.num_rounds += 1;
-        }
-        let w = self.get_winner();
-        let msg = Msg::GameWinner(w.clone());
-        self.update_players(&msg);
-        w
-    }
-
-    /// Determine the winner at the end of the game. Assumption is that there's only one
-    /// player left.
-    fn get_winner(&self) -> Winner {
-        let winner_opt = self.players_order.first();
-        if let Some(name) = winner_opt {
-            let winner = self.players.get(name).unwrap();
-            let mut cards = self.community_cards.clone();
-            let (h1, h2) = winner.hole.unwrap();
-            cards.push(h1);
-            cards.push(h2);
-            let name = winner.name.clone();
-            let hand = compare::best_hand(&cards);
-            Winner::SoleWinner(PlayerHand { name, hand, cards })
-        } else {
-            panic!("Announcing winner but they have been removed...")
+    /// Account for the chips in the game: the chips bought by the players, the chips
+    /// taken away by players who left and the chips still in play.
+    pub fn audit(&self) -> ChipAudit {
+        let in_play = self.players.values().map(|p| p.bank_roll).sum::<usize>()
+            + self.pot
+            + self.side_pots.iter().map(|sp| sp.pot).sum::<usize>();
+        ChipAudit {
+            in_play,
+            ..self.audit
         }
     }
 
-    /// Announce the players at the beginning of a round.
-    fn announce_players(&self) {
-        let players = self
-            .players_order
-            .iter()
-            .map(|name| (name.clone(), self.players.get(name).unwrap().bank_roll))
-            .collect();
-        let dealer = self.dealer.as_ref().unwrap().clone();
-        let msg = Msg::PlayersInfo { players, dealer };
-        self.update_players(&msg);
-    }
-
-    /// Announce the winner at the end of the round.
-    fn announce_winner_round(&self) {
-        let w = self.winner.as_ref().unwrap();
-        let msg = Msg::RoundWinner(w.clone());
-        self.update_players(&msg);
+    /// Whether the rebuy period is still running for the current round.
+    fn rebuys_open(&self) -> bool {
+        let Some(rules) = self.config.rebuys else {
+            return false;
+        };
+        match rules.period {
+            RebuyPeriod::Hands(n) => self.num_rounds < n,
+            RebuyPeriod::Level(n) => {
+                self.blind_schedule.as_ref().map_or(0, |s| s.level_index()) < n
+            }
+            RebuyPeriod::Always => true,
+        }
     }
 
-    /// Set the name of the dealer and reorder the players_order list
-    /// so that the player to the left of the dealer is at the front.
-    fn order_players(&mut self) {
-        if self.stage == Stage::Blinds {
-            let players_order: Vec<String> = self.players_order.clone();
-            self.dealer = Some(players_order.first().unwrap().clone());
-        }
-        let dealer = self.dealer.as_ref();
-        if self.players.contains_key(dealer.unwrap()) {
-            let pos = self
-                .players_order
-                .iter()
-                .position(|n| n == dealer.unwrap())
-                .unwrap();
-            let current_i = pos + 1 % self.players_order.len();
-            self.players_order = rotate_vector(&self.players_order, current_i);
-        } else {
-            self.dealer = Some(self.players_order.first().unwrap().clone());
-        }
-    }
-
-    /// Play a round.
-    fn play_round(&mut self) {
-        self.order_players();
-        self.ante_up();
-        self.announce_players();
-        self.stage = Stage::Hole;
-        self.deal_hole_cards();
-        self.stage = Stage::PreFlop;
-        self.place_bets();
-        self.stage = Stage::Flop;
-        self.deal_flop();
-        self.place_bets();
-        self.stage = Stage::Turn;
-        self.deal_turn();
-        self.place_bets();
-        self.stage = Stage::River;
-        self.deal_river();
-        self.place_bets();
-        self.stage = Stage::ShowDown;
-        self.showdown();
-        self.distribute_pots();
-        // announce the winner.
-        self.announce_winner_round();
-    }
-
-    /// Each player pays the small or big blind at the beginning of each round,
-    /// or chooses not to if they don't want to take part in this round.
-    /// The player to the left of the dealer pays the small blind, everyone else
-    /// pays the big blind.
-    fn ante_up(&mut self) {
-        if self.players_order.is_empty() {
-            return;
+    /// Offer a rebuy to a player who has gone broke, if the rules allow it, returning
+    /// whether they took it.
+    fn offer_rebuy(&mut self, name: &str) -> bool {
+        let Some(rules) = self.config.rebuys else {
+            return false;
+        };
+        if !self.rebuys_open() {
+            return false;
         }
-
-        let players_order = self.players_order.clone();
-
-        // Handle the first player (Small Blind) in a restricted scope.
+        let Some(p) = self.players.get_mut(name) else {
+            return false;
+        };
+        if p.leaving
+            || rules.max_rebuys.is_some_and(|max| p.rebuys >= max)
+            || !p.offer_rebuy(rules.amount)
         {
-            let left_of_dealer: &String = players_order.first().unwrap();
-
-            // Mutably borrow the player and execute the action
-            if let Some(first_p) = self.players.get_mut(left_of_dealer)
-                && let Some(blind) = first_p.ante_up(self.small_blind)
-            {
-                self.pot += blind;
-            }
-            // NB: player marks themself as folded if they responded negatively
-            // or as all in if their bank roll was less than the blind.
+            return false;
         }
-
-        // Handle remaining players.
-        players_order[1..].iter().for_each(|name| {
-            if let Some(p) = self.players.get_mut(name)
-                && let Some(blind) = p.ante_up(self.big_blind)
-            {
-                self.pot += blind
-            }
+        self.audit.rebuys += rules.amount;
+        let player = name.to_string();
+        self.update_players(&Msg::Rebuy {
+            player: player.clone(),
+            amount: rules.amount,
         });
+        self.emit(GameEvent::Rebuy {
+            player,
+            amount: rules.amount,
+        });
+        true
     }
 
-    /// Take num cards from the deck.
-    fn take_cards(&mut self, num: usize) -> Result<Vec<Card>, &'static str> {
-        if self.deck.len() < num {
-            Err("Not enough cards left")
-        } else {
-            let cards: Vec<Card> = self.deck[0..num].to_vec();
-            self.deck = self.deck[num..].to_vec();
-            Ok(cards)
+    /// Offer the add-on to every player, once, when the rebuy period has ended.
+    fn offer_add_ons(&mut self) -> Result<(), GameError> {
+        let Some(amount) = self.config.rebuys.and_then(|r| r.add_on) else {
+            return Ok(());
+        };
+        if self.add_ons_offered || self.rebuys_open() {
+            return Ok(());
         }
+        self.add_ons_offered = true;
+        for name in self.players_order.clone() {
+            let p = self.player_mut(&name)?;
+            if p.leaving || !p.offer_add_on(amount) {
+                continue;
+            }
+            self.audit.add_ons += amount;
+            self.update_players(&Msg::AddOn {
+                player: name.clone(),
+                amount,
+            });
+            self.emit(GameEvent::AddOn {
+                player: name,
+                amount,
+            });
+        }
+        Ok(())
     }
 
-    /// Burn a card.
-    fn burn_card(&mut self) -> Result<(), &'static str> {
-        if self.deck.is_empty() {
-            Err("No cards left")
-        } else {
-            self.deck.pop();
-            Ok(())
+    /// Take a player away from the table with their chips, e.g. to move them to another
+    /// table in a tournament. Players can only be moved between rounds. The players,
+    /// including the one moved, are sent a `Msg::PlayerLeft`.
+    pub fn take_player(&mut self, name: &str) -> Result<Player, GameError> {
+        if self.stage != Stage::Blinds {
+            return Err(GameError::RoundInProgress);
         }
+        let bank_roll = self.player_mut(name)?.bank_roll;
+        self.audit.cashed_out += bank_roll;
+        self.update_players(&Msg::PlayerLeft {
+            player: name.to_string(),
+            bank_roll,
+        });
+        self.emit(GameEvent::PlayerLeft {
+            player: name.to_string(),
+            bank_roll,
+        });
+        self.players_order.retain(|n| n != name);
+        let p = self
+            .players
+            .remove(name)
+            .ok_or_else(|| GameError::UnknownPlayer(name.to_string()))?;
+        Ok(*p)
     }
 
-    /// Deal two hole cards to each player.
-    fn deal_hole_cards(&mut self) {
-        let mut hole_cards = self.take_cards(2 * self.players.len()).unwrap();
-        self.players.iter_mut().for_each(|(_, p)| {
-            let hole_1 = hole_cards.pop().unwrap();
-            let hole_2 = hole_cards.pop().unwrap();
-            p.hole_cards((hole_1, hole_2));
+    /// Seat a player who has been taken from another table, keeping their name and
+    /// chips. Players can only be seated between rounds.
+    pub fn seat_player(&mut self, player: Player) -> Result<(), GameError> {
+        if self.full() {
+            return Err(GameError::TableFull);
+        }
+        if self.stage != Stage::Blinds {
+            return Err(GameError::RoundInProgress);
+        }
+        let name = names::uniquify_name(&player.name, &self.players_order);
+        let bank_roll = player.bank_roll;
+        self.audit.buy_ins += bank_roll;
+        self.update_players(&Msg::PlayerJoined {
+            player: name.clone(),
+            bank_roll,
+        });
+        self.emit(GameEvent::PlayerJoined {
+            player: name.clone(),
+            bank_roll,
         });
+        let mut player = player;
+        player.set_name_and_bank_roll(&name, bank_roll);
+        self.players.insert(name.clone(), Box::new(player));
+        self.players_order.push(name);
+        Ok(())
     }
 
-    /// Burn one card and deal the first three three community cards.
-    fn deal_flop(&mut self) {
-        let _burn = self.burn_card();
-        let mut flop_cards: Vec<Card> = self.take_cards(3).unwrap();
-        self.community_cards.append(flop_cards.as_mut());
+    /// Find a player at the table.
+    fn player_mut(&mut self, name: &str) -> Result<&mut Player, GameError> {
+        self.players
+            .get_mut(name)
+            .map(|p| p.as_mut())
+            .ok_or_else(|| GameError::UnknownPlayer(name.to_string()))
     }
 
-    /// Burn one card and deal the fourth community card.
-    fn deal_turn(&mut self) {
-        let _burn = self.burn_card();
-        let mut turn_card: Vec<Card> = self.take_cards(1).unwrap();
-        self.community_cards.append(turn_card.as_mut());
+    /// Sit a player out, so that they aren't dealt in from the next round until they sit
+    /// in again. A player sitting out doesn't pay the ante or blinds, but if the game is
+    /// configured to post missed blinds they have to post the big blind when they come
+    /// back if the blinds passed them while they were away.
+    pub fn sit_out(&mut self, name: &str) -> Result<(), GameError> {
+        let p = self.player_mut(name)?;
+        if !p.sitting_out {
+            p.sitting_out = true;
+            let player = name.to_string();
+            self.update_players(&Msg::SatOut {
+                player: player.clone(),
+            });
+            self.emit(GameEvent::PlayerSatOut { player });
+        }
+        Ok(())
     }
 
-    /// Burn one card and deal the fifth and final community card.
-    fn deal_river(&mut self) {
-        let _burn = self.burn_card();
-        let mut river_card: Vec<Card> = self.take_cards(1).unwrap();
-        self.community_cards.append(river_card.as_mut());
+    /// Deal a player who is sitting out in again from the next round.
+    pub fn sit_in(&mut self, name: &str) -> Result<(), GameError> {
+        let p = self.player_mut(name)?;
+        if p.sitting_out {
+            p.sitting_out = false;
+            let player = name.to_string();
+            self.update_players(&Msg::SatIn {
+                player: player.clone(),
+            });
+            self.emit(GameEvent::PlayerSatIn { player });
+        }
+        Ok(())
     }
 
-    /// Players are given the opportunity to bet. If a player raises the bet, every
-    /// other player must respond (fold, call or raise again).
-    fn place_bets(&mut self) {
-        // names of players who have not folded
-        let not_folded: Vec<(String, bool)> = self
-            .players
-            .values()
-            .filter(|p| !p.folded)
-            .map(|p| (p.name.clone(), p.all_in))
-            .collect();
-        // names of players who have not folded and are not all in. These are the players who need to make a bet/call/fold.
-        let mut not_all_in: Vec<String> = not_folded
+    /// Remove a player from the table with their chips. A player who leaves during a
+    /// round plays it out and leaves at the end of it. The players, including the one
+    /// leaving, are sent a `Msg::PlayerLeft` with the number of chips they left with.
+    pub fn leave(&mut self, name: &str) -> Result<(), GameError> {
+        self.player_mut(name)?.leaving = true;
+        if self.stage == Stage::Blinds {
+            self.remove_leaving_players();
+        }
+        Ok(())
+    }
+
+    /// Remove the players who are leaving the table.
+    fn remove_leaving_players(&mut self) {
+        let leaving: Vec<String> = self
+            .players_order
             .iter()
-            .filter(|(_name, all_in)| !all_in)
-            .map(|(name, _all_in)| name.clone())
+            .filter(|name| self.players[*name].leaving)
+            .cloned()
             .collect();
-        // The players who will be betting, in the right order
-        let mut players: Vec<String> = Vec::new();
-        for name in self.players_order.clone() {
-            if not_folded.iter().any(|(n, _b)| n == &name) {
-                players.push(name);
-            }
-        }
-        if players.is_empty() {
-            return;
+        for name in leaving {
+            let bank_roll = self.players[&name].bank_roll;
+            self.audit.cashed_out += bank_roll;
+            self.update_players(&Msg::PlayerLeft {
+                player: name.clone(),
+                bank_roll,
+            });
+            self.emit(GameEvent::PlayerLeft {
+                player: name.clone(),
+                bank_roll,
+            });
+            self.players.remove(&name);
+            self.players_order.retain(|n| n != &name);
         }
+    }
 
-        let update = Msg::StageDeclare(self.stage, self.community_cards.clone());
-        self.update_players(&update);
+    /// Whether the game is waiting for players between rounds, because there are
+    /// players at the table but fewer than two of them are dealt in, e.g. because the
+    /// others are sitting out.
+    pub fn waiting_for_players(&self) -> bool {
+        self.stage == Stage::Blinds && self.players.len() >= 2 && self.active_players().len() < 2
+    }
 
-        let mut current_index: usize = 0;
-        // target is the player at which the round of betting should stop.
-        // This is altered when a player raises the bet.
-        let mut target: String = players.first().unwrap().clone();
-        let mut done: bool = false; // flag to stop the loop
-        let mut target_placed_bet: bool = false; // flag to allow target to place first bet.
-        let mut call: usize = 0;
-        let min = self.big_blind;
-        let mut cycle: u8 = 0; // the number of times players have been given a chance to bet in this round.
-
-        // Ask each player to place a bet at least once. Note that the Player struct is responsible
-        // for managing its own state during betting, e.g. keeping the bank roll up to date
-        // and whether the player is folded or all in.
-        while !done && players.len() > 1 {
-            let current_name = &players[current_index % players.len()];
-            let p = self.players.get_mut(current_name).unwrap();
-            if p.name == target && target_placed_bet {
-                done = true;
-            } else {
-                if p.name == target {
-                    target_placed_bet = true;
-                }
-                if !p.all_in && !p.folded {
-                    let ccards = self.community_cards.clone();
-                    let args = BetArgs {
-                        call,
-                        min,
-                        stage: self.stage,
-                        cycle,
-                        community_cards: ccards,
-                    };
-                    let bet_opt = p.place_bet(args);
+    /// The players who are dealt in to rounds, i.e. who aren't sitting out, in seat
+    /// order.
+    fn active_players(&self) -> Vec<String> {
+        self.players_order
+            .iter()
+            .filter(|name| !self.players[*name].sitting_out)
+            .cloned()
+            .collect()
+    }
 
-                    let bet = bet_opt.unwrap();
+    /// The active players with their bank rolls.
+    fn active_bank_rolls(&self) -> Vec<(String, usize)> {
+        self.active_players()
+            .into_iter()
+            .map(|name| {
+                let bank_roll = self.players[&name].bank_roll;
+                (name, bank_roll)
+            })
+            .collect()
+    }
 
-                    match bet {
-                        Bet::Fold => {
-                            players.remove(current_index);
-                            continue; // continue without incrementing current
-                        }
-                        Bet::Check => {
-                            if call > 0 {
-                                panic!(
-                                    "Misbehaving client checked when there was an outstanding bet."
-                                );
-                            }
-                        }
-                        Bet::Call => {
-                            self.pot += call;
-                        }
-                        Bet::Raise(raise) => {
-                            cycle += 1;
-                            if !self.side_pots.is_empty() {
-                                let side_pot = self.side_pots.get_mut(0).unwrap();
-                                side_pot.pot += raise;
-                            } else {
-                                self.pot += raise;
-                            }
-                            // raise is the new amount to match/beat
-                            call = raise;
-                            target = p.name.clone();
-                        }
-                        Bet::AllIn(bet) => {
-                            self.pot += bet;
+    /// Add a subscriber, which receives every event in the game from now on.
+    pub fn subscribe(&mut self, subscriber: impl Subscriber + 'static) {
+        self.subscribers.push(Box::new(subscriber));
+    }
 
-                            if let Some(index) =
-                                not_all_in.iter().position(|value| value == &p.name)
-                            {
-                                not_all_in.swap_remove(index);
-                            }
+    /// Send an event to the subscribers.
+    fn emit(&self, event: GameEvent) {
+        self.subscribers.iter().for_each(|s| s.notify(&event));
+    }
 
-                            let new_side_pot = SidePot {
-                                players: not_all_in.clone(),
-                                pot: 0,
-                            };
-                            self.side_pots.push(new_side_pot);
-                            // don't ask this player again in this round.
-                            players.remove(current_index);
-                            continue; // continue without incrementing current
-                        }
-                    }
-                    let update = Msg::Bet {
-                        player: p.name.clone(),
-                        bet,
-                        pot: self.pot,
-                    };
-                    self.update_players(&update);
-                }
-                current_index = (current_index + 1) % players.len();
-            }
-        }
+    /// Replace the clock giving the time each round starts, which is recorded in hand
+    /// histories. Games use the system clock until this is called, including games
+    /// restored from a snapshot.
+    pub fn set_clock(&mut self, clock: impl Clock + 'static) {
+        self.clock = Box::new(clock);
     }
 
-    /// Send a message to the players.
-    fn update_players(&self, update: &Msg) {
-        self.players.values().for_each(|p| {
-            p.update(update);
-        });
+    /// Attach a blind schedule to the game. From the next round onwards, the blinds and
+    /// ante are taken from the current level of the schedule, and players are notified
+    /// whenever the level changes.
+    pub fn set_blind_schedule(&mut self, schedule: BlindSchedule) {
+        self.set_blinds(schedule.current_level());
+        self.blind_schedule = Some(schedule);
     }
 
-    /// Determines the winner(s) of the round.
-    fn showdown(&mut self) {
-        // Get the best hand for each non-folded player.
-        let mut hands: Vec<PlayerHand> = self.names_to_hands(&self.players_order);
-        // Handle cases where 0 or 1 players remain (the last player standing wins)
-        if hands.len() < 2 {
-            if let Some(PlayerHand {
-                name,
-                hand: best_hand,
-                cards,
-            }) = hands.pop()
-            {
-                let winner = Winner::SoleWinner(PlayerHand {
-                    name,
-                    hand: best_hand,
-                    cards,
-                });
-                self.winner = Some(winner);
-            } else {
-                panic!("No players remaining to determine winner.");
-            }
+    /// The current small blind, big blind and ante.
+    pub fn blinds(&self) -> (usize, usize, usize) {
+        (self.small_blind, self.big_blind, self.ante)
+    }
+
+    /// Set the blinds and ante from a level of the blind schedule.
+    fn set_blinds(&mut self, level: BlindLevel) {
+        self.small_blind = level.small_blind;
+        self.big_blind = level.big_blind;
+        self.ante = level.ante;
+    }
+
+    /// Move the blind schedule on by one round, announcing the blinds if they have
+    /// changed or if this is the first round of the game.
+    fn update_blinds(&mut self) {
+        let Some(schedule) = self.blind_schedule.as_mut() else {
             return;
+        };
+        let changed = schedule.start_round();
+        let level = schedule.current_level();
+        let index = schedule.level_index();
+        if changed.is_some() || self.num_rounds == 0 {
+            self.set_blinds(level);
+            let msg = Msg::BlindLevel {
+                level: index + 1,
+                small_blind: level.small_blind,
+                big_blind: level.big_blind,
+                ante: level.ante,
+            };
+            self.update_players(&msg);
+            self.emit(GameEvent::BlindLevel {
+                level: index + 1,
+                small_blind: level.small_blind,
+                big_blind: level.big_blind,
+                ante: level.ante,
+            });
         }
+    }
 
-        let winner = Game::determine_winner(hands);
-        self.winner = Some(winner);
+    /// Play a game, asking each player's actor for their bets. If a round fails, e.g.
+    /// because a player made an illegal bet or their actor stopped responding, the chips
+    /// bet in that round are returned to the players and the error is returned. The game
+    /// is left ready to play the round again, so the caller can deal with the problem
+    /// (e.g. by removing the player) and carry on by calling `play` again. If the game
+    /// is waiting for players it can't carry on, and `GameError::NotEnoughPlayers` is
+    /// returned with the game unchanged.
+    pub fn play(&mut self) -> Result<String, GameError> {
+        loop {
+            if let Some(w) = self.play_round()? {
+                return Ok(w);
+            }
+            if self.waiting_for_players() {
+                return Err(GameError::NotEnoughPlayers);
+            }
+        }
     }
 
-    /// Takes a vector of player names and return a vector of PlayerHand objects.
-    /// Result contains only non-folded players.
-    fn names_to_hands(&self, names: &[String]) -> Vec<PlayerHand> {
-        // Calculate the best hand for each non-folded player.
-        let hands: Vec<PlayerHand> = self
-            .players
-            .iter() // Use iter() since we don't need to mutate Player state here
-            .filter_map(|(_, p)| {
-                // Only consider players who haven't folded
-                if p.folded || !names.contains(&p.name) {
-                    return None;
+    /// Play one round of the game, asking each player's actor for their bets. Returns
+    /// the winner of the game if it is over. No round is played if the game is waiting
+    /// for players. Errors are dealt with as in `play`.
+    pub fn play_round(&mut self) -> Result<Option<String>, GameError> {
+        if self.waiting_for_players() {
+            return Ok(None);
+        }
+        let round = self.num_rounds;
+        while self.num_rounds == round {
+            let result = self.step().and_then(|decision| match decision {
+                Some(PendingDecision::Bet { player, args }) => {
+                    self.act(&player, args).map(|_| None)
                 }
-                let (c1, c2) = p
-                    .hole
-                    .expect("Hole cards should be dealt before calling names_to_hands");
-                // Collect all 7 cards (2 hole + 5 community)
-                let mut all_cards = self.community_cards.clone();
-                all_cards.push(c1);
-                all_cards.push(c2);
+                Some(PendingDecision::GameOver(w)) => Ok(Some(w)),
+                Some(PendingDecision::Waiting) | None => Ok(None),
+            });
+            match result {
+                Ok(Some(w)) => return Ok(Some(w)),
+                Ok(None) => {}
+                Err(e) => {
+                    self.abandon_round(&e);
+                    return Err(e);
+                }
+            }
+        }
+        Ok(None)
+    }
 
-                let best_hand = compare::best_hand(&all_cards);
-                Some(PlayerHand {
-                    name: p.name.clone(),
-                    hand: best_hand,
-                    cards: all_cards,
-                })
-            })
-            .collect();
-        hands
+    /// Play a cash game session, which ends when the number of hands or the time in the
+    /// configuration's `session` limits is reached, when the handle is told to stop,
+    /// or when there aren't enough players dealt in to carry on. Players can cash out
+    /// during the session through the handle, or with `leave`. The hand being played
+    /// is always finished. Returns a summary of how each player got on, counting from
+    /// their chips at the start of the session. Errors are dealt with as in `play`,
+    /// and end the session.
+    pub fn play_session(
+        &mut self,
+        handle: &SessionHandle,
+        clock: &dyn Clock,
+    ) -> Result<SessionSummary, GameError> {
+        let started = clock.now();
+        let limits = self.config.session;
+        let tracker = SessionTracker::new(self.bank_rolls());
+        self.subscribers.push(Box::new(tracker.clone()));
+        let result = loop {
+            for name in handle.take_cash_outs() {
+                let _ = self.leave(&name);
+            }
+            let over = handle.stopped()
+                || limits.hands.is_some_and(|hands| tracker.hands() >= hands)
+                || limits
+                    .duration
+                    .is_some_and(|d| clock.now().duration_since(started) >= d)
+                || self.players.len() < 2
+                || self.waiting_for_players();
+            if over {
+                break Ok(());
+            }
+            match self.play_round() {
+                Ok(Some(_)) => break Ok(()),
+                Ok(None) => {}
+                Err(e) => break Err(e),
+            }
+        };
+        self.subscribers.pop();
+        result?;
+        Ok(tracker.summary(self.bank_rolls(), clock.now().duration_since(started)))
     }
 
-    /// Determine winner(s) from vector of (name, best_hand, cards) tuples.
-    fn determine_winner(mut hands: Vec<PlayerHand>) -> Winner {
-        // Handle cases where 0 or 1 players remain (the last player standing wins)
-        if hands.len() < 2 {
-            if let Some(PlayerHand {
-                name,
-                hand: best_hand,
-                cards,
-            }) = hands.pop()
-            {
-                return Winner::SoleWinner(PlayerHand {
-                    name,
-                    hand: best_hand,
-                    cards,
-                });
-            } else {
-                panic!("No players remaining to determine winner.");
+    /// Ask a player's actor for the bet needed by a `PendingDecision::Bet` and apply it,
+    /// for callers driving the game with `advance` whose players' actors bet for
+    /// themselves, e.g. auto players. Errors are returned as in `apply`, or if the
+    /// actor doesn't respond.
+    pub fn act(&mut self, name: &str, args: BetArgs) -> Result<Vec<Msg>, GameError> {
+        let bet = self.player_mut(name)?.request_bet(args)?;
+        self.apply(name, bet)
+    }
+
+    /// Run the game until a player needs to make a decision or the game is over. This
+    /// deals the cards, posts the blinds, settles each round and starts the next one,
+    /// sending messages to the players as it goes. When a bet is needed, the caller
+    /// supplies it with `apply` and then calls `advance` again. Calling `advance` again
+    /// without applying a bet returns the same decision, and calling it while the game
+    /// is waiting for players returns `PendingDecision::Waiting` until one sits in or
+    /// joins.
+    pub fn advance(&mut self) -> Result<PendingDecision, GameError> {
+        loop {
+            if let Some(decision) = self.step()? {
+                return Ok(decision);
             }
         }
+    }
 
-        // Compare the hands.
+    /// Apply a bet made by the player whose turn it is, returning the messages sent to
+    /// the players as a result. Returns an error if it isn't the player's turn or the
+    /// bet is illegal, in which case nothing changes and the player can bet again.
+    pub fn apply(&mut self, name: &str, bet: Bet) -> Result<Vec<Msg>, GameError> {
+        let call = match self.next_decision() {
+            Some(PendingDecision::Bet { player, args }) if player == name => args.call,
+            _ => {
+                return Err(GameError::IllegalAction {
+                    player: name.to_string(),
+                    bet,
+                    reason: "it isn't their turn".to_string(),
+                });
+            }
+        };
+        self.check_raise(name, bet, call)?;
+        let (Some(betting), Some(p)) = (self.betting.as_mut(), self.players.get_mut(name)) else {
+            return Err(GameError::NotEnoughPlayers);
+        };
 
-        // Initialize winner with the first player's hand.
-        let mut winner: Winner = {
-            let PlayerHand {
-                name,
-                hand: best_hand,
-                cards,
-            } = hands.remove(0);
-            Winner::SoleWinner(PlayerHand {
-                name,
-                hand: best_hand,
-                cards,
-            })
+        // Note that the Player struct is responsible for managing its own state during
+        // betting, e.g. keeping the bank roll up to date and whether the player is folded
+        // or all in.
+        let before = p.bet;
+        let bet = p.make_bet(bet, call)?;
+        betting.to_act.pop_front();
+        betting.acted.push(name.to_string());
+        let bet_total = p.bet;
+        self.pot += bet_total - before;
+        if bet_total > betting.highest {
+            // The bet was raised, so everyone else who can still bet has to respond. An
+            // all in for less than a full raise doesn't reopen the betting, so the players
+            // who have already acted can only call or fold.
+            if bet_total - betting.highest >= betting.min {
+                betting.min = bet_total - betting.highest;
+                betting.cycle = betting.cycle.saturating_add(1);
+                betting.acted = vec![name.to_string()];
+                betting.capped.clear();
+            } else {
+                betting.capped = betting.acted.clone();
+            }
+            betting.highest = bet_total;
+            self.last_aggressor = Some(name.to_string());
+            let players = &self.players;
+            betting.to_act = betting
+                .order
+                .iter()
+                .cycle()
+                .skip_while(|n| *n != name)
+                .skip(1)
+                .take(betting.order.len() - 1)
+                .filter(|n| {
+                    let p = &players[*n];
+                    !p.folded && !p.all_in
+                })
+                .cloned()
+                .collect();
+        }
+        let update = Msg::Bet {
+            player: name.to_string(),
+            bet,
+            pot: self.pot,
         };
+        self.update_players(&update);
+        self.emit(GameEvent::BetPlaced {
+            player: name.to_string(),
+            bet,
+            amount: bet_total - before,
+            pot: self.pot,
+        });
+        Ok(vec![update])
+    }
 
-        // Compare current winner against all remaining hands.
-        for PlayerHand {
-            name: challenger_name,
-            hand: challenger_hand,
-            cards: challenger_cards,
-        } in hands
+    /// Do the next piece of work in the game, returning a decision if one is needed.
+    fn step(&mut self) -> Result<Option<PendingDecision>, GameError> {
+        if let Some(w) = &self.game_winner {
+            return Ok(Some(PendingDecision::GameOver(w.clone())));
+        }
+        if self.betting.is_some() {
+            if let Some(decision) = self.next_decision() {
+                return Ok(Some(decision));
+            }
+            self.end_betting();
+        }
+        // The round ends as soon as everyone but one player has folded.
+        if matches!(
+            self.stage,
+            Stage::PreFlop | Stage::Flop | Stage::Turn | Stage::River
+        ) && let Some(name) = self.last_player_standing()
         {
-            winner = match winner {
-                Winner::SoleWinner(PlayerHand {
-                    name: w_name,
-                    hand: w_hand,
-                    cards: w_cards,
-                }) => {
-                    // Compare the current winner (w_...) against the challenger (challenger_...)
-                    compare::compare_hands(
-                        PlayerHand {
-                            name: challenger_name,
-                            hand: challenger_hand,
-                            cards: challenger_cards,
-                        },
-                        PlayerHand {
-                            name: w_name,
-                            hand: w_hand,
-                            cards: w_cards,
-                        },
-                    )
+            self.stage = Stage::ShowDown;
+            self.win_uncontested(&name)?;
+            self.reveal_shuffle();
+            return Ok(None);
+        }
+        // Once at most one player can still bet, the rest of the board is dealt without
+        // any more betting.
+        if matches!(self.stage, Stage::PreFlop | Stage::Flop | Stage::Turn)
+            && !self.runout
+            && self.betting_finished()
+        {
+            self.start_runout();
+        }
+        match self.stage {
+            Stage::Blinds => {
+                if self.players.len() < 2 {
+                    let w = self.get_winner()?;
+                    let msg = Msg::GameWinner(w.clone());
+                    self.update_players(&msg);
+                    self.emit(GameEvent::GameWinner(w.clone()));
+                    self.game_winner = Some(w);
+                    return Ok(None);
                 }
-                Winner::Draw(mut draw_winners) => {
-                    // It's a draw, compare the challenger against the best hand in the draw group.
-                    // Assume the first element of a Draw is the benchmark.
-                    let PlayerHand {
-                        name: w_name_benchmark,
-                        hand: w_hand_benchmark,
-                        cards: w_cards_benchmark,
-                    } = draw_winners.pop().unwrap();
+                if self.waiting_for_players() {
+                    return Ok(Some(PendingDecision::Waiting));
+                }
+                self.update_blinds();
+                self.offer_add_ons()?;
+                self.order_players()?;
+                // Players sitting out aren't dealt in, as though they had folded.
+                self.players
+                    .values_mut()
+                    .filter(|p| p.sitting_out)
+                    .for_each(|p| p.folded = true);
+                let started = self.clock.unix_time();
+                self.emit(GameEvent::RoundStarted {
+                    game: self.uuid,
+                    round: self.num_rounds + 1,
+                    started,
+                    dealer: self.dealer.clone().unwrap_or_default(),
+                    players: self.active_bank_rolls(),
+                    small_blind: self.small_blind,
+                    big_blind: self.big_blind,
+                    ante: self.ante,
+                    betting_structure: self.config.betting_structure,
+                });
+                if self.config.provably_fair {
+                    self.commit_shuffle();
+                }
+                self.bomb_pot = self
+                    .config
+                    .bomb_pot
+                    .is_some_and(|rules| (self.num_rounds + 1).is_multiple_of(rules.every));
+                if self.bomb_pot {
+                    self.post_bomb_pot();
+                } else {
+                    self.post_blinds();
+                    self.offer_straddle();
+                }
+                self.announce_players()?;
+                self.stage = Stage::Hole;
+                self.deal_hole_cards()?;
+            }
+            Stage::Hole => {
+                self.stage = Stage::PreFlop;
+                // There is no betting before the flop in a bomb pot.
+                if !self.bomb_pot {
+                    self.start_betting();
+                }
+            }
+            Stage::PreFlop => {
+                self.stage = Stage::Flop;
+                self.deal_flop()?;
+                self.start_betting();
+            }
+            Stage::Flop => {
+                self.stage = Stage::Turn;
+                self.deal_turn()?;
+                self.start_betting();
+            }
+            Stage::Turn => {
+                self.stage = Stage::River;
+                self.deal_river()?;
+                self.start_betting();
+            }
+            Stage::River => {
+                self.stage = Stage::ShowDown;
+                self.collect_side_pots();
+                self.deal_second_board()?;
+                self.showdown()?;
+                self.distribute_pots()?;
+                // announce the winner.
+                self.announce_winner_round()?;
+                self.reveal_shuffle();
+            }
+            Stage::ShowDown => {
+                self.reset_after_round()?;
+                self.num_rounds += 1;
+                self.stage = Stage::Blinds;
+            }
+        }
+        Ok(None)
+    }
 
-                    let comparison_result = compare::compare_hands(
-                        PlayerHand {
-                            name: challenger_name.clone(),
-                            hand: challenger_hand.clone(),
-                            cards: challenger_cards.clone(),
-                        },
-                        PlayerHand {
-                            name: w_name_benchmark.clone(),
-                            hand: w_hand_benchmark.clone(),
-                            cards: w_cards_benchmark.clone(),
-                        },
-                    );
+    /// Determine the winner at the end of the game, which is the only player left at
+    /// the table.
+    fn get_winner(&self) -> Result<String, GameError> {
+        self.players_order
+            .first()
+            .cloned()
+            .ok_or(GameError::NotEnoughPlayers)
+    }
 
-                    // Put the benchmark hand back for future comparisons or draw outcome
-                    draw_winners.push(PlayerHand {
-                        name: w_name_benchmark,
-                        hand: w_hand_benchmark.clone(),
-                        cards: w_cards_benchmark,
-                    });
+    /// Announce the players at the beginning of a round.
+    fn announce_players(&self) -> Result<(), GameError> {
+        let players = self.bank_rolls();
+        let dealer = self.dealer.clone().ok_or(GameError::NotEnoughPlayers)?;
+        let msg = Msg::PlayersInfo { players, dealer };
+        self.update_players(&msg);
+        Ok(())
+    }
 
-                    match comparison_result {
-                        Winner::SoleWinner(PlayerHand {
-                            name: n,
-                            hand: h,
-                            cards: c,
-                        }) => {
-                            if n == challenger_name {
-                                // Challenger is better than the benchmark (and thus all previous winners)
-                                Winner::SoleWinner(PlayerHand {
-                                    name: n,
-                                    hand: h,
-                                    cards: c,
-                                })
-                            } else {
-                                // Challenger is worse than the benchmark, keep the existing draw group
-                                Winner::Draw(draw_winners)
-                            }
-                        }
-                        Winner::Draw(_) => {
-                            // Challenger ties with the benchmark, add challenger to the draw group.
-                            draw_winners.push(PlayerHand {
-                                name: challenger_name,
-                                hand: challenger_hand,
-                                cards: challenger_cards,
-                            });
-                            Winner::Draw(draw_winners)
-                        }
-                    }
-                }
-            };
+    /// The players in seat order with their bank rolls.
+    pub fn bank_rolls(&self) -> Vec<(String, usize)> {
+        self.players_order
+            .iter()
+            .map(|name| (name.clone(), self.players[name].bank_roll))
+            .collect()
+    }
+
+    /// Announce the winner at the end of the round.
+    fn announce_winner_round(&self) -> Result<(), GameError> {
+        let w = self.winner.as_ref().ok_or(GameError::NoWinner)?;
+        let msg = Msg::RoundWinner(w.clone());
+        self.update_players(&msg);
+        self.emit(GameEvent::RoundWinner(w.clone()));
+        Ok(())
+    }
+
+    /// Set the name of the dealer and reorder the players_order list
+    /// so that the player to the left of the dealer is at the front
+    /// and the dealer is at the back.
+    /// The button moves on past players who are sitting out.
+    fn order_players(&mut self) -> Result<(), GameError> {
+        if self.players_order.is_empty() {
+            return Err(GameError::NotEnoughPlayers);
         }
-        winner
+        let n = self.players_order.len();
+        let pos = self
+            .dealer
+            .as_ref()
+            .and_then(|dealer| self.players_order.iter().position(|name| name == dealer))
+            .unwrap_or(0);
+        let pos = (0..n)
+            .map(|i| (pos + i) % n)
+            .find(|i| !self.players[&self.players_order[*i]].sitting_out)
+            .unwrap_or(pos);
+        self.dealer = Some(self.players_order[pos].clone());
+        self.players_order = rotate_vector(&self.players_order, pos + 1);
+        Ok(())
     }
 
-    /// Distributes the pot and side pot to the winner(s).
-    ///
-    /// TODO
-    /// + refactor this into several smaller methods,
-    fn distribute_pots(&mut self) {
-        let winner = self.winner.clone();
-        let main_pot = self.pot;
-        let side_pots = self.side_pots.clone();
-        let ccards = self.community_cards.clone();
-        // details of not folded players: names, bests hands, full sets of cards and whether they are all in
-        let not_folded: Vec<(PlayerHand, bool)> = self
-            .players
-            .values()
-            .filter(|p| !p.folded)
-            .map(|p| {
-                let (c1, c2) = p.hole.unwrap();
-                let mut cards = ccards.clone();
-                cards.extend(vec![c1, c2]);
-                (
-                    PlayerHand {
-                        name: p.name.clone(),
-                        hand: compare::best_hand(&cards),
-                        cards,
-                    },
-                    p.all_in,
-                )
-            })
-            .collect();
-        // not folded and not all in
-        let not_all_in: Vec<PlayerHand> = not_folded
-            .iter()
-            .filter(|(_ph, all_in)| !all_in)
-            .map(
-                |(
-                    PlayerHand {
-                        name,
-                        hand: best_hand,
-                        cards,
-                    },
-                    _all_in,
-                )| PlayerHand {
-                    name: name.to_owned(),
-                    hand: best_hand.to_owned(),
-                    cards: cards.clone(),
-                },
-            )
-            .collect();
-        let not_folded_clone = not_folded.clone();
-        // store winnings during distribution algorithm, allocate at end
-        let mut winnings: HashMap<String, usize> = HashMap::new();
-        for (ph, _b) in not_folded {
-            winnings.insert(ph.name, 0);
-        }
-        if let Some(w) = winner {
-            match w {
-                Winner::SoleWinner(PlayerHand { name, .. }) => {
-                    let winner_name = name.clone();
-                    if not_folded_clone.iter().any(|(ph, _all_in)| ph.name == name) {
-                        // winner is not folded
-                        // distribute the main pot
-                        *winnings.get_mut(&winner_name).unwrap() += main_pot;
-                        if not_all_in.iter().any(|ph| ph.name == winner_name) {
-                            // winner is not all in, they win the side pots too
-                            let side_pots: usize = self.side_pots.iter().map(|sp| sp.pot).sum();
-                            *winnings.get_mut(&winner_name).unwrap() += side_pots;
-                        } else {
-                            // winner is all in, they only win side pots they contributed to
-                            // distribute side pots
-                            for sp in side_pots {
-                                // possible winners
-                                let candidates: Vec<PlayerHand> = not_folded_clone
-                                    .iter()
-                                    .filter(|(ph, _all_in)| sp.players.contains(&ph.name))
-                                    .map(|(ph, _all_in)| PlayerHand {
-                                        name: ph.name.to_owned(),
-                                        hand: ph.hand.to_owned(),
-                                        cards: ph.cards.to_owned(),
-                                    })
-                                    .collect();
-                                if candidates.is_empty() {
-                                    // everyone in this side pot has folded so the winnings go to the winner of the main pot
-                                    *winnings.get_mut(&winner_name).unwrap() += sp.pot;
-                                } else {
-                                    // players who participated in this side pot are still in the round
-                                    let w = Game::determine_winner(candidates);
-                                    match w {
-                                        // single winner for this side pot
-                                        Winner::SoleWinner(PlayerHand { name, .. }) => {
-                                            *winnings.get_mut(&name).unwrap() += sp.pot;
-                                        }
-                                        // multiple winners for this side pot
-                                        Winner::Draw(winners) => {
-                                            let pot_share = sp.pot / winners.len();
-                                            *winnings.get_mut(&name).unwrap() += pot_share;
-                                        }
-                                    }
-                                }
-                            }
-                        }
-                    } else {
-                        panic!("Winner not in not_folded.");
-                    }
+    /// Give back the chips bet in an unfinished round and get ready to play it again
+    /// with the same dealer.
+    fn abandon_round(&mut self, error: &GameError) {
+        self.emit(GameEvent::RoundAbandoned(error.clone()));
+        self.reveal_shuffle();
+        self.players.values_mut().for_each(|p| {
+            p.bank_roll += p.total_bet;
+            p.bet = 0;
+            p.total_bet = 0;
+            p.all_in = false;
+            p.folded = false;
+            p.hole = None;
+        });
+        self.pot = 0;
+        self.side_pots = Vec::new();
+        self.community_cards = Vec::new();
+        self.betting = None;
+        self.runout = false;
+        self.second_board = None;
+        self.last_aggressor = None;
+        self.straddle = None;
+        self.bomb_pot = false;
+        self.stage = Stage::Blinds;
+        self.shuffle_deck();
+        // order_players moves the player after the dealer to the front, so put the
+        // dealer back at the front for the next attempt.
+        if let Some(dealer) = &self.dealer
+            && let Some(pos) = self.players_order.iter().position(|n| n == dealer)
+        {
+            self.players_order = rotate_vector(&self.players_order, pos);
+        }
+    }
+
+    /// The names of the players who pay the small and big blinds. Normally these are
+    /// the two players to the left of the dealer but when there are only two players
+    /// the dealer pays the small blind. Players sitting out are skipped.
+    fn blind_names(&self) -> (String, String) {
+        let active = self.active_players();
+        match active.len() {
+            0 => (String::new(), String::new()),
+            2 => (active[1].clone(), active[0].clone()),
+            n => (active[0].clone(), active[1 % n].clone()),
+        }
+    }
+
+    /// Every player pays the ante, if there is one, then the two players to the left of
+    /// the dealer pay the small and big blinds at the beginning of each round. If a player
+    /// has too few chips to pay the ante or blind, they pay all of their chips and are all
+    /// in for this round. Players sitting out don't pay, and players coming back after
+    /// missing their blinds post the big blind if the game is configured to make them.
+    fn post_blinds(&mut self) {
+        if self.players_order.is_empty() {
+            return;
+        }
+        if self.ante > 0 {
+            for name in self.active_players() {
+                if let Some(p) = self.players.get_mut(&name)
+                    && let Some(paid) = p.pay_ante(self.ante)
+                {
+                    self.pot += paid;
+                    self.emit(GameEvent::AntePosted {
+                        player: name,
+                        amount: paid,
+                    });
                 }
-                Winner::Draw(winners) => {
-                    // distribute main pot
-                    let main_pot_share = main_pot / winners.len();
-                    for PlayerHand {
-                        name,
-                        hand: _,
-                        cards: _,
-                    } in winners.clone()
-                    {
-                        *winnings.get_mut(&name).unwrap() += main_pot_share;
-                    }
-                    //distribute side pots
-                    for sp in side_pots {
-                        // possible winners
-                        let candidates: Vec<PlayerHand> = not_folded_clone
-                            .iter()
-                            .filter(|(ph, _all_in)| sp.players.contains(&ph.name))
-                            .map(|(ph, _all_in)| PlayerHand {
-                                name: ph.name.to_owned(),
-                                hand: ph.hand.to_owned(),
-                                cards: ph.cards.to_owned(),
-                            })
-                            .collect();
-                        if candidates.is_empty() {
-                            // everyone who contributed to this side pot has folded, the winners share the pot
-                            for PlayerHand {
-                                name,
-                                hand: _,
-                                cards: _,
-                            } in winners.clone()
-                            {
-                                *winnings.get_mut(&name).unwrap() += sp.pot;
-                            }
-                        } else {
-                            // there are unfolded players who contributed to this side pot
-                            let w = Game::determine_winner(candidates);
-                            match w {
-                                // single winner for this side pot
-                                Winner::SoleWinner(PlayerHand { name, .. }) => {
-                                    *winnings.get_mut(&name).unwrap() += sp.pot;
-                                }
-                                // multiple winners for this side pot
-                                Winner::Draw(winners) => {
-                                    let pot_share = sp.pot / winners.len();
-                                    for PlayerHand { name, .. } in winners {
-                                        *winnings.get_mut(&name).unwrap() += pot_share;
-                                    }
-                                }
-                            }
-                        }
-                    }
+            }
+        }
+        let (small, big) = self.blind_names();
+        if self.config.post_missed_blinds {
+            // The blinds passed the players sitting out between the dealer and the
+            // big blind.
+            let big_pos = self.players_order.iter().position(|n| *n == big);
+            for name in &self.players_order[..big_pos.unwrap_or(0)] {
+                if let Some(p) = self.players.get_mut(name)
+                    && p.sitting_out
+                {
+                    p.missed_blinds = true;
                 }
             }
-            // distribute winnings
-            for (name, pot_share) in winnings.clone() {
-                if pot_share > 0 {
-                    self.players.get_mut(&name).unwrap().bank_roll += pot_share;
+        }
+        // Players coming back after missing their blinds post the big blind, unless
+        // they are paying one of the blinds anyway.
+        let mut missed = Vec::new();
+        for name in self.active_players() {
+            if let Some(p) = self.players.get_mut(&name)
+                && p.missed_blinds
+            {
+                p.missed_blinds = false;
+                if name != small && name != big {
+                    missed.push((name, self.big_blind, true));
                 }
             }
-            self.pot = 0;
-            self.side_pots = Vec::new();
-        } else {
-            dbg!("Distribute pots called with no winner set.");
+        }
+        for (name, blind, is_big) in [
+            (small, self.small_blind, false),
+            (big, self.big_blind, true),
+        ]
+        .into_iter()
+        .chain(missed)
+        {
+            // NB: player marks themself as folded if they have no chips
+            // or as all in if their bank roll was less than the blind.
+            if let Some(p) = self.players.get_mut(&name)
+                && let Some(paid) = p.ante_up(blind)
+            {
+                self.pot += paid;
+                self.emit(if is_big {
+                    GameEvent::BigBlindPosted {
+                        player: name,
+                        amount: paid,
+                    }
+                } else {
+                    GameEvent::SmallBlindPosted {
+                        player: name,
+                        amount: paid,
+                    }
+                });
+            }
         }
     }
 
-    /// Reset the Game and Players after a round.
-    fn reset_after_round(&mut self) {
-        self.pot = 0;
-        self.side_pots = Vec::new();
-        self.community_cards = Vec::new();
-        self.deck = card::new_deck();
-        let mut removed_names: Vec<String> = Vec::new();
+    /// Every player pays the bomb pot ante at the beginning of a bomb pot, instead of
+    /// the ante and blinds. A player with too few chips pays all of them and is all in.
+    fn post_bomb_pot(&mut self) {
+        let Some(rules) = self.config.bomb_pot else {
+            return;
+        };
+        self.update_players(&Msg::BombPot { ante: rules.ante });
+        self.emit(GameEvent::BombPot { ante: rules.ante });
+        for name in self.active_players() {
+            if let Some(p) = self.players.get_mut(&name)
+                && let Some(paid) = p.pay_ante(rules.ante)
+            {
+                self.pot += paid;
+                self.emit(GameEvent::AntePosted {
+                    player: name,
+                    amount: paid,
+                });
+            }
+        }
+    }
 
-        // Loop through the players resetting all_in and folded, and collecting
-        // the list of ones that need to be removed for lack of money.
-        self.players_order.iter().for_each(|name| {
-            let p = self
-                .players
-                .get_mut(name)
-                .expect("Player in order list not found in map.");
-            if p.bank_roll == 0 {
-                removed_names.push(name.clone());
-            } else {
-                p.all_in = false;
-                p.folded = false;
-                p.hole = None;
+    /// Offer the straddle to the player under the gun or on the button, if the game
+    /// has one and there are at least three players. The straddle is twice the big
+    /// blind, and is posted before the cards are dealt.
+    fn offer_straddle(&mut self) {
+        let Some(straddle) = self.config.straddle else {
+            return;
+        };
+        let active = self.active_players();
+        if active.len() < 3 {
+            return;
+        }
+        let name = match straddle {
+            Straddle::UnderTheGun => active[2].clone(),
+            Straddle::Button => active[active.len() - 1].clone(),
+        };
+        let amount = 2 * self.big_blind;
+        let Some(p) = self.players.get_mut(&name) else {
+            return;
+        };
+        if p.folded || p.all_in || !p.offer_straddle(amount) {
+            return;
+        }
+        if let Some(paid) = p.ante_up(amount) {
+            self.pot += paid;
+            self.straddle = Some(name.clone());
+            self.update_players(&Msg::Straddle {
+                player: name.clone(),
+                amount: paid,
+            });
+            self.emit(GameEvent::StraddlePosted {
+                player: name,
+                amount: paid,
+            });
+        }
+    }
+
+    /// Take num cards from the deck.
+    fn take_cards(&mut self, num: usize) -> Result<Vec<Card>, GameError> {
+        if self.deck.len() < num {
+            Err(GameError::NotEnoughCards {
+                needed: num,
+                remaining: self.deck.len(),
+            })
+        } else {
+            let cards: Vec<Card> = self.deck[0..num].to_vec();
+            self.deck = self.deck[num..].to_vec();
+            Ok(cards)
+        }
+    }
+
+    /// Burn a card.
+    fn burn_card(&mut self) -> Result<(), GameError> {
+        match self.deck.pop() {
+            Some(card) => {
+                self.emit(GameEvent::CardBurned(card));
+                Ok(())
             }
+            None => Err(GameError::NotEnoughCards {
+                needed: 1,
+                remaining: 0,
+            }),
+        }
+    }
+
+    /// Deal two hole cards to each player.
+    fn deal_hole_cards(&mut self) -> Result<(), GameError> {
+        let active = self.active_players();
+        let mut hole_cards = self.take_cards(2 * active.len())?;
+        // Deal in seat order, so that games with the same seed deal the same cards.
+        for name in active {
+            let (Some(hole_1), Some(hole_2)) = (hole_cards.pop(), hole_cards.pop()) else {
+                return Err(GameError::NotEnoughCards {
+                    needed: 2,
+                    remaining: 0,
+                });
+            };
+            self.player_mut(&name)?.hole_cards((hole_1, hole_2));
+            self.emit(GameEvent::HoleCardsDealt {
+                player: name,
+                cards: (hole_1, hole_2),
+            });
+        }
+        Ok(())
+    }
+
+    /// Burn one card and deal the first three three community cards.
+    fn deal_flop(&mut self) -> Result<(), GameError> {
+        self.burn_card()?;
+        let mut flop_cards: Vec<Card> = self.take_cards(3)?;
+        self.emit(GameEvent::CommunityCardsDealt {
+            stage: self.stage,
+            cards: flop_cards.clone(),
         });
+        self.community_cards.append(flop_cards.as_mut());
+        Ok(())
+    }
 
-        // remove player names from self.players_order and Player structs from self.player.
-        removed_names.iter().for_each(|name| {
-            if let Some(index) = self.players_order.iter().position(|value| value == name) {
-                self.players_order.remove(index);
-            }
-            self.players.remove(name);
+    /// Burn one card and deal the fourth community card.
+    fn deal_turn(&mut self) -> Result<(), GameError> {
+        self.burn_card()?;
+        let mut turn_card: Vec<Card> = self.take_cards(1)?;
+        self.emit(GameEvent::CommunityCardsDealt {
+            stage: self.stage,
+            cards: turn_card.clone(),
+        });
+        self.community_cards.append(turn_card.as_mut());
+        Ok(())
+    }
+
+    /// Burn one card and deal the fifth and final community card.
+    fn deal_river(&mut self) -> Result<(), GameError> {
+        self.burn_card()?;
+        let mut river_card: Vec<Card> = self.take_cards(1)?;
+        self.emit(GameEvent::CommunityCardsDealt {
+            stage: self.stage,
+            cards: river_card.clone(),
+        });
+        self.community_cards.append(river_card.as_mut());
+        Ok(())
+    }
+
+    /// The order in which players act in the current stage. Before the flop the
+    /// player to the left of the big blind acts first, so that the big blind acts
+    /// last. On later streets the first player to the left of the dealer acts first.
+    fn action_order(&self) -> Vec<String> {
+        let first = if self.stage == Stage::PreFlop {
+            let last = match &self.straddle {
+                Some(straddle) => straddle.clone(),
+                None => self.blind_names().1,
+            };
+            self.players_order
+                .iter()
+                .position(|n| *n == last)
+                .map_or(0, |i| i + 1)
+        } else {
+            0
+        };
+        rotate_vector(&self.players_order, first)
+    }
+
+    /// Start a round of betting in the current stage. Players are given the opportunity
+    /// to bet, in the order given by `action_order`. Every player who is neither folded
+    /// nor all in acts at least once, which gives the big blind the option to raise when
+    /// the other players have only called. When a player raises the bet, every other
+    /// active player must respond (fold, call or raise again). The round of betting ends
+    /// when all active players have matched the highest bet or are all in.
+    fn start_betting(&mut self) {
+        self.last_aggressor = None;
+        let order = self.action_order();
+        if !order.iter().any(|name| !self.players[name].folded) {
+            return;
+        }
+
+        let update = Msg::StageDeclare(self.stage, self.community_cards.clone());
+        self.update_players(&update);
+        if self.runout {
+            return;
+        }
+
+        let to_act: VecDeque<String> = order
+            .iter()
+            .filter(|name| {
+                let p = &self.players[*name];
+                !p.folded && !p.all_in
+            })
+            .cloned()
+            .collect();
+        // Before the flop the highest bet is the big blind.
+        let highest: usize = self.players.values().map(|p| p.bet).max().unwrap_or(0);
+        self.betting = Some(Betting {
+            order,
+            to_act,
+            highest,
+            min: highest.max(self.big_blind),
+            cycle: 0,
+            acted: Vec::new(),
+            capped: Vec::new(),
         });
+    }
+
+    /// The next player who needs to bet in the current round of betting, skipping
+    /// players who can't or don't need to act. Returns None when the betting is over.
+    fn next_decision(&mut self) -> Option<PendingDecision> {
+        let betting = self.betting.as_mut()?;
+        while let Some(name) = betting.to_act.front() {
+            let in_hand = self.players.values().filter(|p| !p.folded).count();
+            if in_hand < 2 {
+                betting.to_act.clear();
+                break;
+            }
+            let others_can_bet = self
+                .players
+                .values()
+                .any(|p| &p.name != name && !p.folded && !p.all_in);
+            let p = &self.players[name];
+            let call = betting.highest.saturating_sub(p.bet);
+            // Skip players with nobody left to bet against.
+            if p.folded || p.all_in || (call == 0 && !others_can_bet) {
+                betting.to_act.pop_front();
+                continue;
+            }
+            let name = name.clone();
+            let cycle = betting.cycle;
+            let (min, max) = self.raise_limits(&name, call);
+            let args = BetArgs {
+                call,
+                min,
+                max,
+                stage: self.stage,
+                cycle,
+                community_cards: self.community_cards.clone(),
+            };
+            return Some(PendingDecision::Bet { player: name, args });
+        }
+        None
+    }
+
+    /// The smallest and largest amounts by which the named player can raise the bet,
+    /// given the amount they need to call, in the game's betting structure. The largest
+    /// is None if there is no limit, and zero if the bet can't be raised again.
+    fn raise_limits(&self, name: &str, call: usize) -> (usize, Option<usize>) {
+        let Some(betting) = &self.betting else {
+            return (0, Some(0));
+        };
+        if betting.capped.iter().any(|n| n == name) {
+            return (betting.min, Some(0));
+        }
+        match self.config.betting_structure {
+            BettingStructure::NoLimit => (betting.min, None),
+            BettingStructure::PotLimit => (betting.min, Some(self.pot + call)),
+            BettingStructure::FixedLimit {
+                small_bet,
+                big_bet,
+                cap,
+            } => {
+                let size = match self.stage {
+                    Stage::Turn | Stage::River => big_bet,
+                    _ => small_bet,
+                };
+                // Before the flop the big blind, and any straddle, count as bets.
+                let blinds = if self.stage == Stage::PreFlop {
+                    1 + u8::from(self.straddle.is_some())
+                } else {
+                    0
+                };
+                if betting.cycle.saturating_add(blinds) >= cap {
+                    (size, Some(0))
+                } else {
+                    (size, Some(size))
+                }
+            }
+        }
+    }
+
+    /// Check that a bet keeps to the limits on raising, given the amount to call. A
+    /// player can go all in for less than the minimum raise, but not for more than
+    /// the maximum.
+    fn check_raise(&self, name: &str, bet: Bet, call: usize) -> Result<(), GameError> {
+        let Some(p) = self.players.get(name) else {
+            return Err(GameError::UnknownPlayer(name.to_string()));
+        };
+        let chips = match bet {
+            Bet::Raise(n) => n.min(p.bank_roll),
+            Bet::AllIn(_) => p.bank_roll,
+            _ => 0,
+        };
+        if chips <= call {
+            return Ok(());
+        }
+        let raise = chips - call;
+        let (min, max) = self.raise_limits(name, call);
+        let reason = match max {
+            Some(0) => "the bet can't be raised any more".to_string(),
+            Some(max) if raise > max => format!("the most the bet can be raised by is {}", max),
+            _ if raise < min && chips < p.bank_roll => {
+                format!("the least the bet can be raised by is {}", min)
+            }
+            _ => return Ok(()),
+        };
+        Err(GameError::IllegalAction {
+            player: name.to_string(),
+            bet,
+            reason,
+        })
+    }
+
+    /// Finish a round of betting.
+    fn end_betting(&mut self) {
+        self.betting = None;
+        self.players.values_mut().for_each(|p| p.bet = 0);
+    }
+
+    /// Divide the chips in the pot into a main pot, which every player who has not
+    /// folded can win, and side pots which can only be won by the players who paid
+    /// into them. A new level is created each time a player went all in for less than
+    /// the other players contributed.
+    fn collect_side_pots(&mut self) {
+        let mut levels: Vec<usize> = self
+            .players
+            .values()
+            .filter(|p| p.all_in && !p.folded)
+            .map(|p| p.total_bet)
+            .collect();
+        let top = self
+            .players
+            .values()
+            .map(|p| p.total_bet)
+            .max()
+            .unwrap_or(0);
+        levels.push(top);
+        levels.sort();
+        levels.dedup();
+
+        let mut pots: Vec<SidePot> = Vec::new();
+        let mut floor = 0;
+        for level in levels {
+            let pot: usize = self
+                .players
+                .values()
+                .map(|p| p.total_bet.clamp(floor, level) - floor)
+                .sum();
+            let players: Vec<String> = self
+                .players_order
+                .iter()
+                .filter(|name| {
+                    let p = &self.players[*name];
+                    !p.folded && p.total_bet >= level
+                })
+                .cloned()
+                .collect();
+            floor = level;
+            if pot == 0 {
+                continue;
+            }
+            // Chips in a level that no player still in the round reached belong to the
+            // level below.
+            match pots.last_mut() {
+                Some(last) if players.is_empty() => last.pot += pot,
+                _ => pots.push(SidePot { players, pot }),
+            }
+        }
+        if pots.is_empty() {
+            return;
+        }
+        let main = pots.remove(0);
+        self.pot = main.pot;
+        self.side_pots = pots;
+    }
+
+    /// Send a message to the players.
+    fn update_players(&self, update: &Msg) {
+        self.players.values().for_each(|p| {
+            p.update(update);
+        });
+    }
+
+    /// Determines the winner(s) of the round. The players show their hands in turn,
+    /// starting with the last player to bet or raise on the river, or the first player
+    /// to the left of the dealer if everyone checked. A player whose hand is beaten by
+    /// one already shown, from a player who covers them, is asked whether to show it
+    /// or muck it, which gives up the pots. Hands that were revealed for a runout are
+    /// always shown.
+    fn showdown(&mut self) -> Result<(), GameError> {
+        // Get the best hand for each non-folded player.
+        let mut hands: Vec<PlayerHand> = self.names_to_hands(&self.showdown_order())?;
+        // Handle cases where 0 or 1 players remain (the last player standing wins)
+        if hands.len() < 2 {
+            if let Some(PlayerHand {
+                name,
+                hand: best_hand,
+                cards,
+            }) = hands.pop()
+            {
+                let winner = Winner::SoleWinner(PlayerHand {
+                    name,
+                    hand: best_hand,
+                    cards,
+                });
+                self.winner = Some(winner);
+            } else {
+                return Err(GameError::NoWinner);
+            }
+            return Ok(());
+        }
+
+        let mut shown: Vec<PlayerHand> = Vec::new();
+        for ph in hands {
+            let total_bet = self.players[&ph.name].total_bet;
+            let beaten_by = shown
+                .iter()
+                .filter(|other| self.players[&other.name].total_bet >= total_bet)
+                .find(|other| {
+                    matches!(
+                        Game::determine_winner(vec![ph.clone(), (*other).clone()]),
+                        Ok(Winner::SoleWinner(w)) if w.name == other.name
+                    )
+                })
+                .cloned();
+            let muck = match beaten_by {
+                Some(best) if !self.runout => self
+                    .players
+                    .get_mut(&ph.name)
+                    .is_some_and(|p| !p.offer_show(&best)),
+                _ => false,
+            };
+            if muck {
+                self.update_players(&Msg::HandMucked {
+                    player: ph.name.clone(),
+                });
+                self.emit(GameEvent::HandMucked { player: ph.name });
+            } else {
+                self.update_players(&Msg::HandShown(ph.clone()));
+                shown.push(ph);
+            }
+        }
+        self.emit(GameEvent::Showdown(shown.clone()));
+        let winner = Game::determine_winner(shown)?;
+        self.winner = Some(winner);
+        Ok(())
+    }
+
+    /// The players still in the round in the order they show their hands: the last
+    /// aggressor first, then around the table.
+    fn showdown_order(&self) -> Vec<String> {
+        let first = self
+            .last_aggressor
+            .as_ref()
+            .and_then(|name| self.players_order.iter().position(|n| n == name))
+            .unwrap_or(0);
+        rotate_vector(&self.players_order, first)
+    }
+
+    /// Whether the players still in the round can't bet any more, because all of them
+    /// or all but one are all in.
+    fn betting_finished(&self) -> bool {
+        self.players
+            .values()
+            .filter(|p| !p.folded && !p.all_in)
+            .count()
+            < 2
+    }
+
+    /// Start dealing the rest of the board without betting. The hands of the players
+    /// still in the round are shown, and if the game allows it they're asked whether
+    /// to run it twice, which they all have to agree to.
+    fn start_runout(&mut self) {
+        self.runout = true;
+        let hands: Vec<(String, (Card, Card))> = self
+            .players_order
+            .iter()
+            .filter_map(|name| {
+                let p = &self.players[name];
+                p.hole
+                    .filter(|_| !p.folded)
+                    .map(|hole| (name.clone(), hole))
+            })
+            .collect();
+        self.update_players(&Msg::HandsRevealed(hands.clone()));
+        self.emit(GameEvent::HandsRevealed(hands.clone()));
+        if self.config.run_it_twice
+            && hands.iter().all(|(name, _)| {
+                self.players
+                    .get_mut(name)
+                    .is_some_and(|p| p.offer_run_it_twice())
+            })
+        {
+            self.second_board = Some(self.community_cards.clone());
+        }
+    }
+
+    /// Deal the rest of the second board, if the players are running it twice, burning
+    /// cards as usual.
+    fn deal_second_board(&mut self) -> Result<(), GameError> {
+        let Some(mut board) = self.second_board.take() else {
+            return Ok(());
+        };
+        while board.len() < 5 {
+            self.burn_card()?;
+            let num = if board.is_empty() { 3 } else { 1 };
+            board.append(&mut self.take_cards(num)?);
+        }
+        self.update_players(&Msg::SecondBoard(board.clone()));
+        self.emit(GameEvent::SecondBoardDealt(board.clone()));
+        self.second_board = Some(board);
+        Ok(())
+    }
+
+    /// The only player who hasn't folded, if everyone else has.
+    fn last_player_standing(&self) -> Option<String> {
+        let mut in_hand = self
+            .players_order
+            .iter()
+            .filter(|name| !self.players[*name].folded);
+        match (in_hand.next(), in_hand.next()) {
+            (Some(name), None) => Some(name.clone()),
+            _ => None,
+        }
+    }
+
+    /// End a round in which everyone but one player folded, without a showdown. The
+    /// part of the player's bet that nobody called is returned to them and they win
+    /// the rest of the pot without showing their cards. If the game has a rabbit hunt,
+    /// the community cards that would have come are shown afterwards.
+    fn win_uncontested(&mut self, name: &str) -> Result<(), GameError> {
+        let called = self
+            .players
+            .values()
+            .filter(|p| p.name != name)
+            .map(|p| p.total_bet)
+            .max()
+            .unwrap_or(0);
+        let p = self.players.get_mut(name).ok_or(GameError::NoWinner)?;
+        let uncalled = p.total_bet.saturating_sub(called);
+        p.bank_roll += uncalled;
+        p.total_bet -= uncalled;
+        if uncalled > 0 {
+            self.pot -= uncalled;
+            self.update_players(&Msg::UncalledBet {
+                player: name.to_string(),
+                amount: uncalled,
+            });
+            self.emit(GameEvent::UncalledBetReturned {
+                player: name.to_string(),
+                amount: uncalled,
+            });
+        }
+        let mut pots = vec![SidePot {
+            players: vec![name.to_string()],
+            pot: self.pot,
+        }];
+        self.take_rake(&mut pots);
+        let pot = pots[0].pot;
+        self.award_pot(0, pot, vec![name.to_string()]);
+        self.pot = 0;
+        // The winner doesn't have to show their hand, so it isn't kept.
+        self.winner = None;
+        self.update_players(&Msg::RoundWonUncontested {
+            player: name.to_string(),
+            pot,
+        });
+        self.emit(GameEvent::RoundWonUncontested {
+            player: name.to_string(),
+            pot,
+        });
+        if self.config.rabbit_hunt {
+            self.rabbit_hunt()?;
+        }
+        Ok(())
+    }
+
+    /// Deal the community cards that would have come, burning cards as usual, and show
+    /// them to the players. They don't count for anything.
+    fn rabbit_hunt(&mut self) -> Result<(), GameError> {
+        let mut cards: Vec<Card> = Vec::new();
+        while self.community_cards.len() + cards.len() < 5 {
+            self.burn_card()?;
+            let num = if self.community_cards.is_empty() && cards.is_empty() {
+                3
+            } else {
+                1
+            };
+            cards.append(&mut self.take_cards(num)?);
+        }
+        if !cards.is_empty() {
+            self.update_players(&Msg::RabbitHunt(cards.clone()));
+            self.emit(GameEvent::RabbitHunt(cards));
+        }
+        Ok(())
+    }
+
+    /// Takes a vector of player names and return a vector of PlayerHand objects.
+    /// Result contains only non-folded players, in the same order. Returns an error if
+    /// one of them hasn't been dealt their hole cards.
+    fn names_to_hands(&self, names: &[String]) -> Result<Vec<PlayerHand>, GameError> {
+        // Calculate the best hand for each non-folded player, in the order given so that
+        // ties are listed the same way every time.
+        names
+            .iter()
+            .filter_map(|name| self.players.get(name))
+            .filter_map(|p| {
+                // Only consider players who haven't folded
+                if p.folded {
+                    return None;
+                }
+                let Some((c1, c2)) = p.hole else {
+                    return Some(Err(GameError::NotEnoughCards {
+                        needed: 2,
+                        remaining: 0,
+                    }));
+                };
+                // Collect all 7 cards (2 hole + 5 community)
+                let mut all_cards = self.community_cards.clone();
+                all_cards.push(c1);
+                all_cards.push(c2);
+
+                let best_hand = compare::best_hand(&all_cards);
+                Some(Ok(PlayerHand {
+                    name: p.name.clone(),
+                    hand: best_hand,
+                    cards: all_cards,
+                }))
+            })
+            .collect()
+    }
+
+    /// Determine winner(s) from vector of (name, best_hand, cards) tuples. Returns an
+    /// error if there are no hands.
+    pub(crate) fn determine_winner(mut hands: Vec<PlayerHand>) -> Result<Winner, GameError> {
+        // Handle cases where 0 or 1 players remain (the last player standing wins)
+        if hands.len() < 2 {
+            if let Some(PlayerHand {
+                name,
+                hand: best_hand,
+                cards,
+            }) = hands.pop()
+            {
+                return Ok(Winner::SoleWinner(PlayerHand {
+                    name,
+                    hand: best_hand,
+                    cards,
+                }));
+            } else {
+                return Err(GameError::NoWinner);
+            }
+        }
+
+        // Compare the hands.
+
+        // Initialize winner with the first player's hand.
+        let mut winner: Winner = {
+            let PlayerHand {
+                name,
+                hand: best_hand,
+                cards,
+            } = hands.remove(0);
+            Winner::SoleWinner(PlayerHand {
+                name,
+                hand: best_hand,
+                cards,
+            })
+        };
+
+        // Compare current winner against all remaining hands.
+        for PlayerHand {
+            name: challenger_name,
+            hand: challenger_hand,
+            cards: challenger_cards,
+        } in hands
+        {
+            winner = match winner {
+                Winner::SoleWinner(PlayerHand {
+                    name: w_name,
+                    hand: w_hand,
+                    cards: w_cards,
+                }) => {
+                    // Compare the current winner (w_...) against the challenger (challenger_...)
+                    compare::compare_hands(
+                        PlayerHand {
+                            name: challenger_name,
+                            hand: challenger_hand,
+                            cards: challenger_cards,
+                        },
+                        PlayerHand {
+                            name: w_name,
+                            hand: w_hand,
+                            cards: w_cards,
+                        },
+                    )
+                }
+                Winner::Draw(mut draw_winners) => {
+                    // It's a draw, compare the challenger against the best hand in the draw group.
+                    // Assume the first element of a Draw is the benchmark.
+                    let Some(PlayerHand {
+                        name: w_name_benchmark,
+                        hand: w_hand_benchmark,
+                        cards: w_cards_benchmark,
+                    }) = draw_winners.pop()
+                    else {
+                        return Err(GameError::NoWinner);
+                    };
+
+                    let comparison_result = compare::compare_hands(
+                        PlayerHand {
+                            name: challenger_name.clone(),
+                            hand: challenger_hand.clone(),
+                            cards: challenger_cards.clone(),
+                        },
+                        PlayerHand {
+                            name: w_name_benchmark.clone(),
+                            hand: w_hand_benchmark.clone(),
+                            cards: w_cards_benchmark.clone(),
+                        },
+                    );
+
+                    // Put the benchmark hand back for future comparisons or draw outcome
+                    draw_winners.push(PlayerHand {
+                        name: w_name_benchmark,
+                        hand: w_hand_benchmark.clone(),
+                        cards: w_cards_benchmark,
+                    });
+
+                    match comparison_result {
+                        Winner::SoleWinner(PlayerHand {
+                            name: n,
+                            hand: h,
+                            cards: c,
+                        }) => {
+                            if n == challenger_name {
+                                // Challenger is better than the benchmark (and thus all previous winners)
+                                Winner::SoleWinner(PlayerHand {
+                                    name: n,
+                                    hand: h,
+                                    cards: c,
+                                })
+                            } else {
+                                // Challenger is worse than the benchmark, keep the existing draw group
+                                Winner::Draw(draw_winners)
+                            }
+                        }
+                        Winner::Draw(_) => {
+                            // Challenger ties with the benchmark, add challenger to the draw group.
+                            draw_winners.push(PlayerHand {
+                                name: challenger_name,
+                                hand: challenger_hand,
+                                cards: challenger_cards,
+                            });
+                            Winner::Draw(draw_winners)
+                        }
+                    }
+                }
+            };
+        }
+        Ok(winner)
+    }
+
+    /// Distributes the main pot and side pots. The main pot goes to the winner(s) of the
+    /// round. Each side pot goes to the best hand among the players who paid into it,
+    /// which is the winner of the round if they are one of those players. If everyone who
+    /// paid into a side pot has folded, it goes to the winner(s) of the round. When the
+    /// players ran it twice, half of each pot is won on each board, with any odd chip
+    /// going with the first.
+    /// Returns an error if the round has no winner.
+    fn distribute_pots(&mut self) -> Result<(), GameError> {
+        let winner = self.winner.clone().ok_or(GameError::NoWinner)?;
+        let mut pots = vec![SidePot {
+            players: self.players_order.clone(),
+            pot: self.pot,
+        }];
+        pots.append(&mut self.side_pots);
+        self.take_rake(&mut pots);
+        match self.second_board.take() {
+            Some(board) => {
+                let second: Vec<SidePot> = pots
+                    .iter_mut()
+                    .map(|sp| {
+                        let half = sp.pot / 2;
+                        sp.pot -= half;
+                        SidePot {
+                            players: sp.players.clone(),
+                            pot: half,
+                        }
+                    })
+                    .collect();
+                self.award_pots(pots, &winner)?;
+                let first_board = std::mem::replace(&mut self.community_cards, board);
+                let second_winner =
+                    Game::determine_winner(self.names_to_hands(&self.players_order)?)?;
+                self.update_players(&Msg::SecondBoardWinner(second_winner.clone()));
+                self.emit(GameEvent::SecondBoardWinner(second_winner.clone()));
+                self.award_pots(second, &second_winner)?;
+                self.community_cards = first_board;
+            }
+            None => self.award_pots(pots, &winner)?,
+        }
+        self.pot = 0;
+        Ok(())
+    }
+
+    /// Award each pot to the best hand among the players who paid into it, given the
+    /// winner of the round. The community cards are those of the board being played.
+    fn award_pots(&mut self, pots: Vec<SidePot>, winner: &Winner) -> Result<(), GameError> {
+        let round_winners: Vec<String> = match winner {
+            Winner::SoleWinner(ph) => vec![ph.name.clone()],
+            Winner::Draw(phs) => phs.iter().map(|ph| ph.name.clone()).collect(),
+        };
+        for (index, sp) in pots.into_iter().enumerate() {
+            let candidates: Vec<String> = sp
+                .players
+                .into_iter()
+                .filter(|name| self.players.get(name).is_some_and(|p| !p.folded))
+                .collect();
+            let mut winners: Vec<String> = round_winners
+                .iter()
+                .filter(|name| candidates.contains(name))
+                .cloned()
+                .collect();
+            if winners.is_empty() && !candidates.is_empty() {
+                winners = match Game::determine_winner(self.names_to_hands(&candidates)?)? {
+                    Winner::SoleWinner(ph) => vec![ph.name],
+                    Winner::Draw(phs) => phs.into_iter().map(|ph| ph.name).collect(),
+                };
+            }
+            if winners.is_empty() {
+                winners = round_winners.clone();
+            }
+            self.award_pot(index, sp.pot, winners);
+        }
+        Ok(())
+    }
+
+    /// Take the house's share of the pots, if the game has a rake, from the main pot
+    /// first and then from the side pots in order.
+    fn take_rake(&mut self, pots: &mut [SidePot]) {
+        let Some(rules) = self.config.rake else {
+            return;
+        };
+        let total = pots.iter().map(|sp| sp.pot).sum();
+        let mut rake = rules.rake(total, !self.community_cards.is_empty());
+        if rake == 0 {
+            return;
+        }
+        self.audit.rake += rake;
+        self.emit(GameEvent::RakeTaken { amount: rake });
+        for sp in pots.iter_mut() {
+            let taken = rake.min(sp.pot);
+            sp.pot -= taken;
+            rake -= taken;
+        }
+    }
+
+    /// Share a pot equally between its winners. Any odd chips go to the winners
+    /// closest to the left of the dealer.
+    fn award_pot(&mut self, index: usize, pot: usize, mut winners: Vec<String>) {
+        if pot == 0 || winners.is_empty() {
+            return;
+        }
+        winners.sort_by_key(|name| self.players_order.iter().position(|n| n == name));
+        let share = pot / winners.len();
+        let odd_chips = pot % winners.len();
+        for (i, name) in winners.into_iter().enumerate() {
+            let amount = if i < odd_chips { share + 1 } else { share };
+            if let Some(p) = self.players.get_mut(&name) {
+                p.bank_roll += amount;
+                self.emit(GameEvent::PotAwarded {
+                    pot: index,
+                    player: name,
+                    amount,
+                });
+            }
+        }
+    }
+
+    /// Reset the Game and Players after a round.
+    fn reset_after_round(&mut self) -> Result<(), GameError> {
+        self.pot = 0;
+        self.side_pots = Vec::new();
+        self.runout = false;
+        self.second_board = None;
+        self.last_aggressor = None;
+        self.straddle = None;
+        self.bomb_pot = false;
+        self.community_cards = Vec::new();
+        self.shuffle_deck();
+        let mut removed_names: Vec<String> = Vec::new();
+
+        // Loop through the players resetting all_in and folded, and collecting
+        // the list of ones that need to be removed for lack of money, unless they rebuy.
+        for name in self.players_order.clone() {
+            if self.players[&name].bank_roll == 0 && !self.offer_rebuy(&name) {
+                removed_names.push(name);
+                continue;
+            }
+            let p = self.player_mut(&name)?;
+            p.all_in = false;
+            p.folded = false;
+            p.hole = None;
+            p.bet = 0;
+            p.total_bet = 0;
+        }
+
+        // remove player names from self.players_order and Player structs from self.player.
+        removed_names.iter().for_each(|name| {
+            if let Some(index) = self.players_order.iter().position(|value| value == name) {
+                self.players_order.remove(index);
+            }
+            self.players.remove(name);
+            self.emit(GameEvent::PlayerEliminated {
+                player: name.clone(),
+            });
+        });
+
+        // Assign new dealer.
+        let dealer_name = self.dealer.clone().ok_or(GameError::NotEnoughPlayers)?;
+        if !self.players_order.is_empty() {
+            let dealer_index = self
+                .players_order
+                .iter()
+                .position(|n| n == &dealer_name)
+                .unwrap_or_default();
+            let players_order = self.players_order.clone();
+            self.dealer = Some(players_order[(dealer_index + 1) % players_order.len()].clone());
+        }
+        self.remove_leaving_players();
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::{
+        autoactor::AutoActor,
+        betting_strategy::BetArgs,
+        card::{BestHand, Card, Hand, Rank, Suit},
+        config::RebuyRules,
+        player::Actor,
+    };
+    use std::{cell::RefCell, rc::Rc, time::Duration};
+
+    // Build a game with the default buy in and small blind.
+    fn test_game(big_blind: usize, max_players: u8) -> Game {
+        let config = GameConfig::builder()
+            .big_blind(big_blind)
+            .max_players(max_players)
+            .build()
+            .unwrap();
+        Game::from_config(config).unwrap()
+    }
+
+    #[test]
+    fn test_build() {
+        let game = test_game(10, 5);
+        assert!(
+            game.deck.len() == 52,
+            "Expected game.deck to have 52 cards, was {}",
+            game.deck.len()
+        );
+    }
+
+    #[test]
+    fn test_seeded_games_deal_the_same_cards() {
+        let deal = |seed: u64| {
+            let config = GameConfig::builder()
+                .big_blind(20)
+                .max_players(3)
+                .seed(seed)
+                .build()
+                .unwrap();
+            let mut game = Game::from_config(config).unwrap();
+            for i in 1..=3 {
+                let _ = game.join(Player::build(&format!("player{}", i), AutoActor::new()));
+            }
+            game.order_players().unwrap();
+            game.deal_hole_cards().unwrap();
+            let first: Vec<Option<(Card, Card)>> = game
+                .players_order
+                .iter()
+                .map(|n| game.players[n].hole)
+                .collect();
+            // The deck is shuffled again for the next round.
+            game.reset_after_round().unwrap();
+            (first, game.deck.clone())
+        };
+        let (hole_a, deck_a) = deal(42);
+        let (hole_b, deck_b) = deal(42);
+        assert!(
+            hole_a == hole_b && deck_a == deck_b,
+            "Expected games with the same seed to deal the same cards"
+        );
+        let (hole_c, _) = deal(43);
+        assert!(
+            hole_a != hole_c,
+            "Expected games with different seeds to deal different cards"
+        );
+        assert!(
+            deck_a != card::new_deck(),
+            "Expected the deck to be shuffled after a round"
+        );
+    }
+
+    #[test]
+    fn test_starting_stacks() {
+        let config = GameConfig::builder()
+            .big_blind(20)
+            .max_players(2)
+            .starting_stack("player2", 500)
+            .build()
+            .unwrap();
+        let mut game = Game::from_config(config).unwrap();
+        let _ = game.join(Player::build("player1", AutoActor::new()));
+        let _ = game.join(Player::build("player2", AutoActor::new()));
+        assert!(
+            game.players["player1"].bank_roll == 2000 && game.players["player2"].bank_roll == 500,
+            "Expected bank rolls of 2000 and 500, was {} and {}",
+            game.players["player1"].bank_roll,
+            game.players["player2"].bank_roll
+        );
+    }
+
+    // Helpers for running parts of a round with the players' actors.
+    impl Game {
+        fn place_bets(&mut self) -> Result<(), GameError> {
+            self.start_betting();
+            while let Some(PendingDecision::Bet { player, args }) = self.next_decision() {
+                self.act(&player, args)?;
+            }
+            self.end_betting();
+            Ok(())
+        }
+
+        fn play_to_showdown(&mut self) -> Result<(), GameError> {
+            while self.stage != Stage::ShowDown {
+                if let Some(PendingDecision::Bet { player, args }) = self.step()? {
+                    self.act(&player, args)?;
+                }
+            }
+            Ok(())
+        }
+    }
+
+    #[test]
+    fn test_add_too_many_players() {
+        let mut game = test_game(10, 2);
+        let _ = game.join(Player::build("player1", AutoActor::new()));
+        let _ = game.join(Player::build("player2", AutoActor::new()));
+        if let Err(e) = game.join(Player::build("player3", AutoActor::new())) {
+            assert!(
+                e == GameError::TableFull,
+                "Expected GameError::TableFull, was {}",
+                e
+            );
+        } else {
+            panic!("Expected add_player to raise an error.");
+        }
+    }
+
+    #[test]
+    fn test_players_receive_bank_roll() {
+        let mut game = test_game(20, 2);
+        let _ = game.join(Player::build("player1", AutoActor::new()));
+        let _ = game.join(Player::build("player2", AutoActor::new()));
+        // each player should receive 100 x big blind
+        game.players.values().for_each(|p| {
+            assert!(
+                p.bank_roll == 100 * 20,
+                "Expected new player to receive {}, was {}",
+                100 * 20,
+                p.bank_roll
+            )
+        });
+    }
+
+    #[test]
+    fn test_deal_hole_cards() {
+        let mut game = test_game(20, 2);
+        let _ = game.join(Player::build("player1", AutoActor::new()));
+        let _ = game.join(Player::build("player2", AutoActor::new()));
+        game.deal_hole_cards().unwrap();
+        assert!(
+            game.deck.len() == 48,
+            "Expected game.deck.len() to be 48, was {}",
+            game.deck.len()
+        );
+        game.players.iter().for_each(|(name, p)| {
+            if let Some((c1, c2)) = p.hole {
+                assert!(
+                    !game.deck.contains(&c1),
+                    "Expected {:?} not to be in game.deck but it was.",
+                    c1
+                );
+                assert!(
+                    !game.deck.contains(&c2),
+                    "Expected {:?} not to be in game.deck but it was.",
+                    c2
+                );
+            } else {
+                panic!("Expected hole cards for player {}", name);
+            }
+        });
+    }
+
+    #[test]
+    fn test_place_bets_default_strategy() {
+        let mut game = test_game(20, 2);
+        let _ = game.join(Player::build("player1", AutoActor::new()));
+        let _ = game.join(Player::build("player2", AutoActor::build(test_strategy)));
+        game.order_players().unwrap();
+        game.deal_hole_cards().unwrap();
+
+        // Test with players that don't place bets.
+        // Both players will use default strategy and check.
+        // The pot should contain only the blinds.
+        game.place_bets().unwrap();
+        assert!(
+            game.pot == 40,
+            "Expected game.pot to be 40, was {}",
+            game.pot
+        );
+        game.players.iter().for_each(|(_name, p)| {
+            assert!(
+                p.bank_roll == 1980,
+                "Expected p.bank_roll to be 1980, was {}.",
+                p.bank_roll
+            );
+        });
+        game.place_bets().unwrap();
+        assert!(
+            game.pot == 80,
+            "Expected game.pot to be 80, was {}",
+            game.pot
+        );
+        game.players.iter().for_each(|(_name, p)| {
+            assert!(
+                p.bank_roll == 1960,
+                "Expected p.bank_roll to be 1960, was {}.",
+                p.bank_roll
+            );
+        });
+    }
+
+    // A betting strategy that will place a bet if the call is zero
+    fn test_strategy(args: BetArgs, _hole_cards: (Card, Card), bank_roll: usize) -> Bet {
+        if bank_roll == 0 {
+            Bet::Fold
+        } else if bank_roll <= args.call {
+            Bet::AllIn(bank_roll)
+        } else if args.call == 0 {
+            Bet::Raise(args.min)
+        } else {
+            Bet::Call
+        }
+    }
+
+    #[test]
+    fn test_place_bets_modest_strategy() {
+        let mut game = test_game(20, 2);
+        let _ = game.join(Player::build("player1", AutoActor::new()));
+        let _ = game.join(Player::build("player2", AutoActor::build(test_strategy)));
+        game.order_players().unwrap();
+        game.deal_hole_cards().unwrap();
+
+        game.place_bets().unwrap();
+        assert!(
+            game.pot == 40,
+            "Expected game.pot to be 40, was {}",
+            game.pot
+        );
+        game.players.iter().for_each(|(_name, p)| {
+            assert!(
+                p.bank_roll == 1980,
+                "Expected p.bank_roll to be 1980, was {}.",
+                p.bank_roll
+            );
+        });
+    }
+
+    #[test]
+    fn test_place_bets_folded_player() {
+        let mut game = test_game(20, 3);
+        let _ = game.join(Player::build("player1", AutoActor::new()));
+        let _ = game.join(Player::build("player2", AutoActor::new()));
+        let _ = game.join(Player::build("player3", AutoActor::build(test_strategy)));
+        game.order_players().unwrap();
+        game.deal_hole_cards().unwrap();
+
+        // Test with a player who bets once and one which is folded.
+
+        let p2 = game.players.get_mut("player2").unwrap();
+        p2.folded = true;
+
+        game.place_bets().unwrap();
+        assert!(
+            game.pot == 40,
+            "Expected game.pot to be 40, was {}",
+            game.pot
+        );
+        game.players.iter().for_each(|(_name, p)| {
+            if p.name == "player2" {
+                assert!(
+                    p.bank_roll == 2000,
+                    "Expected p.bank_roll to be 2000, was {}.",
+                    p.bank_roll
+                );
+            } else {
+                assert!(
+                    p.bank_roll == 1980,
+                    "Expected p.bank_roll to be 1980, was {}.",
+                    p.bank_roll
+                );
+            }
+        });
+    }
+
+    type BetLog = Rc<RefCell<Vec<(String, Bet)>>>;
+
+    // An actor that records the bets announced to it and bets using the supplied strategy.
+    #[derive(Debug)]
+    struct RecordingActor {
+        strategy: crate::poker::betting_strategy::BettingStrategy,
+        log: BetLog,
+    }
+    impl Actor for RecordingActor {
+        fn set_name_and_bank_roll(&self, _name: &str, _bank_roll: usize) {}
+        fn hole_cards(&self, _hole_cards: (Card, Card)) {}
+        fn place_bet(
+            &mut self,
+            args: BetArgs,
+            hole_cards: (Card, Card),
+            bank_roll: usize,
+        ) -> Option<Bet> {
+            Some((self.strategy)(args, hole_cards, bank_roll))
+        }
+        fn update(&self, msg: &Msg) {
+            if let Msg::Bet { player, bet, .. } = msg {
+                self.log.borrow_mut().push((player.clone(), *bet));
+            }
+        }
+    }
+
+    // Build a game where the first player records the bets made by everyone. The
+    // players in `raisers` use `test_strategy`, the others check or call.
+    fn recorded_game(num_players: u8, raisers: &[&str]) -> (Game, BetLog) {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let mut game = test_game(20, num_players);
+        for i in 1..=num_players {
+            let name = format!("player{}", i);
+            let strategy = if raisers.contains(&name.as_str()) {
+                test_strategy
+            } else {
+                crate::poker::betting_strategy::default_betting_strategy
+            };
+            if i == 1 {
+                let actor = RecordingActor {
+                    strategy,
+                    log: log.clone(),
+                };
+                let _ = game.join(Player::build(&name, actor));
+            } else {
+                let _ = game.join(Player::build(&name, AutoActor::build(strategy)));
+            }
+        }
+        game.order_players().unwrap();
+        game.post_blinds();
+        game.deal_hole_cards().unwrap();
+        (game, log)
+    }
+
+    fn bettors(log: &BetLog) -> Vec<String> {
+        log.borrow().iter().map(|(name, _)| name.clone()).collect()
+    }
+
+    #[test]
+    fn test_post_blinds() {
+        let (game, _log) = recorded_game(4, &[]);
+        // player1 is the dealer, so player2 and player3 pay the blinds.
+        assert!(
+            game.pot == 30,
+            "Expected game.pot to be 30, was {}",
+            game.pot
+        );
+        let p2 = &game.players["player2"];
+        let p3 = &game.players["player3"];
+        let p4 = &game.players["player4"];
+        assert!(p2.bank_roll == 1990, "Expected 1990, was {}", p2.bank_roll);
+        assert!(p3.bank_roll == 1980, "Expected 1980, was {}", p3.bank_roll);
+        assert!(p4.bank_roll == 2000, "Expected 2000, was {}", p4.bank_roll);
+    }
+
+    #[test]
+    fn test_preflop_action_order() {
+        let (mut game, log) = recorded_game(4, &[]);
+        game.stage = Stage::PreFlop;
+        game.place_bets().unwrap();
+        // UTG acts first, the big blind acts last.
+        let order = bettors(&log);
+        assert!(
+            order == vec!["player4", "player1", "player2", "player3"],
+            "Expected UTG first and big blind last, was {:?}",
+            order
+        );
+        assert!(
+            matches!(log.borrow().last(), Some((_, Bet::Check))),
+            "Expected the big blind to check, was {:?}",
+            log.borrow().last()
+        );
+        assert!(
+            game.pot == 80,
+            "Expected game.pot to be 80, was {}",
+            game.pot
+        );
+    }
+
+    #[test]
+    fn test_big_blind_option() {
+        let (mut game, log) = recorded_game(3, &["player3"]);
+        game.stage = Stage::PreFlop;
+        game.place_bets().unwrap();
+        // player1 (dealer) and player2 (small blind) limp, player3 (big blind) raises
+        // and the others call.
+        let order = bettors(&log);
+        assert!(
+            order == vec!["player1", "player2", "player3", "player1", "player2"],
+            "Expected the big blind to raise and the others to respond, was {:?}",
+            order
+        );
+        assert!(
+            game.pot == 120,
+            "Expected game.pot to be 120, was {}",
+            game.pot
+        );
+        game.players.values().for_each(|p| {
+            assert!(
+                p.bank_roll == 1960,
+                "Expected p.bank_roll to be 1960, was {}.",
+                p.bank_roll
+            );
+        });
+    }
+
+    #[test]
+    fn test_heads_up_action_order() {
+        let (mut game, log) = recorded_game(2, &[]);
+        // player1 is the dealer and pays the small blind.
+        assert!(
+            game.players["player1"].bank_roll == 1990,
+            "Expected the dealer to pay the small blind"
+        );
+        game.stage = Stage::PreFlop;
+        game.place_bets().unwrap();
+        game.stage = Stage::Flop;
+        game.place_bets().unwrap();
+        // The dealer acts first before the flop and last after it.
+        let order = bettors(&log);
+        assert!(
+            order == vec!["player1", "player2", "player2", "player1"],
+            "Expected dealer first preflop and last postflop, was {:?}",
+            order
+        );
+    }
+
+    #[test]
+    fn test_postflop_action_order() {
+        let (mut game, log) = recorded_game(4, &["player4"]);
+        game.players.values_mut().for_each(|p| p.bet = 0);
+        game.stage = Stage::Flop;
+        game.place_bets().unwrap();
+        // The first player to the left of the dealer acts first and betting continues
+        // until everyone has matched player4's bet.
+        let order = bettors(&log);
+        assert!(
+            order
+                == vec![
+                    "player2", "player3", "player4", "player1", "player2", "player3"
+                ],
+            "Expected action to start left of the dealer, was {:?}",
+            order
+        );
+    }
+
+    #[test]
+    fn test_collect_side_pots() {
+        let (mut game, _log) = recorded_game(3, &[]);
+        game.players.values_mut().for_each(|p| {
+            p.bank_roll = 0;
+            p.all_in = true;
+        });
+        game.players.get_mut("player1").unwrap().total_bet = 50;
+        game.players.get_mut("player2").unwrap().total_bet = 100;
+        game.players.get_mut("player3").unwrap().total_bet = 200;
+        game.pot = 350;
+        game.collect_side_pots();
+        assert!(
+            game.pot == 150,
+            "Expected main pot of 150, was {}",
+            game.pot
+        );
+        let side_pots: Vec<(Vec<String>, usize)> = game
+            .side_pots
+            .iter()
+            .map(|sp| (sp.players.clone(), sp.pot))
+            .collect();
+        assert!(
+            side_pots
+                == vec![
+                    (vec!["player2".to_string(), "player3".to_string()], 100),
+                    (vec!["player3".to_string()], 100)
+                ],
+            "Expected side pots of 100 and 100, was {:?}",
+            side_pots
+        );
+    }
+
+    #[test]
+    fn test_blind_schedule() {
+        use crate::poker::blinds::{BlindLevel, BlindSchedule, LevelDuration};
+        let (mut game, _log) = recorded_game(3, &[]);
+        let levels = vec![
+            BlindLevel {
+                small_blind: 10,
+                big_blind: 20,
+                ante: 0,
+                duration: LevelDuration::Hands(1),
+            },
+            BlindLevel {
+                small_blind: 20,
+                big_blind: 40,
+                ante: 5,
+                duration: LevelDuration::Hands(1),
+            },
+        ];
+        game.set_blind_schedule(BlindSchedule::build(levels).unwrap());
+        game.update_blinds();
+        assert!(
+            game.blinds() == (10, 20, 0),
+            "Expected the first level, was {:?}",
+            game.blinds()
+        );
+        game.num_rounds += 1;
+        game.update_blinds();
+        assert!(
+            game.blinds() == (20, 40, 5),
+            "Expected the second level, was {:?}",
+            game.blinds()
+        );
+
+        // Everyone pays the ante and the blinds are paid on top.
+        game.players.values_mut().for_each(|p| {
+            p.bank_roll = 1000;
+            p.bet = 0;
+            p.total_bet = 0;
+        });
+        game.pot = 0;
+        game.post_blinds();
+        assert!(
+            game.pot == 75,
+            "Expected game.pot to be 3 antes and the blinds (75), was {}",
+            game.pot
+        );
+        let bets: Vec<usize> = game
+            .players_order
+            .iter()
+            .map(|name| game.players[name].bet)
+            .collect();
+        assert!(
+            bets == vec![20, 40, 0],
+            "Expected antes not to count towards the bet, was {:?}",
+            bets
+        );
+    }
+
+    // An actor that always makes the same bet, or none at all.
+    #[derive(Debug)]
+    struct FixedActor(Option<Bet>);
+    impl Actor for FixedActor {
+        fn set_name_and_bank_roll(&self, _name: &str, _bank_roll: usize) {}
+        fn hole_cards(&self, _hole_cards: (Card, Card)) {}
+        fn place_bet(
+            &mut self,
+            _args: BetArgs,
+            _hole_cards: (Card, Card),
+            _bank_roll: usize,
+        ) -> Option<Bet> {
+            self.0
+        }
+        fn update(&self, _msg: &Msg) {}
+    }
+
+    fn fixed_game(bet: Option<Bet>) -> Game {
+        let mut game = test_game(20, 3);
+        for i in 1..=3 {
+            let _ = game.join(Player::build(&format!("player{}", i), FixedActor(bet)));
+        }
+        game
+    }
+
+    #[test]
+    fn test_illegal_check() {
+        let mut game = fixed_game(Some(Bet::Check));
+        let result = game.play();
+        assert!(
+            matches!(
+                result,
+                Err(GameError::IllegalAction {
+                    bet: Bet::Check,
+                    ..
+                })
+            ),
+            "Expected checking preflop to be illegal, was {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_actor_failure_refunds_round() {
+        let mut game = fixed_game(None);
+        let result = game.play();
+        assert!(
+            matches!(result, Err(GameError::ActorFailed { .. })),
+            "Expected the actor to fail, was {:?}",
+            result
+        );
+        let bank_rolls: Vec<usize> = game.players.values().map(|p| p.bank_roll).collect();
+        assert!(
+            game.pot == 0 && bank_rolls == vec![2000; 3],
+            "Expected the blinds to be returned, pot was {} and bank rolls were {:?}",
+            game.pot,
+            bank_rolls
+        );
+        assert!(
+            game.players.values().all(|p| p.hole.is_none()) && game.deck.len() == 52,
+            "Expected the cards to be collected and the deck reshuffled"
+        );
+    }
+
+    #[test]
+    fn test_errors_instead_of_panics() {
+        let mut game = fixed_game(Some(Bet::Call));
+        let result = Game::determine_winner(Vec::new());
+        assert!(
+            matches!(result, Err(GameError::NoWinner)),
+            "Expected no winner from no hands, was {:?}",
+            result
+        );
+        let result = game.distribute_pots();
+        assert!(
+            result == Err(GameError::NoWinner),
+            "Expected no winner before a showdown, was {:?}",
+            result
+        );
+        let result = game.names_to_hands(&game.players_order.clone());
+        assert!(
+            matches!(result, Err(GameError::NotEnoughCards { .. })),
+            "Expected no hands before the hole cards are dealt, was {:?}",
+            result.map(|hands| hands.len())
+        );
+        let args = BetArgs {
+            call: 20,
+            min: 20,
+            max: None,
+            stage: Stage::PreFlop,
+            cycle: 0,
+            community_cards: Vec::new(),
+        };
+        let result = game
+            .players
+            .get_mut("player1")
+            .unwrap()
+            .request_bet(args.clone());
+        assert!(
+            matches!(result, Err(GameError::NotEnoughCards { .. })),
+            "Expected no bet without hole cards, was {:?}",
+            result
+        );
+        let result = game.act("player9", args);
+        assert!(
+            matches!(result, Err(GameError::UnknownPlayer(ref name)) if name == "player9"),
+            "Expected an unknown player, was {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_advance_and_apply() {
+        let mut game = fixed_game(None);
+        let PendingDecision::Bet { player, args } = game.advance().unwrap() else {
+            panic!("Expected a bet to be needed");
+        };
+        // player1 is the dealer, and acts first when there are three players.
+        assert!(
+            player == "player1" && args.call == 20 && game.stage == Stage::PreFlop,
+            "Expected player1 to have 20 to call before the flop, was {} with {}",
+            player,
+            args.call
+        );
+        let result = game.apply("player2", Bet::Call);
+        assert!(
+            matches!(result, Err(GameError::IllegalAction { .. })),
+            "Expected betting out of turn to be illegal, was {:?}",
+            result
+        );
+        let result = game.apply("player1", Bet::Check);
+        assert!(
+            matches!(result, Err(GameError::IllegalAction { .. })),
+            "Expected checking to be illegal, was {:?}",
+            result
+        );
+        let again = game.advance().unwrap();
+        assert!(
+            matches!(&again, PendingDecision::Bet { player, .. } if player == "player1"),
+            "Expected player1 to bet again, was {:?}",
+            again
+        );
+        let msgs = game.apply("player1", Bet::Call).unwrap();
+        assert!(
+            matches!(
+                msgs.as_slice(),
+                [Msg::Bet {
+                    bet: Bet::Call,
+                    pot: 50,
+                    ..
+                }]
+            ),
+            "Expected a message for the call, was {:?}",
+            msgs
+        );
+
+        // Everyone calls or checks until the next round starts.
+        loop {
+            let decision = game.advance().unwrap();
+            if game.num_rounds > 0 {
+                break;
+            }
+            if let PendingDecision::Bet { player, args } = decision {
+                let bet = if args.call > 0 { Bet::Call } else { Bet::Check };
+                game.apply(&player, bet).unwrap();
+            }
+        }
+        let total: usize = game.players.values().map(|p| p.bank_roll).sum();
+        assert!(
+            total + game.pot == 6000 && game.stage == Stage::PreFlop,
+            "Expected the pot to be paid out and the next round to start, was {} and {:?}",
+            total + game.pot,
+            game.stage
+        );
+    }
+
+    /// The pot, stage and each player's chips and bet, to check that nothing changed.
+    fn table_state(game: &Game) -> (usize, Stage, Vec<(String, usize, usize)>) {
+        let mut players: Vec<(String, usize, usize)> = game
+            .players
+            .values()
+            .map(|p| (p.name.clone(), p.bank_roll, p.bet))
+            .collect();
+        players.sort();
+        (game.pot, game.stage, players)
+    }
+
+    #[test]
+    fn test_apply_out_of_turn() {
+        let mut game = fixed_game(None);
+        let (player, _) = next_bet(&mut game);
+        let before = table_state(&game);
+        for name in ["player2", "player3", "player9"] {
+            let result = game.apply(name, Bet::Call);
+            assert!(
+                matches!(&result, Err(GameError::IllegalAction { player, .. }) if player == name),
+                "Expected {} betting out of turn to be illegal, was {:?}",
+                name,
+                result
+            );
+        }
+        assert!(
+            table_state(&game) == before,
+            "Expected nothing to change, was {:?}",
+            table_state(&game)
+        );
+        let (again, _) = next_bet(&mut game);
+        assert!(
+            again == player,
+            "Expected {} to still need to bet, was {}",
+            player,
+            again
+        );
+    }
+
+    #[test]
+    fn test_apply_illegal_amount() {
+        let mut game = structured_game(BettingStructure::PotLimit, 3);
+        let (player, args) = next_bet(&mut game);
+        let before = table_state(&game);
+        // The least the bet can be raised by is the big blind, and the most is the pot
+        // after calling, 10 + 20 + 20, for a bet of 70.
+        for bet in [Bet::Raise(30), Bet::Raise(100)] {
+            let result = game.apply(&player, bet);
+            assert!(
+                matches!(result, Err(GameError::IllegalAction { .. })),
+                "Expected {:?} with {} to call to be illegal, was {:?}",
+                bet,
+                args.call,
+                result
+            );
+            assert!(
+                table_state(&game) == before,
+                "Expected nothing to change after {:?}, was {:?}",
+                bet,
+                table_state(&game)
+            );
+        }
+        let (again, again_args) = next_bet(&mut game);
+        assert!(
+            again == player && again_args.call == args.call && again_args.max == args.max,
+            "Expected {} to bet again with the same limits, was {} with {:?}",
+            player,
+            again,
+            again_args
+        );
+        game.apply(&player, Bet::Raise(70)).unwrap();
+        assert!(
+            game.pot == 100,
+            "Expected a raise to the pot to be allowed, pot was {}",
+            game.pot
+        );
+    }
+
+    #[test]
+    fn test_advance_twice() {
+        let (mut game, log) = table(
+            GameConfig::builder()
+                .big_blind(20)
+                .max_players(3)
+                .build()
+                .unwrap(),
+        );
+        let (player, args) = next_bet(&mut game);
+        let before = table_state(&game);
+        let events = log.events().len();
+        let (again, again_args) = next_bet(&mut game);
+        assert!(
+            again == player
+                && again_args.call == args.call
+                && again_args.min == args.min
+                && again_args.cycle == args.cycle,
+            "Expected the same decision, was {} with {:?} and then {} with {:?}",
+            player,
+            args,
+            again,
+            again_args
+        );
+        assert!(
+            table_state(&game) == before && log.events().len() == events,
+            "Expected nothing to happen, was {:?} with {} new events",
+            table_state(&game),
+            log.events().len() - events
+        );
+    }
+
+    /// The next bet needed in a game, with the player who needs to make it.
+    fn next_bet(game: &mut Game) -> (String, BetArgs) {
+        match game.advance().unwrap() {
+            PendingDecision::Bet { player, args } => (player, args),
+            PendingDecision::GameOver(w) => panic!("Expected the game to carry on, was {}", w),
+            PendingDecision::Waiting => panic!("Expected the game to carry on, was waiting"),
+        }
+    }
+
+    /// A game with the betting structure and number of players, who bet by `apply`.
+    fn structured_game(structure: BettingStructure, num_players: u8) -> Game {
+        let config = GameConfig::builder()
+            .big_blind(20)
+            .max_players(num_players)
+            .betting_structure(structure)
+            .build()
+            .unwrap();
+        let mut game = Game::from_config(config).unwrap();
+        for i in 1..=num_players {
+            let _ = game.join(Player::build(&format!("player{}", i), FixedActor(None)));
+        }
+        game
+    }
+
+    #[test]
+    fn test_betting_structures() {
+        let illegal = |result: Result<Vec<Msg>, GameError>| {
+            matches!(result, Err(GameError::IllegalAction { .. }))
+        };
+
+        // No limit: any raise of at least the big blind.
+        let mut game = structured_game(BettingStructure::NoLimit, 3);
+        let (player, args) = next_bet(&mut game);
+        assert!(
+            args.min == 20 && args.max.is_none(),
+            "Expected raises of at least 20 with no limit, was {:?}",
+            args
+        );
+        assert!(
+            illegal(game.apply(&player, Bet::Raise(args.call + 10))),
+            "Expected a raise of less than the minimum to be illegal"
+        );
+        game.apply(&player, Bet::Raise(args.call + 500)).unwrap();
+
+        // Pot limit: heads up, the dealer can raise by the pot of 30 plus the 10 to call.
+        let mut game = structured_game(BettingStructure::PotLimit, 2);
+        let (player, args) = next_bet(&mut game);
+        assert!(
+            args.call == 10 && args.max == Some(40),
+            "Expected raises of up to 40, was {:?}",
+            args
+        );
+        assert!(
+            illegal(game.apply(&player, Bet::AllIn(2000))),
+            "Expected going all in for more than the pot to be illegal"
+        );
+        game.apply(&player, Bet::Raise(args.call + 40)).unwrap();
+
+        // Fixed limit: raises of the small bet, until there have been four bets.
+        let mut game = structured_game(
+            BettingStructure::FixedLimit {
+                small_bet: 20,
+                big_bet: 40,
+                cap: 4,
+            },
+            3,
+        );
+        for _ in 0..3 {
+            let (player, args) = next_bet(&mut game);
+            assert!(
+                args.min == 20 && args.max == Some(20),
+                "Expected raises of exactly 20, was {:?}",
+                args
+            );
+            assert!(
+                illegal(game.apply(&player, Bet::Raise(args.call + 40))),
+                "Expected a raise of more than the small bet to be illegal"
+            );
+            game.apply(&player, Bet::Raise(args.call + 20)).unwrap();
+        }
+        let (player, args) = next_bet(&mut game);
+        assert!(
+            args.max == Some(0) && illegal(game.apply(&player, Bet::Raise(args.call + 20))),
+            "Expected no more raises after four bets, was {:?}",
+            args
+        );
+        game.apply(&player, Bet::Call).unwrap();
+        while game.stage != Stage::Turn {
+            call_or_check(&mut game);
+        }
+        let (_, args) = next_bet(&mut game);
+        assert!(
+            args.min == 40 && args.max == Some(40),
+            "Expected raises of the big bet on the turn, was {:?}",
+            args
+        );
+    }
+
+    #[test]
+    fn test_short_all_in() {
+        let illegal = |result: Result<Vec<Msg>, GameError>| {
+            matches!(result, Err(GameError::IllegalAction { .. }))
+        };
+        let mut game = structured_game(BettingStructure::NoLimit, 3);
+        game.players.get_mut("player2").unwrap().bank_roll = 150;
+        // player1 raises to 100, and player2 goes all in for 150, which is less than a
+        // full raise.
+        let (player, args) = next_bet(&mut game);
+        game.apply(&player, Bet::Raise(args.call + 80)).unwrap();
+        let (player, _) = next_bet(&mut game);
+        game.apply(&player, Bet::AllIn(0)).unwrap();
+        // player3 hadn't acted, so can still raise.
+        let (player, args) = next_bet(&mut game);
+        assert!(
+            player == "player3" && args.max.is_none() && args.cycle == 1,
+            "Expected player3 to be able to raise after one raise, was {} with {:?}",
+            player,
+            args
+        );
+        game.apply(&player, Bet::Call).unwrap();
+        // player1 had acted, so can only call or fold.
+        let (player, args) = next_bet(&mut game);
+        assert!(
+            player == "player1" && args.call == 50 && args.max == Some(0),
+            "Expected player1 to only be able to call 50, was {} with {:?}",
+            player,
+            args
+        );
+        assert!(
+            illegal(game.apply(&player, Bet::Raise(args.call + 100))),
+            "Expected player1 not to be able to raise again"
+        );
+        game.apply(&player, Bet::Call).unwrap();
+        let (_, args) = next_bet(&mut game);
+        assert!(
+            args.stage == Stage::Flop,
+            "Expected the betting before the flop to be over, was {:?}",
+            args
+        );
+    }
+
+    /// Make the next decision in a game by calling or checking, returning the player
+    /// who made it.
+    fn call_or_check(game: &mut Game) -> String {
+        match game.advance().unwrap() {
+            PendingDecision::Bet { player, args } => {
+                let bet = if args.call > 0 { Bet::Call } else { Bet::Check };
+                game.apply(&player, bet).unwrap();
+                player
+            }
+            PendingDecision::GameOver(w) => panic!("Expected the game to carry on, was {}", w),
+            PendingDecision::Waiting => panic!("Expected the game to carry on, was waiting"),
+        }
+    }
+
+    #[test]
+    fn test_snapshot_and_restore() {
+        let mut game = fixed_game(None);
+        // Stop in the middle of the betting after the flop.
+        (0..4).for_each(|_| {
+            call_or_check(&mut game);
+        });
+        assert!(
+            game.stage == Stage::Flop,
+            "Expected to be betting after the flop, was {:?}",
+            game.stage
+        );
+        let json = game.snapshot().to_json();
+        let snapshot = GameSnapshot::from_json(&json).unwrap();
+        let players = (1..=3).map(|i| Player::build(&format!("player{}", i), FixedActor(None)));
+        let mut restored = Game::restore(snapshot, players).unwrap();
+        assert!(
+            restored.uuid == game.uuid
+                && restored.bank_rolls() == game.bank_rolls()
+                && restored.pot == game.pot
+                && restored.players["player1"].hole == game.players["player1"].hole,
+            "Expected the restored game to be the same as the original"
+        );
+        // Both games carry on in the same way, including the rounds dealt after the
+        // snapshot was taken.
+        for _ in 0..30 {
+            let expected = call_or_check(&mut game);
+            let actual = call_or_check(&mut restored);
+            assert!(
+                expected == actual
+                    && restored.bank_rolls() == game.bank_rolls()
+                    && restored.community_cards == game.community_cards
+                    && restored.num_rounds == game.num_rounds,
+                "Expected the restored game to play like the original, was {} and {}",
+                actual,
+                expected
+            );
+        }
+        assert!(
+            game.num_rounds > 1,
+            "Expected several rounds to be played, was {}",
+            game.num_rounds
+        );
+    }
+
+    #[test]
+    fn test_restore_errors() {
+        let mut game = fixed_game(None);
+        let snapshot = game.snapshot();
+        let players = (1..=2).map(|i| Player::build(&format!("player{}", i), FixedActor(None)));
+        let result = Game::restore(snapshot.clone(), players);
+        assert!(
+            matches!(&result, Err(SnapshotError::MissingPlayer(name)) if name == "player3"),
+            "Expected player3 to be missing, was {:?}",
+            result.err()
+        );
+        let players = ["player1", "player2", "player3", "player4"]
+            .map(|name| Player::build(name, FixedActor(None)));
+        let result = Game::restore(snapshot, players);
+        assert!(
+            matches!(&result, Err(SnapshotError::UnknownPlayer(name)) if name == "player4"),
+            "Expected player4 to be unknown, was {:?}",
+            result.err()
+        );
+        let result = GameSnapshot::from_json("{}");
+        assert!(
+            matches!(result, Err(SnapshotError::Json(_))),
+            "Expected an incomplete snapshot to be rejected"
+        );
+    }
+
+    /// Build a game of players who call or check, logging the events.
+    fn table(config: GameConfig) -> (Game, crate::poker::events::EventLog) {
+        let log = crate::poker::events::EventLog::new();
+        let num_players = config.max_players;
+        let mut game = Game::from_config(config).unwrap();
+        game.subscribe(log.clone());
+        for i in 1..=num_players {
+            let actor = MsgLog(Rc::new(RefCell::new(Vec::new())));
+            let _ = game.join(Player::build(&format!("player{}", i), actor));
+        }
+        (game, log)
+    }
+
+    /// Play rounds until the given number of rounds have been played.
+    fn play_rounds(game: &mut Game, rounds: usize) {
+        while game.num_rounds < rounds {
+            game.play_round().unwrap();
+        }
+    }
+
+    /// The events in the given round, starting from one.
+    fn round_events(events: &[GameEvent], round: usize) -> Vec<GameEvent> {
+        events
+            .iter()
+            .skip_while(|e| !matches!(e, GameEvent::RoundStarted { round: r, .. } if *r == round))
+            .take_while(|e| !matches!(e, GameEvent::RoundStarted { round: r, .. } if *r > round))
+            .cloned()
+            .collect()
+    }
+
+    #[test]
+    fn test_sit_out() {
+        let config = GameConfig::builder()
+            .big_blind(20)
+            .max_players(4)
+            .post_missed_blinds(true)
+            .build()
+            .unwrap();
+        let (mut game, log) = table(config);
+        // player1 deals first, so player3 would have paid the big blind.
+        game.sit_out("player3").unwrap();
+        play_rounds(&mut game, 1);
+        let events = round_events(&log.events(), 1);
+        let Some(GameEvent::RoundStarted { players, .. }) = events.first() else {
+            panic!("Expected the round to start, was {:?}", events.first());
+        };
+        let names: Vec<&str> = players.iter().map(|(name, _)| name.as_str()).collect();
+        assert!(
+            names == ["player2", "player4", "player1"],
+            "Expected player3 not to be dealt in, was {:?}",
+            names
+        );
+        let big_blind = events.iter().find_map(|e| match e {
+            GameEvent::BigBlindPosted { player, .. } => Some(player.as_str()),
+            _ => None,
+        });
+        assert!(
+            big_blind == Some("player4") && game.players["player3"].bank_roll == 2000,
+            "Expected player4 to pay the big blind and player3 to pay nothing, was {:?} and {}",
+            big_blind,
+            game.players["player3"].bank_roll
+        );
+        assert!(
+            game.players["player3"].missed_blinds,
+            "Expected player3 to have missed the blinds"
+        );
+        // player3 misses the blinds again, then comes back when it's their turn to deal
+        // and posts the big blind.
+        play_rounds(&mut game, 2);
+        game.sit_in("player3").unwrap();
+        play_rounds(&mut game, 3);
+        let events = round_events(&log.events(), 3);
+        let big_blinds: Vec<(&str, usize)> = events
+            .iter()
+            .filter_map(|e| match e {
+                GameEvent::BigBlindPosted { player, amount } => Some((player.as_str(), *amount)),
+                _ => None,
+            })
+            .collect();
+        assert!(
+            big_blinds == [("player1", 20), ("player3", 20)],
+            "Expected player3 to post a missed big blind, was {:?}",
+            big_blinds
+        );
+        assert!(
+            !game.players["player3"].missed_blinds,
+            "Expected player3's missed blinds to have been paid"
+        );
+        let result = game.sit_out("nobody");
+        assert!(
+            result == Err(GameError::UnknownPlayer("nobody".to_string())),
+            "Expected an unknown player to be rejected, was {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_sit_out_everyone() {
+        let (mut game, _) = table(test_game(20, 2).config.clone());
+        game.sit_out("player2").unwrap();
+        let result = game.advance();
+        assert!(
+            matches!(result, Ok(PendingDecision::Waiting)),
+            "Expected the game to wait for players, was {:?}",
+            result
+        );
+        let result = game.play_round();
+        assert!(
+            matches!(result, Ok(None)) && game.num_rounds == 0,
+            "Expected a round not to be played with one player, was {:?}",
+            result
+        );
+        let result = game.play();
+        assert!(
+            matches!(result, Err(GameError::NotEnoughPlayers)),
+            "Expected the game not to be playable with one player, was {:?}",
+            result
+        );
+        let summary = game
+            .play_session(&SessionHandle::new(), &SystemClock)
+            .unwrap();
+        assert!(
+            summary.hands == 0 && summary.players.iter().all(|p| p.net() == 0),
+            "Expected a session with no hands, was {:?}",
+            summary
+        );
+        // The game carries on once the player sits in again.
+        game.sit_in("player2").unwrap();
+        let result = game.advance();
+        assert!(
+            matches!(result, Ok(PendingDecision::Bet { .. })),
+            "Expected a round to be played, was {:?}",
+            result
+        );
+    }
+
+    /// An actor that keeps the messages it is sent.
+    #[derive(Debug)]
+    struct MsgLog(Rc<RefCell<Vec<Msg>>>);
+    impl Actor for MsgLog {
+        fn set_name_and_bank_roll(&self, _name: &str, _bank_roll: usize) {}
+        fn hole_cards(&self, _hole_cards: (Card, Card)) {}
+        fn place_bet(
+            &mut self,
+            args: BetArgs,
+            _hole_cards: (Card, Card),
+            _bank_roll: usize,
+        ) -> Option<Bet> {
+            Some(if args.call > 0 { Bet::Call } else { Bet::Check })
+        }
+        fn update(&self, msg: &Msg) {
+            self.0.borrow_mut().push(msg.clone());
+        }
+    }
+
+    #[test]
+    fn test_leave_and_join() {
+        let msgs = Rc::new(RefCell::new(Vec::new()));
+        let (mut game, log) = table(test_game(20, 4).config.clone());
+        let _ = game.leave("player4");
+        let _ = game.join(Player::build("watcher", MsgLog(msgs.clone())));
+        assert!(
+            !game.players.contains_key("player4") && game.players.len() == 4,
+            "Expected player4 to leave before the first round"
+        );
+        // player2 leaves during the round and plays it out.
+        call_or_check(&mut game);
+        game.leave("player2").unwrap();
+        assert!(
+            game.players.contains_key("player2"),
+            "Expected player2 to stay until the end of the round"
+        );
+        play_rounds(&mut game, 1);
+        let left = log.events().into_iter().find_map(|e| match e {
+            GameEvent::PlayerLeft { player, bank_roll } if player == "player2" => Some(bank_roll),
+            _ => None,
+        });
+        assert!(
+            !game.players.contains_key("player2") && left.is_some(),
+            "Expected player2 to leave with their chips at the end of the round"
+        );
+        // A player joining during a round is dealt in from the next one.
+        call_or_check(&mut game);
+        let actor = MsgLog(Rc::new(RefCell::new(Vec::new())));
+        game.join(Player::build("player5", actor)).unwrap();
+        assert!(
+            game.players["player5"].folded && game.players["player5"].hole.is_none(),
+            "Expected player5 to sit out the rest of the round"
+        );
+        play_rounds(&mut game, 2);
+        call_or_check(&mut game);
+        assert!(
+            game.players["player5"].hole.is_some(),
+            "Expected player5 to be dealt in to the next round"
+        );
+        let msgs = msgs.borrow();
+        let left = msgs
+            .iter()
+            .any(|m| matches!(m, Msg::PlayerLeft { player, .. } if player == "player2"));
+        let joined = msgs
+            .iter()
+            .any(|m| matches!(m, Msg::PlayerJoined { player, .. } if player == "player5"));
+        assert!(
+            left && joined,
+            "Expected the table to be told about the players leaving and joining"
+        );
+    }
+
+    #[test]
+    fn test_leave_heads_up() {
+        // A player leaving before the first round leaves the other as the winner.
+        let (mut game, log) = table(test_game(20, 2).config.clone());
+        game.leave("player2").unwrap();
+        let result = game.play();
+        assert!(
+            matches!(&result, Ok(w) if w == "player1"),
+            "Expected player1 to win, was {:?}",
+            result
+        );
+        assert!(
+            log.events()
+                .iter()
+                .any(|e| matches!(e, GameEvent::GameWinner(w) if w == "player1")),
+            "Expected the winner to be announced"
+        );
+        // The same goes for a player leaving later on, whoever won the last round.
+        let (mut game, _) = table(test_game(20, 2).config.clone());
+        play_rounds(&mut game, 1);
+        let leaving = game.players_order[0].clone();
+        game.leave(&leaving).unwrap();
+        let result = game.play();
+        assert!(
+            matches!(&result, Ok(w) if w != &leaving),
+            "Expected the player who stayed to win, was {:?}",
+            result
+        );
+    }
+
+    /// An actor that goes all in every time, and always rebuys and takes the add-on.
+    #[derive(Debug)]
+    struct Rebuyer;
+    impl Actor for Rebuyer {
+        fn set_name_and_bank_roll(&self, _name: &str, _bank_roll: usize) {}
+        fn hole_cards(&self, _hole_cards: (Card, Card)) {}
+        fn place_bet(
+            &mut self,
+            _args: BetArgs,
+            _hole_cards: (Card, Card),
+            bank_roll: usize,
+        ) -> Option<Bet> {
+            Some(Bet::AllIn(bank_roll))
+        }
+        fn update(&self, _msg: &Msg) {}
+        fn rebuy(&mut self, _amount: usize) -> bool {
+            true
+        }
+        fn add_on(&mut self, _amount: usize) -> bool {
+            true
+        }
+    }
+
+    /// Build a game of `Rebuyer` players, logging the events.
+    fn rebuy_table(rules: RebuyRules, num_players: u8) -> (Game, crate::poker::events::EventLog) {
+        let log = crate::poker::events::EventLog::new();
+        let config = GameConfig::builder()
+            .big_blind(20)
+            .max_players(num_players)
+            .seed(7)
+            .rebuys(rules)
+            .build()
+            .unwrap();
+        let mut game = Game::from_config(config).unwrap();
+        game.subscribe(log.clone());
+        for i in 1..=num_players {
+            let _ = game.join(Player::build(&format!("player{}", i), Rebuyer));
+        }
+        (game, log)
+    }
+
+    #[test]
+    fn test_rebuys_and_add_ons() {
+        let rules = RebuyRules {
+            amount: 1000,
+            max_rebuys: Some(1),
+            period: RebuyPeriod::Always,
+            add_on: None,
+        };
+        let (mut game, log) = rebuy_table(rules, 3);
+        game.play().unwrap();
+        let events = log.events();
+        for i in 1..=3 {
+            let name = format!("player{}", i);
+            let rebuys = events
+                .iter()
+                .filter(
+                    |e| matches!(e, GameEvent::Rebuy { player, amount: 1000 } if *player == name),
+                )
+                .count();
+            assert!(
+                rebuys <= 1,
+                "Expected {} to rebuy at most once, was {}",
+                name,
+                rebuys
+            );
+        }
+        let audit = game.audit();
+        assert!(
+            audit.balanced() && audit.in_play == audit.bought() && audit.rebuys > 0,
+            "Expected all the chips bought to be in play, was {:?}",
+            audit
+        );
+
+        // Rebuys close after the first round, when the add-on is offered.
+        let rules = RebuyRules {
+            amount: 1000,
+            max_rebuys: None,
+            period: RebuyPeriod::Hands(1),
+            add_on: Some(500),
+        };
+        let (mut game, log) = rebuy_table(rules, 2);
+        play_rounds(&mut game, 1);
+        let audit = game.audit();
+        assert!(
+            audit.rebuys == 1000 && audit.add_ons == 0 && audit.balanced(),
+            "Expected the player who went broke to rebuy, was {:?}",
+            audit
+        );
+        play_rounds(&mut game, 2);
+        let add_ons: Vec<GameEvent> = round_events(&log.events(), 1)
+            .into_iter()
+            .filter(|e| matches!(e, GameEvent::AddOn { amount: 500, .. }))
+            .collect();
+        assert!(
+            add_ons.len() == 2,
+            "Expected both players to take the add-on before the second round, was {:?}",
+            add_ons
+        );
+        let audit = game.audit();
+        assert!(
+            game.players.len() == 1 && audit.rebuys == 1000 && audit.balanced(),
+            "Expected the player who went broke again to be out, was {:?}",
+            audit
+        );
+        let _ = game.leave(&game.players_order[0].clone());
+        let audit = game.audit();
+        assert!(
+            audit.in_play == 0 && audit.cashed_out == 6000 && audit.balanced(),
+            "Expected the winner to cash out every chip, was {:?}",
+            audit
+        );
+    }
+
+    #[test]
+    fn test_session() {
+        use crate::poker::{
+            blinds::{ManualClock, SystemClock},
+            config::SessionLimits,
+            session::SessionHandle,
+        };
+        let limits = SessionLimits {
+            hands: Some(3),
+            duration: None,
+        };
+        let config = test_game(20, 3).config.clone();
+        let (mut game, _) = table(GameConfig {
+            session: limits,
+            ..config.clone()
+        });
+        let handle = SessionHandle::new();
+        let summary = game.play_session(&handle, &SystemClock).unwrap();
+        let net: i64 = summary.players.iter().map(|p| p.net()).sum();
+        assert!(
+            summary.hands == 3 && net == 0 && summary.biggest_pot > 0,
+            "Expected three hands with no chips lost, was {:?}",
+            summary
+        );
+        assert!(
+            summary
+                .players
+                .iter()
+                .all(|p| p.hands_played == 3 && !p.left),
+            "Expected everyone to play every hand, was {:?}",
+            summary.players
+        );
+        // The game carries on from where it was for the next session.
+        handle.cash_out("player2");
+        let summary = game.play_session(&handle, &SystemClock).unwrap();
+        let player2 = summary.player("player2").unwrap();
+        assert!(
+            summary.hands == 3 && player2.left && player2.hands_played == 0 && player2.net() == 0,
+            "Expected player2 to cash out before the session, was {:?}",
+            summary
+        );
+        // Stop from another thread.
+        let remote = handle.clone();
+        std::thread::spawn(move || remote.stop()).join().unwrap();
+        let summary = game.play_session(&handle, &SystemClock).unwrap();
+        assert!(
+            summary.hands == 0,
+            "Expected a stopped session not to play any hands, was {}",
+            summary.hands
+        );
+
+        // Play until the time runs out, with each bet taking a minute.
+        let limits = SessionLimits {
+            hands: None,
+            duration: Some(Duration::from_secs(5 * 60)),
+        };
+        let clock = Rc::new(ManualClock::new());
+        let mut game = Game::from_config(GameConfig {
+            session: limits,
+            ..config
+        })
+        .unwrap();
+        for i in 1..=3 {
+            let actor = SlowActor(clock.clone());
+            let _ = game.join(Player::build(&format!("player{}", i), actor));
+        }
+        let summary = game.play_session(&SessionHandle::new(), &clock).unwrap();
+        assert!(
+            summary.duration >= Duration::from_secs(5 * 60) && (1..=3).contains(&summary.hands),
+            "Expected the session to stop after the hand when time ran out, was {:?}",
+            summary
+        );
+    }
+
+    #[test]
+    fn test_win_uncontested() {
+        use crate::poker::replay::ScriptedActor;
+        let log = crate::poker::events::EventLog::new();
+        let config = GameConfig::builder()
+            .big_blind(20)
+            .max_players(3)
+            .rabbit_hunt(true)
+            .build()
+            .unwrap();
+        let mut game = Game::from_config(config).unwrap();
+        game.subscribe(log.clone());
+        // player1 is the dealer, so acts first before the flop.
+        let scripts = [vec![Bet::Raise(60)], vec![Bet::Fold], vec![Bet::Fold]];
+        for (i, bets) in scripts.into_iter().enumerate() {
+            let name = format!("player{}", i + 1);
+            let _ = game.join(Player::build(&name, ScriptedActor::new(bets)));
+        }
+        game.play_round().unwrap();
+        let events = log.events();
+        assert!(
+            events.iter().any(|e| matches!(
+                e,
+                GameEvent::UncalledBetReturned { player, amount: 40 } if player == "player1"
+            )) && events.iter().any(|e| matches!(
+                e,
+                GameEvent::RoundWonUncontested { player, pot: 50 } if player == "player1"
+            )),
+            "Expected player1 to get back 40 and win the pot of 50, was {:?}",
+            events
+        );
+        assert!(
+            !events.iter().any(|e| matches!(
+                e,
+                GameEvent::CommunityCardsDealt { .. }
+                    | GameEvent::Showdown(_)
+                    | GameEvent::RoundWinner(_)
+            )),
+            "Expected no board, showdown or winning hand, was {:?}",
+            events
+        );
+        assert!(
+            events
+                .iter()
+                .any(|e| matches!(e, GameEvent::RabbitHunt(cards) if cards.len() == 5)),
+            "Expected a rabbit hunt of the whole board, was {:?}",
+            events
+        );
+        let bank_rolls = game.bank_rolls();
+        assert!(
+            bank_rolls
+                == vec![
+                    ("player2".to_string(), 1990),
+                    ("player3".to_string(), 1980),
+                    ("player1".to_string(), 2030)
+                ],
+            "Expected player1 to win the blinds, was {:?}",
+            bank_rolls
+        );
+    }
+
+    #[test]
+    fn test_uncontested_final_hand() {
+        use crate::poker::{
+            events::{Audience, EventLog},
+            replay::ScriptedActor,
+        };
+        let log = EventLog::new();
+        let mut game = test_game(20, 2);
+        game.subscribe(log.clone());
+        for name in ["player1", "player2"] {
+            let _ = game.join(Player::build(name, ScriptedActor::new(vec![Bet::Fold])));
+        }
+        game.play_round().unwrap();
+        // The player who folded leaves, which ends the game with the uncontested hand.
+        let (loser, winner) = match game.bank_rolls().as_slice() {
+            [(a, x), (b, y)] if x < y => (a.clone(), b.clone()),
+            [(a, _), (b, _)] => (b.clone(), a.clone()),
+            _ => panic!("Expected two players"),
+        };
+        game.leave(&loser).unwrap();
+        let result = game.play();
+        assert!(
+            matches!(&result, Ok(w) if w == &winner),
+            "Expected {} to win, was {:?}",
+            winner,
+            result
+        );
+        let events = log.events();
+        let cards = events
+            .iter()
+            .find_map(|e| match e {
+                GameEvent::HoleCardsDealt { player, cards } if player == &winner => Some(*cards),
+                _ => None,
+            })
+            .unwrap();
+        let shown = events
+            .iter()
+            .filter(|e| e.audience() == Audience::Public)
+            .find(|e| {
+                let text = format!("{:?}", e);
+                text.contains(&format!("{:?}", cards.0)) || text.contains(&format!("{:?}", cards.1))
+            });
+        assert!(
+            shown.is_none() && game.winner.is_none(),
+            "Expected {}'s cards not to be shown, was {:?}",
+            winner,
+            shown
+        );
+    }
 
-        // Assign new dealer.
-        let dealer_name = self.dealer.clone().unwrap();
-        if !self.players_order.is_empty() {
-            let dealer_index = self
-                .players_order
-                .iter()
-                .position(|n| n == &dealer_name)
-                .unwrap_or_default();
-            let players_order = self.players_order.clone();
-            self.dealer = Some(players_order[(dealer_index + 1) % players_order.len()].clone());
+    /// An actor that goes all in, and agrees to run it twice or not.
+    #[derive(Debug)]
+    struct AllInActor(bool);
+    impl Actor for AllInActor {
+        fn set_name_and_bank_roll(&self, _name: &str, _bank_roll: usize) {}
+        fn hole_cards(&self, _hole_cards: (Card, Card)) {}
+        fn place_bet(
+            &mut self,
+            _args: BetArgs,
+            _hole_cards: (Card, Card),
+            bank_roll: usize,
+        ) -> Option<Bet> {
+            Some(Bet::AllIn(bank_roll))
+        }
+        fn update(&self, _msg: &Msg) {}
+        fn run_it_twice(&mut self) -> bool {
+            self.0
         }
     }
-}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use crate::poker::{
-        autoactor::AutoActor,
-        betting_strategy::BetArgs,
-        card::{BestHand, Card, Hand, Rank, Suit},
-    };
+    /// Play a round between two players who go all in, returning the events.
+    fn all_in_round(run_it_twice: bool, agree: [bool; 2]) -> Vec<GameEvent> {
+        let log = crate::poker::events::EventLog::new();
+        let config = GameConfig::builder()
+            .big_blind(20)
+            .max_players(2)
+            .seed(3)
+            .run_it_twice(run_it_twice)
+            .build()
+            .unwrap();
+        let mut game = Game::from_config(config).unwrap();
+        game.subscribe(log.clone());
+        for (i, agree) in agree.into_iter().enumerate() {
+            let name = format!("player{}", i + 1);
+            let _ = game.join(Player::build(&name, AllInActor(agree)));
+        }
+        game.play_round().unwrap();
+        log.events()
+    }
 
     #[test]
-    fn test_build() {
-        let game = Game::build(10, 5);
+    fn test_all_in_runout() {
+        let events = all_in_round(false, [true, true]);
+        let revealed = events
+            .iter()
+            .position(|e| matches!(e, GameEvent::HandsRevealed(hands) if hands.len() == 2));
         assert!(
-            game.deck.len() == 52,
-            "Expected game.deck to have 52 cards, was {}",
-            game.deck.len()
+            revealed.is_some(),
+            "Expected both hands to be shown, was {:?}",
+            events
+        );
+        let after = &events[revealed.unwrap()..];
+        let streets = after
+            .iter()
+            .filter(|e| matches!(e, GameEvent::CommunityCardsDealt { .. }))
+            .count();
+        assert!(
+            streets == 3
+                && !after
+                    .iter()
+                    .any(|e| matches!(e, GameEvent::BetPlaced { .. })),
+            "Expected the flop, turn and river to be dealt without betting, was {:?}",
+            after
+        );
+        assert!(
+            !events
+                .iter()
+                .any(|e| matches!(e, GameEvent::SecondBoardDealt(_))),
+            "Expected one board when the game doesn't run it twice"
+        );
+        let events = all_in_round(true, [true, false]);
+        assert!(
+            !events
+                .iter()
+                .any(|e| matches!(e, GameEvent::SecondBoardDealt(_))),
+            "Expected one board when a player doesn't agree to run it twice"
         );
     }
 
     #[test]
-    fn test_add_too_many_players() {
-        let mut game = Game::build(10, 2);
-        let _ = game.join(Player::build("player1", AutoActor::new()));
-        let _ = game.join(Player::build("player2", AutoActor::new()));
-        if let Err(e) = game.join(Player::build("player3", AutoActor::new())) {
-            assert!(
-                e == "Cannot add more players.",
-                "Expected 'Cannot add more players.', was {}",
-                e
-            );
-        } else {
-            panic!("Expected add_player to raise an error.");
+    fn test_run_it_twice() {
+        use crate::poker::{history::HandHistoryRecorder, replay};
+        let recorder = HandHistoryRecorder::new();
+        let config = GameConfig::builder()
+            .big_blind(20)
+            .max_players(2)
+            .seed(3)
+            .run_it_twice(true)
+            .build()
+            .unwrap();
+        let mut game = Game::from_config(config).unwrap();
+        game.subscribe(recorder.clone());
+        let log = crate::poker::events::EventLog::new();
+        game.subscribe(log.clone());
+        for i in 1..=2 {
+            let _ = game.join(Player::build(&format!("player{}", i), AllInActor(true)));
         }
+        game.play_round().unwrap();
+        let events = log.events();
+        let history = recorder.histories().remove(0);
+        assert!(
+            history.board.len() == 5
+                && history.second_board.len() == 5
+                && history
+                    .second_board
+                    .iter()
+                    .all(|c| !history.board.contains(c)),
+            "Expected two different boards, was {:?} and {:?}",
+            history.board,
+            history.second_board
+        );
+        let second = events
+            .iter()
+            .position(|e| matches!(e, GameEvent::SecondBoardWinner(_)))
+            .unwrap();
+        let awarded = |events: &[GameEvent]| -> usize {
+            events
+                .iter()
+                .filter_map(|e| match e {
+                    GameEvent::PotAwarded { amount, .. } => Some(*amount),
+                    _ => None,
+                })
+                .sum()
+        };
+        assert!(
+            awarded(&events[..second]) == 2000 && awarded(&events[second..]) == 2000,
+            "Expected half the pot to be won on each board, was {:?}",
+            events
+        );
+        let total: usize = game.bank_rolls().iter().map(|(_, b)| b).sum();
+        assert!(
+            total == 4000 && game.audit().balanced(),
+            "Expected every chip to be awarded, was {:?}",
+            game.bank_rolls()
+        );
+        let replayed = replay::replay(&history);
+        assert!(
+            replayed.is_ok(),
+            "Expected a hand run twice to replay, was {:?}",
+            replayed
+        );
     }
 
-    #[test]
-    fn test_players_receive_bank_roll() {
-        let mut game = Game::build(20, 2);
-        let _ = game.join(Player::build("player1", AutoActor::new()));
-        let _ = game.join(Player::build("player2", AutoActor::new()));
-        // each player should receive 100 x big blind
-        game.players.values().for_each(|p| {
-            assert!(
-                p.bank_roll == 100 * 20,
-                "Expected new player to receive {}, was {}",
-                100 * 20,
-                p.bank_roll
-            )
-        });
+    /// Play a round with players who bet as scripted, returning the events and the
+    /// history of the round. The first player is the dealer.
+    fn scripted_round(
+        config: GameConfig,
+        scripts: Vec<Vec<Bet>>,
+    ) -> (Vec<GameEvent>, crate::poker::history::HandHistory) {
+        use crate::poker::{history::HandHistoryRecorder, replay::ScriptedActor};
+        let log = crate::poker::events::EventLog::new();
+        let recorder = HandHistoryRecorder::new();
+        let mut game = Game::from_config(config).unwrap();
+        game.subscribe(log.clone());
+        game.subscribe(recorder.clone());
+        for (i, bets) in scripts.into_iter().enumerate() {
+            let name = format!("player{}", i + 1);
+            let _ = game.join(Player::build(&name, ScriptedActor::new(bets)));
+        }
+        game.play_round().unwrap();
+        (log.events(), recorder.histories().remove(0))
     }
 
     #[test]
-    fn test_deal_hole_cards() {
-        let mut game = Game::build(20, 2);
-        let _ = game.join(Player::build("player1", AutoActor::new()));
-        let _ = game.join(Player::build("player2", AutoActor::new()));
-        game.deal_hole_cards();
+    fn test_straddle() {
+        let config = GameConfig::builder()
+            .big_blind(20)
+            .max_players(4)
+            .straddle(Straddle::UnderTheGun)
+            .build()
+            .unwrap();
+        // player2 and player3 are the blinds and player4 straddles, so player1 acts
+        // first before the flop and player4 last.
+        let checks = [Bet::Check, Bet::Check, Bet::Check];
+        let scripts = vec![
+            [&[Bet::Call][..], &checks].concat(),
+            vec![Bet::Fold],
+            vec![Bet::Fold],
+            [&[Bet::Check][..], &checks].concat(),
+        ];
+        let (events, history) = scripted_round(config, scripts);
         assert!(
-            game.deck.len() == 48,
-            "Expected game.deck.len() to be 48, was {}",
-            game.deck.len()
+            events.iter().any(|e| matches!(
+                e,
+                GameEvent::StraddlePosted { player, amount: 40 } if player == "player4"
+            )),
+            "Expected player4 to straddle for 40, was {:?}",
+            events
+        );
+        let preflop: Vec<&str> = history
+            .actions_in(Stage::PreFlop)
+            .map(|a| a.player.as_str())
+            .collect();
+        assert!(
+            preflop == ["player1", "player2", "player3", "player4"],
+            "Expected the straddler to act last before the flop, was {:?}",
+            preflop
+        );
+        assert!(
+            history.total_pot() == 110,
+            "Expected a pot of 110, was {}",
+            history.total_pot()
+        );
+        let replayed = crate::poker::replay::replay(&history);
+        assert!(
+            replayed.is_ok(),
+            "Expected a straddled hand to replay, was {:?}",
+            replayed
         );
-        game.players.iter().for_each(|(name, p)| {
-            if let Some((c1, c2)) = p.hole {
-                assert!(
-                    !game.deck.contains(&c1),
-                    "Expected {:?} not to be in game.deck but it was.",
-                    c1
-                );
-                assert!(
-                    !game.deck.contains(&c2),
-                    "Expected {:?} not to be in game.deck but it was.",
-                    c2
-                );
-            } else {
-                panic!("Expected hole cards for player {}", name);
-            }
-        });
     }
 
     #[test]
-    fn test_place_bets_default_strategy() {
-        let mut game = Game::build(20, 2);
-        let _ = game.join(Player::build("player1", AutoActor::new()));
-        let _ = game.join(Player::build("player2", AutoActor::build(test_strategy)));
-        game.order_players();
-        game.deal_hole_cards();
-
-        // Test with players that don't place bets.
-        // Both players will use default strategy and check.
-        // The pot should contain only the blinds.
-        game.place_bets();
+    fn test_bomb_pot() {
+        use crate::poker::config::BombPotRules;
+        let config = GameConfig::builder()
+            .big_blind(20)
+            .max_players(3)
+            .bomb_pot(BombPotRules { ante: 50, every: 1 })
+            .build()
+            .unwrap();
+        let scripts = vec![vec![Bet::Check; 3]; 3];
+        let (events, history) = scripted_round(config, scripts);
         assert!(
-            game.pot == 40,
-            "Expected game.pot to be 40, was {}",
-            game.pot
+            events
+                .iter()
+                .any(|e| matches!(e, GameEvent::BombPot { ante: 50 })),
+            "Expected a bomb pot, was {:?}",
+            events
         );
-        game.players.iter().for_each(|(_name, p)| {
-            assert!(
-                p.bank_roll == 1980,
-                "Expected p.bank_roll to be 1980, was {}.",
-                p.bank_roll
-            );
-        });
-        game.place_bets();
         assert!(
-            game.pot == 80,
-            "Expected game.pot to be 80, was {}",
-            game.pot
+            history
+                .actions_in(Stage::Blinds)
+                .all(|a| a.action == crate::poker::history::ActionKind::Ante && a.amount == 50)
+                && history.actions_in(Stage::Blinds).count() == 3
+                && history.actions_in(Stage::PreFlop).count() == 0,
+            "Expected antes of 50 and no blinds or betting before the flop, was {:?}",
+            history.actions
         );
-        game.players.iter().for_each(|(_name, p)| {
+        assert!(
+            history.bomb_pot() == Some(50) && history.total_pot() == 150,
+            "Expected a bomb pot of 150, was {:?}",
+            history
+        );
+        let replayed = crate::poker::replay::replay(&history);
+        assert!(
+            replayed.is_ok(),
+            "Expected a bomb pot to replay, was {:?}",
+            replayed
+        );
+    }
+
+    #[test]
+    fn test_rake() {
+        use crate::poker::{
+            betting_strategy::modest_betting_strategy,
+            blinds::SystemClock,
+            config::{RakeRules, SessionLimits},
+            history::HandHistoryRecorder,
+            replay,
+            session::SessionHandle,
+        };
+        let rules = RakeRules {
+            percent: 5,
+            cap: Some(30),
+            no_flop_no_drop: true,
+        };
+        let config = GameConfig::builder()
+            .big_blind(20)
+            .max_players(4)
+            .seed(5)
+            .rake(rules)
+            .session(SessionLimits {
+                hands: Some(20),
+                duration: None,
+            })
+            .build()
+            .unwrap();
+        let mut game = Game::from_config(config).unwrap();
+        let recorder = HandHistoryRecorder::new();
+        game.subscribe(recorder.clone());
+        for i in 1..=4 {
+            let actor = AutoActor::build(modest_betting_strategy);
+            let _ = game.join(Player::build(&format!("player{}", i), actor));
+        }
+        let summary = game
+            .play_session(&SessionHandle::new(), &SystemClock)
+            .unwrap();
+        let histories = recorder.histories();
+        for h in &histories {
+            let awarded: usize = h.awards.iter().map(|a| a.amount).sum();
             assert!(
-                p.bank_roll == 1960,
-                "Expected p.bank_roll to be 1960, was {}.",
-                p.bank_roll
+                awarded + h.rake == h.total_pot(),
+                "Expected the pot to be shared between the winners and the house, was {:?}",
+                h
             );
-        });
+            assert!(
+                h.rake == rules.rake(h.total_pot(), !h.board.is_empty()),
+                "Expected 5% of the pot up to 30 once the flop is dealt, was {} of {}",
+                h.rake,
+                h.total_pot()
+            );
+            let replayed = replay::replay(h);
+            assert!(
+                replayed.is_ok(),
+                "Expected hand {} to replay with its rake, was {:?}",
+                h.hand_number,
+                replayed
+            );
+        }
+        let rake: usize = histories.iter().map(|h| h.rake).sum();
+        let net: i64 = summary.players.iter().map(|p| p.net()).sum();
+        assert!(
+            rake > 0 && summary.rake == rake && net == -(rake as i64),
+            "Expected the players to lose the rake to the house, was {:?}",
+            summary
+        );
+        let audit = game.audit();
+        assert!(
+            audit.rake == rake && audit.balanced(),
+            "Expected the rake in the chip audit, was {:?}",
+            audit
+        );
     }
 
-    // A betting strategy that will place a bet if the call is zero
-    fn test_strategy(args: BetArgs, _hole_cards: (Card, Card), bank_roll: usize) -> Bet {
-        if bank_roll == 0 {
-            Bet::Fold
-        } else if bank_roll <= args.call {
-            Bet::AllIn(bank_roll)
-        } else if args.call == 0 {
-            Bet::Raise(args.min)
-        } else {
-            Bet::Call
+    /// An actor that calls or checks, taking a minute to do it.
+    #[derive(Debug)]
+    struct SlowActor(Rc<crate::poker::blinds::ManualClock>);
+    impl Actor for SlowActor {
+        fn set_name_and_bank_roll(&self, _name: &str, _bank_roll: usize) {}
+        fn hole_cards(&self, _hole_cards: (Card, Card)) {}
+        fn place_bet(
+            &mut self,
+            args: BetArgs,
+            _hole_cards: (Card, Card),
+            _bank_roll: usize,
+        ) -> Option<Bet> {
+            self.0.advance(Duration::from_secs(60));
+            Some(if args.call > 0 { Bet::Call } else { Bet::Check })
         }
+        fn update(&self, _msg: &Msg) {}
     }
 
     #[test]
-    fn test_place_bets_modest_strategy() {
-        let mut game = Game::build(20, 2);
-        let _ = game.join(Player::build("player1", AutoActor::new()));
-        let _ = game.join(Player::build("player2", AutoActor::build(test_strategy)));
-        game.order_players();
-        game.deal_hole_cards();
-
-        game.place_bets();
+    fn test_move_player() {
+        let (mut from, _) = table(test_game(20, 3).config.clone());
+        let (mut to, log) = table(test_game(20, 3).config.clone());
+        let _ = to.leave("player1");
+        call_or_check(&mut from);
+        let result = from.take_player("player1");
         assert!(
-            game.pot == 40,
-            "Expected game.pot to be 40, was {}",
-            game.pot
+            matches!(result, Err(GameError::RoundInProgress)),
+            "Expected players not to be moved during a round, was {:?}",
+            result
         );
-        game.players.iter().for_each(|(_name, p)| {
-            assert!(
-                p.bank_roll == 1980,
-                "Expected p.bank_roll to be 1980, was {}.",
-                p.bank_roll
-            );
+        play_rounds(&mut from, 1);
+        let bank_roll = from.players["player1"].bank_roll;
+        let p = from.take_player("player1").unwrap();
+        to.seat_player(p).unwrap();
+        let joined = log.events().into_iter().any(|e| {
+            matches!(e, GameEvent::PlayerJoined { player, bank_roll: b }
+                if player == "player1" && b == bank_roll)
         });
+        assert!(
+            !from.players.contains_key("player1")
+                && to.players["player1"].bank_roll == bank_roll
+                && joined,
+            "Expected player1 to move with their chips, was {:?}",
+            to.bank_rolls()
+        );
+        assert!(
+            from.audit().balanced() && to.audit().balanced(),
+            "Expected the chips moved to be accounted for"
+        );
     }
 
     #[test]
-    fn test_place_bets_folded_player() {
-        let mut game = Game::build(20, 3);
-        let _ = game.join(Player::build("player1", AutoActor::new()));
-        let _ = game.join(Player::build("player2", AutoActor::new()));
-        let _ = game.join(Player::build("player3", AutoActor::build(test_strategy)));
-        game.order_players();
-        game.deal_hole_cards();
-
-        // Test with a player who bets once and one which is folded.
+    fn test_events() {
+        use crate::poker::events::{Audience, EventLog};
+        let log = EventLog::new();
+        let mut game = test_game(20, 3);
+        game.subscribe(log.clone());
+        for i in 1..=3 {
+            let _ = game.join(Player::build(&format!("player{}", i), AutoActor::new()));
+        }
+        game.play_to_showdown().unwrap();
+        let events = log.events();
+        assert!(
+            matches!(
+                &events[..6],
+                [
+                    GameEvent::PlayerJoined { .. },
+                    GameEvent::PlayerJoined { .. },
+                    GameEvent::PlayerJoined { .. },
+                    GameEvent::RoundStarted { round: 1, .. },
+                    GameEvent::SmallBlindPosted { amount: 10, .. },
+                    GameEvent::BigBlindPosted { amount: 20, .. },
+                ]
+            ),
+            "Unexpected events at the start of the game {:?}",
+            &events[..6]
+        );
+        let private: Vec<Audience> = events
+            .iter()
+            .map(|e| e.audience())
+            .filter(|a| *a != Audience::Public)
+            .collect();
+        assert!(
+            private
+                == vec![
+                    Audience::Player("player2".to_string()),
+                    Audience::Player("player3".to_string()),
+                    Audience::Player("player1".to_string()),
+                    Audience::House,
+                    Audience::House,
+                    Audience::House,
+                ],
+            "Expected hole cards for each player and three burned cards, was {:?}",
+            private
+        );
+        let board: usize = events
+            .iter()
+            .filter_map(|e| match e {
+                GameEvent::CommunityCardsDealt { cards, .. } => Some(cards.len()),
+                _ => None,
+            })
+            .sum();
+        let awarded: usize = events
+            .iter()
+            .filter_map(|e| match e {
+                GameEvent::PotAwarded { amount, .. } => Some(*amount),
+                _ => None,
+            })
+            .sum();
+        assert!(
+            board == 5 && awarded == 60,
+            "Expected 5 community cards and a pot of 60 to be awarded, was {} and {}",
+            board,
+            awarded
+        );
+        assert!(
+            matches!(events.last(), Some(GameEvent::RoundWinner(_))),
+            "Expected the round to end with the winner, was {:?}",
+            events.last()
+        );
+    }
 
-        let p2 = game.players.get_mut("player2").unwrap();
-        p2.folded = true;
+    /// An actor that supplies a client seed and bets like an `AutoActor`.
+    #[derive(Debug)]
+    struct SeedActor(AutoActor);
+    impl Actor for SeedActor {
+        fn set_name_and_bank_roll(&self, _name: &str, _bank_roll: usize) {}
+        fn hole_cards(&self, _hole_cards: (Card, Card)) {}
+        fn place_bet(
+            &mut self,
+            args: BetArgs,
+            hole_cards: (Card, Card),
+            bank_roll: usize,
+        ) -> Option<Bet> {
+            self.0.place_bet(args, hole_cards, bank_roll)
+        }
+        fn update(&self, _msg: &Msg) {}
+        fn client_seed(&self) -> Option<String> {
+            Some("client seed".to_string())
+        }
+    }
 
-        game.place_bets();
+    #[test]
+    fn test_provably_fair() {
+        use crate::poker::events::EventLog;
+        let log = EventLog::new();
+        let config = GameConfig::builder()
+            .big_blind(20)
+            .max_players(3)
+            .provably_fair(true)
+            .build()
+            .unwrap();
+        let mut game = Game::from_config(config).unwrap();
+        game.subscribe(log.clone());
+        let _ = game.join(Player::build("player1", SeedActor(AutoActor::new())));
+        let _ = game.join(Player::build("player2", AutoActor::new()));
+        let _ = game.join(Player::build("player3", AutoActor::new()));
+        game.play_to_showdown().unwrap();
+        let events = log.events();
+        let committed = events
+            .iter()
+            .position(|e| matches!(e, GameEvent::ShuffleCommitted { hand: 1, .. }));
+        let dealt = events
+            .iter()
+            .position(|e| matches!(e, GameEvent::HoleCardsDealt { .. }));
         assert!(
-            game.pot == 40,
-            "Expected game.pot to be 40, was {}",
-            game.pot
+            committed.is_some() && committed < dealt,
+            "Expected the commitment before the deal, was {:?} and {:?}",
+            committed,
+            dealt
         );
-        game.players.iter().for_each(|(_name, p)| {
-            if p.name == "player2" {
-                assert!(
-                    p.bank_roll == 2000,
-                    "Expected p.bank_roll to be 2000, was {}.",
-                    p.bank_roll
-                );
-            } else {
+        let Some(GameEvent::ShuffleCommitted { commitment, .. }) = committed.map(|i| &events[i])
+        else {
+            unreachable!()
+        };
+        let Some(GameEvent::ShuffleRevealed(seeds)) = events.last() else {
+            panic!("Expected the seeds to be revealed after the round")
+        };
+        assert!(
+            seeds.client_seeds == vec!["client seed".to_string()],
+            "Expected the client's seed to be used, was {:?}",
+            seeds.client_seeds
+        );
+        let deck = fair::verify(commitment, seeds).unwrap();
+        let seats: Vec<String> = events
+            .iter()
+            .find_map(|e| match e {
+                GameEvent::RoundStarted { players, .. } => {
+                    Some(players.iter().map(|(name, _)| name.clone()).collect())
+                }
+                _ => None,
+            })
+            .unwrap();
+        for e in &events {
+            if let GameEvent::HoleCardsDealt { player, cards } = e {
+                let seat = seats.iter().position(|n| n == player).unwrap();
+                let result = fair::verify_cards(&deck, 3, seat, *cards, &game.community_cards);
                 assert!(
-                    p.bank_roll == 1980,
-                    "Expected p.bank_roll to be 1980, was {}.",
-                    p.bank_roll
+                    result.is_ok(),
+                    "Expected the cards dealt to {} to match the seeds",
+                    player
                 );
             }
-        });
+        }
     }
 
     #[test]
     fn test_deal_flop() {
-        let mut game = Game::build(20, 2);
+        let mut game = test_game(20, 2);
         let _ = game.join(Player::build("player1", AutoActor::new()));
         let _ = game.join(Player::build("player2", AutoActor::new()));
-        game.deal_hole_cards();
-        game.place_bets();
-        game.deal_flop();
+        game.deal_hole_cards().unwrap();
+        game.place_bets().unwrap();
+        game.deal_flop().unwrap();
         assert!(
             game.deck.len() == 44,
             "Expected game.deck.len() to be 44, was {}",
@@ -1082,14 +4283,14 @@ mod tests {
 
     #[test]
     fn test_deal_turn() {
-        let mut game = Game::build(20, 2);
+        let mut game = test_game(20, 2);
         let _ = game.join(Player::build("player1", AutoActor::new()));
         let _ = game.join(Player::build("player2", AutoActor::new()));
-        game.deal_hole_cards();
-        game.place_bets();
-        game.deal_flop();
-        game.place_bets();
-        game.deal_turn();
+        game.deal_hole_cards().unwrap();
+        game.place_bets().unwrap();
+        game.deal_flop().unwrap();
+        game.place_bets().unwrap();
+        game.deal_turn().unwrap();
         assert!(
             game.deck.len() == 42,
             "Expected game.deck.len() to be 42, was {}",
@@ -1111,16 +4312,16 @@ mod tests {
 
     #[test]
     fn test_deal_river() {
-        let mut game = Game::build(20, 2);
+        let mut game = test_game(20, 2);
         let _ = game.join(Player::build("player1", AutoActor::new()));
         let _ = game.join(Player::build("player2", AutoActor::new()));
-        game.deal_hole_cards();
-        game.place_bets();
-        game.deal_flop();
-        game.place_bets();
-        game.deal_turn();
-        game.place_bets();
-        game.deal_river();
+        game.deal_hole_cards().unwrap();
+        game.place_bets().unwrap();
+        game.deal_flop().unwrap();
+        game.place_bets().unwrap();
+        game.deal_turn().unwrap();
+        game.place_bets().unwrap();
+        game.deal_river().unwrap();
         assert!(
             game.deck.len() == 40,
             "Expected game.deck.len() to be 40, was {}",
@@ -1142,7 +4343,7 @@ mod tests {
 
     #[test]
     fn test_showdown() {
-        let mut game = Game::build(20, 2);
+        let mut game = test_game(20, 2);
         let _ = game.join(Player::build("player1", AutoActor::new()));
         let _ = game.join(Player::build("player2", AutoActor::new()));
 
@@ -1199,7 +4400,7 @@ mod tests {
             },
         ];
 
-        game.showdown();
+        game.showdown().unwrap();
 
         let w = &game.winner;
 
@@ -1280,7 +4481,7 @@ mod tests {
             },
         ];
 
-        game.showdown();
+        game.showdown().unwrap();
 
         let w = &game.winner;
 
@@ -1307,13 +4508,102 @@ mod tests {
             panic!("Expected a draw.");
         }
     }
+    #[test]
+    fn test_showdown_order_and_muck() {
+        use crate::poker::{events::EventLog, replay::ScriptedActor};
+        let log = EventLog::new();
+        let mut game = test_game(20, 3);
+        game.subscribe(log.clone());
+        // player1 and player3 would muck a beaten hand, player2 would show it.
+        let _ = game.join(Player::build("player1", ScriptedActor::new([]).mucking()));
+        let _ = game.join(Player::build("player2", AutoActor::new()));
+        let _ = game.join(Player::build("player3", ScriptedActor::new([]).mucking()));
+        let card = |rank, suit| Card { rank, suit };
+        game.community_cards = vec![
+            card(Rank::Rank2, Suit::Hearts),
+            card(Rank::Rank7, Suit::Diamonds),
+            card(Rank::Rank9, Suit::Clubs),
+            card(Rank::Jack, Suit::Spades),
+            card(Rank::King, Suit::Diamonds),
+        ];
+        // player3 has a pair of nines, player1 ace high and player2 king high.
+        let holes = [
+            (
+                "player1",
+                (Rank::Ace, Suit::Hearts),
+                (Rank::Rank4, Suit::Clubs),
+            ),
+            (
+                "player2",
+                (Rank::Queen, Suit::Hearts),
+                (Rank::Rank5, Suit::Spades),
+            ),
+            (
+                "player3",
+                (Rank::Rank9, Suit::Spades),
+                (Rank::Rank3, Suit::Clubs),
+            ),
+        ];
+        let deal = |game: &mut Game, short: Option<&str>| {
+            for (name, (r1, s1), (r2, s2)) in holes {
+                let p = game.players.get_mut(name).unwrap();
+                p.hole = Some((card(r1, s1), card(r2, s2)));
+                p.folded = false;
+                p.total_bet = if Some(name) == short { 50 } else { 100 };
+            }
+            game.last_aggressor = Some("player3".to_string());
+            log.take();
+        };
+        let shown = |events: Vec<GameEvent>| -> (Vec<String>, Vec<String>) {
+            let mut shown = Vec::new();
+            let mut mucked = Vec::new();
+            for e in events {
+                match e {
+                    GameEvent::Showdown(hands) => {
+                        shown = hands.into_iter().map(|ph| ph.name).collect()
+                    }
+                    GameEvent::HandMucked { player } => mucked.push(player),
+                    _ => {}
+                }
+            }
+            (shown, mucked)
+        };
+
+        // The last aggressor shows first, then the others in turn. player1 mucks.
+        deal(&mut game, None);
+        game.showdown().unwrap();
+        let (shown_hands, mucked) = shown(log.take());
+        assert!(
+            shown_hands == ["player3", "player2"] && mucked == ["player1"],
+            "Expected player3 and player2 to show and player1 to muck, was {:?} and {:?}",
+            shown_hands,
+            mucked
+        );
+        assert!(
+            game.players["player1"].folded,
+            "Expected player1's mucked hand to be folded"
+        );
+
+        // A hand that is only beaten by a player who is all in for less can still win a
+        // side pot, so it has to be shown.
+        deal(&mut game, Some("player3"));
+        game.showdown().unwrap();
+        let (shown_hands, mucked) = shown(log.take());
+        assert!(
+            shown_hands == ["player3", "player1", "player2"] && mucked.is_empty(),
+            "Expected every hand to be shown, was {:?} and {:?}",
+            shown_hands,
+            mucked
+        );
+    }
+
     #[test]
     fn test_distribute_pot() {
-        let mut game = Game::build(20, 3);
+        let mut game = test_game(20, 3);
         let _ = game.join(Player::build("player1", AutoActor::new()));
         let _ = game.join(Player::build("player2", AutoActor::new()));
 
-        game.deal_hole_cards();
+        game.deal_hole_cards().unwrap();
         // test outight winner
         game.pot = 120;
         game.winner = Some(Winner::SoleWinner(PlayerHand {
@@ -1325,7 +4615,7 @@ mod tests {
             cards: Vec::new(),
         }));
 
-        game.distribute_pots();
+        game.distribute_pots().unwrap();
 
         assert!(game.pot == 0, "Expected game.pot == 0, was {}", game.pot);
         assert!(
@@ -1377,7 +4667,7 @@ mod tests {
             },
         ]));
 
-        game.distribute_pots();
+        game.distribute_pots().unwrap();
 
         assert!(game.pot == 0, "Expected game.pot == 0, was {}", game.pot);
 
@@ -1405,7 +4695,7 @@ mod tests {
         // test a draw with a side pot
 
         let _ = game.join(Player::build("player3", AutoActor::new()));
-        //game.deal_hole_cards();
+        //game.deal_hole_cards().unwrap();
         // players 2 and 3 are all in
         game.players.iter_mut().for_each(|(_name, p)| {
             p.bank_roll = 0;
@@ -1457,7 +4747,7 @@ mod tests {
             },
         ]));
 
-        game.distribute_pots();
+        game.distribute_pots().unwrap();
 
         assert!(game.pot == 0, "Expected game.pot == 0, was {}", game.pot);
 
@@ -1493,15 +4783,15 @@ mod tests {
 
     #[test]
     fn test_reset_after_round() {
-        let mut game = Game::build(20, 4);
+        let mut game = test_game(20, 4);
         let _ = game.join(Player::build("player1", AutoActor::new()));
         let _ = game.join(Player::build("player2", AutoActor::new()));
         let _ = game.join(Player::build("player3", AutoActor::new()));
         let _ = game.join(Player::build("player4", AutoActor::new()));
-        game.play_round();
+        game.play_to_showdown().unwrap();
         let dealer_first = game.dealer.clone().unwrap();
         game.players.get_mut("player3").unwrap().bank_roll = 0;
-        game.reset_after_round();
+        game.reset_after_round().unwrap();
         assert!(
             !game.players.contains_key("player3"),
             "Expected player3 to be removed"
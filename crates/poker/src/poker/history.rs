@@ -0,0 +1,520 @@
+/// Hand histories, recording everything that happened in each round of a game.
+use crate::poker::{
+    card::Card,
+    config::BettingStructure,
+    events::{GameEvent, Subscriber},
+    game::{Bet, Stage},
+    player::{PlayerHand, Winner},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    fmt::{self, Debug},
+    rc::Rc,
+};
+use uuid::Uuid;
+
+/// A player's seat at the beginning of a round.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Seat {
+    pub player: String,
+    /// The player's bank roll before the ante and blinds were paid.
+    pub stack: usize,
+    pub hole_cards: Option<(Card, Card)>,
+}
+
+/// Enum for the things a player can do in a round.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ActionKind {
+    Ante,
+    SmallBlind,
+    BigBlind,
+    Straddle,
+    Bet(Bet),
+}
+
+/// Something a player did, with the number of chips it put into the pot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Action {
+    pub stage: Stage,
+    pub player: String,
+    pub action: ActionKind,
+    pub amount: usize,
+}
+
+/// Chips won from a pot, which is numbered from zero for the main pot.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PotAward {
+    pub pot: usize,
+    pub player: String,
+    pub amount: usize,
+}
+
+/// The history of a round.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HandHistory {
+    pub game: Uuid,
+    /// The number of the round in the game, starting from one.
+    pub hand_number: usize,
+    /// The time the round started, in seconds since the Unix epoch.
+    pub started: u64,
+    pub button: String,
+    pub small_blind: usize,
+    pub big_blind: usize,
+    pub ante: usize,
+    #[serde(default)]
+    pub betting_structure: BettingStructure,
+    /// The seats in order, starting to the left of the button.
+    pub seats: Vec<Seat>,
+    pub actions: Vec<Action>,
+    pub board: Vec<Card>,
+    /// The second board, if the players ran it twice.
+    #[serde(default)]
+    pub second_board: Vec<Card>,
+    /// The hands shown at the showdown, in the order they were shown.
+    pub showdown: Vec<PlayerHand>,
+    /// The players who mucked their hands at the showdown.
+    #[serde(default)]
+    pub mucked: Vec<String>,
+    pub awards: Vec<PotAward>,
+    /// The chips taken from the pots by the house.
+    #[serde(default)]
+    pub rake: usize,
+    /// The winning hand, or None if everyone else folded.
+    pub winner: Option<Winner>,
+}
+/// Implementation for HandHistory.
+impl HandHistory {
+    /// Serialise the history as JSON.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Read a history from JSON.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// The actions in a stage of the round.
+    pub fn actions_in(&self, stage: Stage) -> impl Iterator<Item = &Action> {
+        self.actions.iter().filter(move |a| a.stage == stage)
+    }
+
+    /// The ante paid by every player if the hand was a bomb pot, which is one where
+    /// antes were paid but no blinds.
+    pub fn bomb_pot(&self) -> Option<usize> {
+        let blinds = self
+            .actions
+            .iter()
+            .any(|a| matches!(a.action, ActionKind::SmallBlind | ActionKind::BigBlind));
+        let ante = self
+            .actions
+            .iter()
+            .filter(|a| a.action == ActionKind::Ante)
+            .map(|a| a.amount)
+            .max()?;
+        (!blinds).then_some(ante)
+    }
+
+    /// The total number of chips put into the pot.
+    pub fn total_pot(&self) -> usize {
+        self.actions.iter().map(|a| a.amount).sum()
+    }
+
+    /// Each player's stack at the end of the round, in seat order.
+    pub fn final_stacks(&self) -> Vec<(String, usize)> {
+        self.seats
+            .iter()
+            .map(|s| {
+                let bet: usize = self
+                    .actions
+                    .iter()
+                    .filter(|a| a.player == s.player)
+                    .map(|a| a.amount)
+                    .sum();
+                let won: usize = self
+                    .awards
+                    .iter()
+                    .filter(|a| a.player == s.player)
+                    .map(|a| a.amount)
+                    .sum();
+                (s.player.clone(), (s.stack + won).saturating_sub(bet))
+            })
+            .collect()
+    }
+
+    /// Start a history from the event at the beginning of a round.
+    fn start(event: &GameEvent) -> Option<Self> {
+        if let GameEvent::RoundStarted {
+            game,
+            round,
+            started,
+            dealer,
+            players,
+            small_blind,
+            big_blind,
+            ante,
+            betting_structure,
+        } = event
+        {
+            Some(HandHistory {
+                game: *game,
+                hand_number: *round,
+                started: *started,
+                button: dealer.clone(),
+                small_blind: *small_blind,
+                big_blind: *big_blind,
+                ante: *ante,
+                betting_structure: *betting_structure,
+                seats: players
+                    .iter()
+                    .map(|(player, stack)| Seat {
+                        player: player.clone(),
+                        stack: *stack,
+                        hole_cards: None,
+                    })
+                    .collect(),
+                actions: Vec::new(),
+                board: Vec::new(),
+                second_board: Vec::new(),
+                showdown: Vec::new(),
+                mucked: Vec::new(),
+                awards: Vec::new(),
+                rake: 0,
+                winner: None,
+            })
+        } else {
+            None
+        }
+    }
+
+    /// Add an event to the history. The stage is the stage of the round the event
+    /// happened in.
+    fn record(&mut self, stage: Stage, event: &GameEvent) {
+        let mut act = |player: &String, action: ActionKind, amount: usize| {
+            self.actions.push(Action {
+                stage,
+                player: player.clone(),
+                action,
+                amount,
+            })
+        };
+        match event {
+            GameEvent::AntePosted { player, amount } => act(player, ActionKind::Ante, *amount),
+            GameEvent::SmallBlindPosted { player, amount } => {
+                act(player, ActionKind::SmallBlind, *amount)
+            }
+            GameEvent::BigBlindPosted { player, amount } => {
+                act(player, ActionKind::BigBlind, *amount)
+            }
+            GameEvent::StraddlePosted { player, amount } => {
+                act(player, ActionKind::Straddle, *amount)
+            }
+            GameEvent::BetPlaced {
+                player,
+                bet,
+                amount,
+                ..
+            } => act(player, ActionKind::Bet(*bet), *amount),
+            GameEvent::HoleCardsDealt { player, cards } => {
+                if let Some(seat) = self.seats.iter_mut().find(|s| &s.player == player) {
+                    seat.hole_cards = Some(*cards);
+                }
+            }
+            GameEvent::CommunityCardsDealt { cards, .. } => self.board.extend(cards),
+            GameEvent::SecondBoardDealt(cards) => self.second_board = cards.clone(),
+            GameEvent::HandMucked { player } => self.mucked.push(player.clone()),
+            GameEvent::Showdown(hands) => self.showdown = hands.clone(),
+            // An uncalled bet is counted as part of what the player won from the main
+            // pot, which is the only pot when everyone else folds.
+            GameEvent::UncalledBetReturned { player, amount } => self.awards.push(PotAward {
+                pot: 0,
+                player: player.clone(),
+                amount: *amount,
+            }),
+            GameEvent::PotAwarded {
+                pot,
+                player,
+                amount,
+            } => match self
+                .awards
+                .iter_mut()
+                .find(|a| a.pot == *pot && &a.player == player)
+            {
+                Some(award) => award.amount += amount,
+                None => self.awards.push(PotAward {
+                    pot: *pot,
+                    player: player.clone(),
+                    amount: *amount,
+                }),
+            },
+            GameEvent::RakeTaken { amount } => self.rake += amount,
+            GameEvent::RoundWinner(w) => self.winner = Some(w.clone()),
+            _ => {}
+        }
+    }
+}
+
+/// Split a time in seconds since the Unix epoch into a date and time in UTC, as
+/// `(year, month, day, hour, minute, second)`.
+pub(crate) fn to_date_time(secs: u64) -> (u64, u64, u64, u64, u64, u64) {
+    // Convert days since the epoch to a date in the Gregorian calendar.
+    let z = secs / 86400 + 719468;
+    let era = z / 146097;
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + if month <= 2 { 1 } else { 0 };
+    let time = secs % 86400;
+    (year, month, day, time / 3600, time % 3600 / 60, time % 60)
+}
+
+/// The time in seconds since the Unix epoch of a date and time in UTC. The inverse of
+/// `to_date_time`.
+pub(crate) fn from_date_time(
+    year: u64,
+    month: u64,
+    day: u64,
+    hour: u64,
+    minute: u64,
+    second: u64,
+) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let yoe = year - era * 400;
+    let mp = if month > 2 { month - 3 } else { month + 9 };
+    let doy = (153 * mp + 2) / 5 + day - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    let days = era * 146097 + doe - 719468;
+    days * 86400 + hour * 3600 + minute * 60 + second
+}
+
+/// The state of a recorder.
+#[derive(Default)]
+struct Recording {
+    current: Option<HandHistory>,
+    stage: Option<Stage>,
+    histories: Vec<HandHistory>,
+    callback: Option<Box<dyn FnMut(HandHistory)>>,
+}
+
+/// A subscriber that builds a `HandHistory` for every round of a game. Rounds that
+/// are abandoned are left out. Clones share the same histories, so a clone can be
+/// given to the game and the original used to read them.
+#[derive(Clone, Default)]
+pub struct HandHistoryRecorder {
+    recording: Rc<RefCell<Recording>>,
+}
+/// Implementation for HandHistoryRecorder.
+impl HandHistoryRecorder {
+    /// Construct a recorder that keeps the histories.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Construct a recorder that passes each history to a callback at the end of the
+    /// round, instead of keeping it.
+    pub fn with_callback(callback: impl FnMut(HandHistory) + 'static) -> Self {
+        let recorder = Self::default();
+        recorder.recording.borrow_mut().callback = Some(Box::new(callback));
+        recorder
+    }
+
+    /// The histories of the rounds finished so far.
+    pub fn histories(&self) -> Vec<HandHistory> {
+        self.recording.borrow().histories.clone()
+    }
+
+    /// Serialise the histories of the rounds finished so far as a JSON array.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(&self.recording.borrow().histories)
+    }
+}
+/// Implementation of Debug trait for HandHistoryRecorder.
+impl Debug for HandHistoryRecorder {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let recording = self.recording.borrow();
+        f.debug_struct("HandHistoryRecorder")
+            .field("current", &recording.current)
+            .field("histories", &recording.histories.len())
+            .finish()
+    }
+}
+/// Implementation of Subscriber for HandHistoryRecorder.
+impl Subscriber for HandHistoryRecorder {
+    fn notify(&self, event: &GameEvent) {
+        let mut recording = self.recording.borrow_mut();
+        match event {
+            GameEvent::RoundStarted { .. } => {
+                recording.current = HandHistory::start(event);
+                recording.stage = Some(Stage::Blinds);
+            }
+            GameEvent::RoundAbandoned(_) => {
+                recording.current = None;
+            }
+            GameEvent::HoleCardsDealt { .. } => recording.stage = Some(Stage::PreFlop),
+            GameEvent::CommunityCardsDealt { stage, .. } => recording.stage = Some(*stage),
+            GameEvent::Showdown(_) => recording.stage = Some(Stage::ShowDown),
+            _ => {}
+        }
+        let stage = recording.stage.unwrap_or(Stage::Blinds);
+        let Some(history) = recording.current.as_mut() else {
+            return;
+        };
+        history.record(stage, event);
+        if let GameEvent::RoundWinner(_) | GameEvent::RoundWonUncontested { .. } = event
+            && let Some(history) = recording.current.take()
+        {
+            match recording.callback.as_mut() {
+                Some(callback) => callback(history),
+                None => recording.histories.push(history),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::{
+        autoactor::AutoActor,
+        betting_strategy::{modest_betting_strategy, six_max},
+        blinds::ManualClock,
+        config::GameConfig,
+        game::Game,
+        player::Player,
+    };
+    use std::time::Duration;
+
+    fn recorded_game(recorder: HandHistoryRecorder) {
+        let config = GameConfig::builder()
+            .big_blind(20)
+            .max_players(3)
+            .seed(7)
+            .build()
+            .unwrap();
+        let mut game = Game::from_config(config).unwrap();
+        game.subscribe(recorder);
+        let _ = game.join(Player::build("Alice", AutoActor::build(six_max)));
+        let _ = game.join(Player::build(
+            "Bob",
+            AutoActor::build(modest_betting_strategy),
+        ));
+        let _ = game.join(Player::build("Carol", AutoActor::new()));
+        game.play().unwrap();
+    }
+
+    #[test]
+    fn test_histories() {
+        let recorder = HandHistoryRecorder::new();
+        recorded_game(recorder.clone());
+        let histories = recorder.histories();
+        assert!(!histories.is_empty(), "Expected some hand histories");
+        for (i, h) in histories.iter().enumerate() {
+            assert!(
+                h.hand_number == i + 1,
+                "Expected hand {}, was {}",
+                i + 1,
+                h.hand_number
+            );
+            let awarded: usize = h.awards.iter().map(|a| a.amount).sum();
+            assert!(
+                awarded == h.total_pot(),
+                "Expected the whole pot of {} to be awarded in hand {}, was {}",
+                h.total_pot(),
+                h.hand_number,
+                awarded
+            );
+            assert!(
+                h.seats.iter().all(|s| s.hole_cards.is_some()),
+                "Expected hole cards for every seat in hand {}",
+                h.hand_number
+            );
+            assert!(
+                h.board.len() == 5 || (h.winner.is_none() && h.showdown.is_empty()),
+                "Expected a full board in hand {} unless it was won uncontested",
+                h.hand_number
+            );
+            let blinds: Vec<ActionKind> = h.actions_in(Stage::Blinds).map(|a| a.action).collect();
+            assert!(
+                blinds == vec![ActionKind::SmallBlind, ActionKind::BigBlind],
+                "Expected the blinds to be posted in hand {}, was {:?}",
+                h.hand_number,
+                blinds
+            );
+        }
+    }
+
+    #[test]
+    fn test_json() {
+        let recorder = HandHistoryRecorder::new();
+        recorded_game(recorder.clone());
+        let history = recorder.histories().remove(0);
+        let json = history.to_json().unwrap();
+        let restored = HandHistory::from_json(&json).unwrap();
+        assert!(
+            restored.to_json().unwrap() == json,
+            "Expected the history to survive a round trip through JSON"
+        );
+    }
+
+    #[test]
+    fn test_start_time() {
+        let clock = Rc::new(ManualClock::at(1_700_000_000));
+        let recorder = HandHistoryRecorder::new();
+        let config = GameConfig::builder()
+            .big_blind(20)
+            .max_players(3)
+            .build()
+            .unwrap();
+        let mut game = Game::from_config(config).unwrap();
+        game.set_clock(clock.clone());
+        game.subscribe(recorder.clone());
+        for name in ["Alice", "Bob", "Carol"] {
+            let _ = game.join(Player::build(name, AutoActor::new()));
+        }
+        game.play_round().unwrap();
+        clock.advance(Duration::from_secs(90));
+        game.play_round().unwrap();
+        let started: Vec<u64> = recorder.histories().iter().map(|h| h.started).collect();
+        assert!(
+            started == vec![1_700_000_000, 1_700_000_090],
+            "Expected the hands to start at the clock's time, was {:?}",
+            started
+        );
+    }
+
+    #[test]
+    fn test_date_time() {
+        let date = to_date_time(1_700_000_000);
+        assert!(
+            date == (2023, 11, 14, 22, 13, 20),
+            "Unexpected date {:?}",
+            date
+        );
+        assert!(to_date_time(0) == (1970, 1, 1, 0, 0, 0));
+        for secs in [0, 951_782_400, 1_700_000_000, 4_107_542_399] {
+            let (y, m, d, h, min, s) = to_date_time(secs);
+            assert!(
+                from_date_time(y, m, d, h, min, s) == secs,
+                "Expected {} to survive a round trip",
+                secs
+            );
+        }
+    }
+
+    #[test]
+    fn test_callback() {
+        let count = Rc::new(RefCell::new(0));
+        let counter = count.clone();
+        let recorder = HandHistoryRecorder::with_callback(move |_| *counter.borrow_mut() += 1);
+        recorded_game(recorder.clone());
+        assert!(
+            *count.borrow() > 0 && recorder.histories().is_empty(),
+            "Expected the histories to be passed to the callback, not kept"
+        );
+    }
+}
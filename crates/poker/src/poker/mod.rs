@@ -1,22 +1,39 @@
 use autoactor::AutoActor;
+use config::{ConfigError, GameConfig};
 use game::Game;
 use player::Player;
 
+pub mod audit;
 pub mod autoactor;
 pub mod betting_strategy;
+pub mod blinds;
 pub mod card;
 pub mod compare;
+pub mod config;
+pub mod error;
+pub mod events;
+pub mod fair;
 pub mod game;
+pub mod history;
+pub mod mtt;
 pub mod names;
+pub mod ohh;
 pub mod player;
+pub mod pokerstars;
+pub mod replay;
 pub mod sequence;
+pub mod session;
+pub mod snapshot;
+pub mod stats;
 mod test_data;
+pub mod tournament;
 
-/// Create a new game with one supplied player and the supplied number of auto players.
-/// Supply an interactive player to create a one player game.
+/// Create a new game with one supplied player, filling the other seats at the table
+/// with auto players. Supply an interactive player to create a one player game.
 /// The auto players use either the `six_max` or `modest` betting strategies.
-pub fn new_game_one_player(player: Player, big_blind: usize, num_auto_players: u8) -> Game {
-    let mut g = Game::build(big_blind, num_auto_players + 1);
+pub fn new_game_one_player(player: Player, config: GameConfig) -> Result<Game, ConfigError> {
+    let num_auto_players = config.max_players - 1;
+    let mut g = Game::from_config(config)?;
     g.join(player).unwrap_or_else(|e| eprintln!("{e:?}"));
     // make an iterator of actors using different strategies.
     let actors = (0..num_auto_players).map(|i| {
@@ -33,16 +50,25 @@ pub fn new_game_one_player(player: Player, big_blind: usize, num_auto_players: u
         let auto_player = Player::build(name, actor);
         g.join(auto_player).unwrap_or_else(|e| eprintln!("{e:?}"));
     });
-    g
+    Ok(g)
 }
 
-/// Create a new game with the supplied players
-pub fn new_game_with_players(players: Vec<Player>, big_blind: usize) -> Game {
-    let mut g = Game::build(big_blind, players.len() as u8);
+/// Create a new game with the supplied players.
+pub fn new_game_with_players(
+    players: Vec<Player>,
+    config: GameConfig,
+) -> Result<Game, ConfigError> {
+    if players.len() > config.max_players as usize {
+        return Err(ConfigError::TooManyPlayers {
+            players: players.len(),
+            max_players: config.max_players,
+        });
+    }
+    let mut g = Game::from_config(config)?;
     for p in players {
         g.join(p).unwrap_or_else(|e| eprintln!("{e:?}"));
     }
-    g
+    Ok(g)
 }
 
 /// Rotate a vector (V) by a given index (I).
@@ -66,6 +92,26 @@ pub fn rotate_vector<T: Clone>(v: &[T], i: usize) -> Vec<T> {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_new_game_with_players() {
+        let players = (0..3)
+            .map(|i| Player::build(&format!("player{}", i), AutoActor::new()))
+            .collect();
+        let config = GameConfig::builder().max_players(2).build().unwrap();
+        let result = new_game_with_players(players, config);
+        assert!(
+            matches!(
+                result,
+                Err(ConfigError::TooManyPlayers {
+                    players: 3,
+                    max_players: 2
+                })
+            ),
+            "Expected too many players, was {:?}",
+            result
+        );
+    }
+
     #[test]
     fn test_rotate_vector() {
         let vec1 = vec![1, 2, 3, 4, 5, 6, 7, 8, 9];
@@ -0,0 +1,372 @@
+/// Multi-table tournaments, run by a director that plays a hand at each table in turn
+/// and moves players between the tables to keep them balanced. Tables are broken as
+/// players are knocked out, until the players left are brought together at a final
+/// table.
+use crate::poker::{
+    blinds::BlindLevel,
+    error::GameError,
+    game::Game,
+    names,
+    player::Player,
+    tournament::{self, EliminationTracker, Standings, TournamentError},
+};
+use serde::{Deserialize, Serialize};
+
+/// The settings for a multi-table tournament.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct MttConfig {
+    /// The amount each player pays to enter, which goes into the prize pool.
+    pub buy_in: usize,
+    /// The number of chips each player starts with.
+    pub starting_stack: usize,
+    /// The blind schedule followed at each table, which must have at least one level.
+    pub blind_levels: Vec<BlindLevel>,
+    /// The percentage of the prize pool paid to each place, starting from first. If
+    /// empty, `tournament::standard_payouts` are used.
+    #[serde(default)]
+    pub payouts: Vec<u32>,
+    /// The number of seats at each table.
+    pub table_size: u8,
+    /// Seed for shuffling the decks. Each table's seed is this plus the table's number.
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// A player moved from one table to another.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct TableMove {
+    pub player: String,
+    /// The number of the table the player left.
+    pub from: usize,
+    /// The number of the table the player joined.
+    pub to: usize,
+    /// Whether the player moved because their table was broken, rather than to balance
+    /// the tables.
+    pub broken: bool,
+}
+
+/// A table in the tournament, numbered from one.
+#[derive(Debug)]
+struct Table {
+    number: usize,
+    game: Game,
+}
+/// Implementation for Table.
+impl Table {
+    fn num_players(&self) -> usize {
+        self.game.bank_rolls().len()
+    }
+}
+
+/// Runs a multi-table tournament.
+#[derive(Debug)]
+pub struct TournamentDirector {
+    config: MttConfig,
+    tables: Vec<Table>,
+    payouts: Vec<u32>,
+    prize_pool: usize,
+    tracker: EliminationTracker,
+    moves: Vec<TableMove>,
+}
+/// Implementation for TournamentDirector.
+impl TournamentDirector {
+    /// Seat the players at as few tables as they fit at, dealing them out in turn so
+    /// that the tables are balanced. Names are made unique across the tournament.
+    /// Returns an error if a player can't be seated.
+    pub fn build(config: MttConfig, players: Vec<Player>) -> Result<Self, TournamentError> {
+        let num_players = players.len();
+        let payouts = tournament::payouts_for(&config.payouts, num_players)?;
+        let num_tables = num_players
+            .div_ceil(config.table_size.max(1) as usize)
+            .max(1);
+        let tracker = EliminationTracker::default();
+        let tables = (1..=num_tables)
+            .map(|number| {
+                let game = tournament::new_table(
+                    &config.blind_levels,
+                    config.starting_stack,
+                    config.table_size,
+                    config.seed.map(|seed| seed + number as u64),
+                    &tracker,
+                )?;
+                Ok(Table { number, game })
+            })
+            .collect::<Result<Vec<Table>, TournamentError>>()?;
+        let mut director = TournamentDirector {
+            prize_pool: config.buy_in * num_players,
+            config,
+            tables,
+            payouts,
+            tracker,
+            moves: Vec::new(),
+        };
+        let mut taken: Vec<String> = Vec::new();
+        for (i, mut p) in players.into_iter().enumerate() {
+            p.name = names::uniquify_name(&p.name, &taken);
+            taken.push(p.name.clone());
+            let table = &mut director.tables[i % num_tables].game;
+            let player = p.name.clone();
+            table
+                .join(p)
+                .map_err(|error| TournamentError::Join { player, error })?;
+        }
+        Ok(director)
+    }
+
+    pub fn config(&self) -> &MttConfig {
+        &self.config
+    }
+
+    /// The tables still in play with their numbers.
+    pub fn tables(&self) -> impl Iterator<Item = (usize, &Game)> {
+        self.tables.iter().map(|t| (t.number, &t.game))
+    }
+
+    /// The table with the given number, e.g. to add subscribers.
+    pub fn table(&mut self, number: usize) -> Option<&mut Game> {
+        self.tables
+            .iter_mut()
+            .find(|t| t.number == number)
+            .map(|t| &mut t.game)
+    }
+
+    /// The players moved between tables so far, in order.
+    pub fn moves(&self) -> &[TableMove] {
+        &self.moves
+    }
+
+    /// The number of players still in the tournament.
+    pub fn players_remaining(&self) -> usize {
+        self.tables.iter().map(|t| t.num_players()).sum()
+    }
+
+    /// Whether the players left have been brought together at one table.
+    pub fn final_table(&self) -> bool {
+        self.tables.len() == 1
+    }
+
+    /// Whether one player has all the chips.
+    pub fn is_over(&self) -> bool {
+        self.players_remaining() <= 1
+    }
+
+    /// Play a hand at every table with enough players, then break and balance the
+    /// tables. Returns whether the tournament is over. The tables play their hands one
+    /// after another rather than at the same time, since a game and its players'
+    /// actors can't be moved to another thread. If a hand fails the error is returned
+    /// as in `Game::play`, and the tournament can be carried on by calling `play_hand`
+    /// again.
+    pub fn play_hand(&mut self) -> Result<bool, GameError> {
+        for table in self.tables.iter_mut() {
+            if table.num_players() >= 2 {
+                table.game.play_round()?;
+            }
+        }
+        self.balance()?;
+        Ok(self.is_over())
+    }
+
+    /// Play the tournament until one player has all the chips, returning the final
+    /// standings.
+    pub fn play(&mut self) -> Result<Standings, GameError> {
+        while !self.is_over() {
+            self.play_hand()?;
+        }
+        Ok(self.standings())
+    }
+
+    /// The standings so far, with the players still in ranked by their chips.
+    pub fn standings(&self) -> Standings {
+        let remaining = self
+            .tables
+            .iter()
+            .flat_map(|t| t.game.bank_rolls())
+            .collect();
+        self.tracker
+            .standings(remaining, self.prize_pool, &self.payouts)
+    }
+
+    /// Break tables while the players left would fit at fewer of them, then move
+    /// players from the biggest tables to the smallest until no table has more than one
+    /// player more than another. The player moved to balance the tables is the one who
+    /// was last on the button, so that they don't miss paying the blinds.
+    fn balance(&mut self) -> Result<(), GameError> {
+        let table_size = self.config.table_size as usize;
+        while self.tables.len() > self.players_remaining().div_ceil(table_size).max(1) {
+            let i = self.smallest();
+            let mut broken = self.tables.remove(i);
+            for (name, _) in broken.game.bank_rolls() {
+                let player = broken.game.take_player(&name)?;
+                let to = self.smallest();
+                self.move_player(player, broken.number, to, true)?;
+            }
+        }
+        loop {
+            let (smallest, biggest) = (self.smallest(), self.biggest());
+            if self.tables[biggest].num_players() <= self.tables[smallest].num_players() + 1 {
+                return Ok(());
+            }
+            let table = &mut self.tables[biggest];
+            let Some((name, _)) = table.game.bank_rolls().pop() else {
+                return Ok(());
+            };
+            let player = table.game.take_player(&name)?;
+            let from = table.number;
+            self.move_player(player, from, smallest, false)?;
+        }
+    }
+
+    /// Seat a player at the table at the given index, recording the move.
+    fn move_player(
+        &mut self,
+        player: Player,
+        from: usize,
+        to: usize,
+        broken: bool,
+    ) -> Result<(), GameError> {
+        let table = &mut self.tables[to];
+        self.moves.push(TableMove {
+            player: player.name.clone(),
+            from,
+            to: table.number,
+            broken,
+        });
+        table.game.seat_player(player)
+    }
+
+    /// The index of the table with the fewest players.
+    fn smallest(&self) -> usize {
+        (0..self.tables.len())
+            .min_by_key(|i| self.tables[*i].num_players())
+            .unwrap_or(0)
+    }
+
+    /// The index of the table with the most players.
+    fn biggest(&self) -> usize {
+        (0..self.tables.len())
+            .max_by_key(|i| self.tables[*i].num_players())
+            .unwrap_or(0)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::{autoactor::AutoActor, blinds::LevelDuration};
+
+    fn config() -> MttConfig {
+        MttConfig {
+            buy_in: 10,
+            starting_stack: 1000,
+            blind_levels: vec![
+                BlindLevel {
+                    small_blind: 25,
+                    big_blind: 50,
+                    ante: 0,
+                    duration: LevelDuration::Hands(5),
+                },
+                BlindLevel {
+                    small_blind: 100,
+                    big_blind: 200,
+                    ante: 25,
+                    duration: LevelDuration::Hands(5),
+                },
+                BlindLevel {
+                    small_blind: 300,
+                    big_blind: 600,
+                    ante: 75,
+                    duration: LevelDuration::Hands(5),
+                },
+            ],
+            payouts: vec![],
+            table_size: 6,
+            seed: Some(11),
+        }
+    }
+
+    fn players(n: usize) -> Vec<Player> {
+        (1..=n)
+            .map(|i| Player::build(&format!("player{}", i), AutoActor::new()))
+            .collect()
+    }
+
+    fn table_sizes(director: &TournamentDirector) -> Vec<usize> {
+        director
+            .tables()
+            .map(|(_, game)| game.bank_rolls().len())
+            .collect()
+    }
+
+    #[test]
+    fn test_seating() {
+        let mut players = players(13);
+        players.push(Player::build("player1", AutoActor::new()));
+        let director = TournamentDirector::build(config(), players).unwrap();
+        let sizes = table_sizes(&director);
+        assert!(
+            sizes == vec![5, 5, 4],
+            "Expected 14 players at three balanced tables, was {:?}",
+            sizes
+        );
+        let mut names: Vec<String> = director
+            .tables()
+            .flat_map(|(_, game)| game.bank_rolls())
+            .map(|(name, _)| name)
+            .collect();
+        names.sort();
+        names.dedup();
+        assert!(
+            names.len() == 14,
+            "Expected unique names across the tables, was {:?}",
+            names
+        );
+    }
+
+    #[test]
+    fn test_tournament() {
+        let mut director = TournamentDirector::build(config(), players(14)).unwrap();
+        let mut reached_final_table = false;
+        while !director.play_hand().unwrap() {
+            let sizes = table_sizes(&director);
+            let (min, max) = (sizes.iter().min().unwrap(), sizes.iter().max().unwrap());
+            assert!(
+                max - min <= 1,
+                "Expected the tables to be balanced, was {:?}",
+                sizes
+            );
+            assert!(
+                sizes.len() == director.players_remaining().div_ceil(6),
+                "Expected tables to be broken as players are knocked out, was {:?}",
+                sizes
+            );
+            reached_final_table |= director.final_table();
+        }
+        assert!(
+            reached_final_table && director.moves().iter().any(|m| m.broken),
+            "Expected tables to be broken down to a final table, was {:?}",
+            director.moves()
+        );
+        let standings = director.standings();
+        let prizes: Vec<usize> = standings.places.iter().map(|s| s.prize).collect();
+        assert!(
+            standings.places.len() == 14 && prizes[..4] == [70, 42, 28, 0],
+            "Expected 14 places with the top three paid, was {:?}",
+            standings
+        );
+        let winner = standings.winner().unwrap();
+        assert!(
+            director.tables().next().unwrap().1.bank_rolls() == vec![(winner.to_string(), 14000)],
+            "Expected the winner to have every chip, was {:?}",
+            standings
+        );
+        let hands: Vec<usize> = standings.places[1..]
+            .iter()
+            .map(|s| s.eliminated_in.unwrap())
+            .collect();
+        assert!(
+            hands.windows(2).all(|w| w[0] >= w[1]),
+            "Expected the players to be placed in the order they were knocked out, was {:?}",
+            standings
+        );
+    }
+}
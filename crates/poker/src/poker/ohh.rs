@@ -0,0 +1,806 @@
+/// Hand histories in the Open Hand History (OHH) JSON standard, which is described at
+/// https://hh-specs.handhistory.org. A file holds one JSON object per hand, each with
+/// the hand under an `ohh` key, separated by blank lines.
+use crate::poker::{
+    card::Card,
+    compare,
+    config::BettingStructure,
+    game::{Bet, Game, Stage},
+    history::{Action, ActionKind, HandHistory, PotAward, Seat, from_date_time, to_date_time},
+    player::PlayerHand,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+};
+use uuid::Uuid;
+
+/// The version of the standard written by `export`.
+pub const SPEC_VERSION: &str = "1.4.6";
+/// The site name written by `export`.
+pub const SITE_NAME: &str = "poker";
+
+/// Errors found when importing a hand history.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum OhhError {
+    /// The input isn't valid OHH JSON.
+    Json(String),
+    /// A field has a value the library can't use.
+    Field {
+        field: String,
+        value: String,
+    },
+    Card(String),
+    /// An amount isn't a whole number of chips.
+    Amount(f64),
+    /// An action or a pot refers to a player who isn't in the hand.
+    UnknownPlayer(usize),
+}
+/// Implementation of Display trait for OhhError.
+impl Display for OhhError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            OhhError::Json(e) => write!(f, "Invalid hand history: {}", e),
+            OhhError::Field { field, value } => {
+                write!(f, "Invalid value for {}: {}", field, value)
+            }
+            OhhError::Card(card) => write!(f, "Invalid card: {}", card),
+            OhhError::Amount(amount) => {
+                write!(f, "Amount {} is not a whole number of chips", amount)
+            }
+            OhhError::UnknownPlayer(id) => write!(f, "No player with id {}", id),
+        }
+    }
+}
+/// Implementation of Error trait for OhhError.
+impl std::error::Error for OhhError {}
+
+/// A hand in the OHH standard. Amounts are numbers of chips.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OpenHandHistory {
+    pub spec_version: String,
+    pub site_name: String,
+    #[serde(default)]
+    pub network_name: String,
+    #[serde(default)]
+    pub internal_version: String,
+    #[serde(default)]
+    pub tournament: bool,
+    pub game_number: String,
+    /// The start of the hand in ISO 8601 format, e.g. `2023-11-14T22:13:20Z`.
+    pub start_date_utc: String,
+    pub table_name: String,
+    pub table_size: usize,
+    pub game_type: String,
+    pub bet_limit: BetLimit,
+    pub dealer_seat: usize,
+    pub small_blind_amount: f64,
+    pub big_blind_amount: f64,
+    #[serde(default)]
+    pub ante_amount: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub hero_player_id: Option<usize>,
+    pub players: Vec<OhhPlayer>,
+    pub rounds: Vec<OhhRound>,
+    #[serde(default)]
+    pub pots: Vec<OhhPot>,
+}
+
+/// The betting structure of a hand: `NL` for no limit, `PL` for pot limit or `FL` for
+/// fixed limit. The cap of a fixed-limit hand is the number of bets and raises allowed
+/// in each round of betting. The standard doesn't give the sizes of the bets in a
+/// fixed-limit hand, so they are written in the extension fields `small_bet` and
+/// `big_bet`, which other tools ignore.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct BetLimit {
+    pub bet_type: String,
+    #[serde(default)]
+    pub bet_cap: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub small_bet: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub big_bet: Option<f64>,
+}
+
+/// A player at the table.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OhhPlayer {
+    pub id: usize,
+    pub seat: usize,
+    pub name: String,
+    pub starting_stack: f64,
+}
+
+/// Enum for the streets of a hand.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum Street {
+    Preflop,
+    Flop,
+    Turn,
+    River,
+    Showdown,
+}
+
+/// The cards dealt on a street and the actions taken on it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OhhRound {
+    pub id: usize,
+    pub street: Street,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cards: Vec<String>,
+    pub actions: Vec<OhhAction>,
+}
+
+/// Enum for the kinds of action in the standard.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub enum ActionType {
+    #[serde(rename = "Dealt Cards")]
+    DealtCards,
+    #[serde(rename = "Mucks Cards")]
+    MucksCards,
+    #[serde(rename = "Shows Cards")]
+    ShowsCards,
+    #[serde(rename = "Post Ante")]
+    PostAnte,
+    #[serde(rename = "Post SB")]
+    PostSmallBlind,
+    #[serde(rename = "Post BB")]
+    PostBigBlind,
+    Straddle,
+    Fold,
+    Check,
+    Bet,
+    Raise,
+    Call,
+    /// Any other action, such as a dead blind or a player sitting out, which the library
+    /// doesn't model. These are skipped when importing.
+    #[serde(other)]
+    Other,
+}
+
+/// An action by a player. The amount is the number of chips the action put into the pot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OhhAction {
+    pub action_number: usize,
+    pub player_id: usize,
+    pub action: ActionType,
+    #[serde(default)]
+    pub amount: f64,
+    #[serde(default)]
+    pub is_allin: bool,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cards: Vec<String>,
+}
+
+/// A pot, numbered from zero for the main pot, and the players who won it.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OhhPot {
+    pub number: usize,
+    pub amount: f64,
+    #[serde(default)]
+    pub rake: f64,
+    pub player_wins: Vec<OhhWin>,
+}
+
+/// Chips won from a pot by a player.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OhhWin {
+    pub player_id: usize,
+    pub win_amount: f64,
+    #[serde(default)]
+    pub contributed_rake: f64,
+}
+
+/// The wrapper around each hand in an OHH file.
+#[derive(Serialize, Deserialize)]
+struct OhhDocument {
+    ohh: OpenHandHistory,
+}
+
+/// Convert a hand history to the OHH standard. Players are given ids from zero and
+/// seats from one, in the order of the history's seats, so the button has the last seat.
+/// The standard has no place for the sizes of fixed-limit bets, which are taken to be
+/// the big blind and twice the big blind when the hand is imported.
+pub fn export(history: &HandHistory) -> OpenHandHistory {
+    let ids: HashMap<&str, usize> = history
+        .seats
+        .iter()
+        .enumerate()
+        .map(|(i, s)| (s.player.as_str(), i))
+        .collect();
+    let id_of = |player: &str| ids.get(player).copied().unwrap_or_default();
+    let cards = |cards: &[Card]| cards.iter().map(Card::short_name).collect::<Vec<String>>();
+    let (year, month, day, hour, minute, second) = to_date_time(history.started);
+
+    let mut rounds: Vec<OhhRound> = Vec::new();
+    let mut number = 0;
+    let mut next_number = || {
+        number += 1;
+        number
+    };
+    // The amount each player has bet on the current street and the highest bet, to tell
+    // bets from raises and calls.
+    let mut street: HashMap<&str, usize> = HashMap::new();
+    let streets = [
+        (Street::Preflop, 0..0, vec![Stage::Blinds, Stage::PreFlop]),
+        (Street::Flop, 0..3, vec![Stage::Flop]),
+        (Street::Turn, 3..4, vec![Stage::Turn]),
+        (Street::River, 4..5, vec![Stage::River]),
+    ];
+    for (id, (name, dealt, stages)) in streets.into_iter().enumerate() {
+        if dealt.end > history.board.len() {
+            break;
+        }
+        street.clear();
+        let mut highest = 0;
+        let mut actions: Vec<OhhAction> = Vec::new();
+        for stage in stages {
+            if stage == Stage::PreFlop {
+                for seat in &history.seats {
+                    if let Some((c1, c2)) = seat.hole_cards {
+                        actions.push(OhhAction {
+                            action_number: next_number(),
+                            player_id: id_of(&seat.player),
+                            action: ActionType::DealtCards,
+                            amount: 0.0,
+                            is_allin: false,
+                            cards: cards(&[c1, c2]),
+                        });
+                    }
+                }
+            }
+            for a in history.actions_in(stage) {
+                let bet = street.entry(a.player.as_str()).or_default();
+                if a.action != ActionKind::Ante {
+                    *bet += a.amount;
+                }
+                let total = *bet;
+                let action = match a.action {
+                    ActionKind::Ante => ActionType::PostAnte,
+                    ActionKind::SmallBlind => ActionType::PostSmallBlind,
+                    ActionKind::BigBlind => ActionType::PostBigBlind,
+                    ActionKind::Straddle => ActionType::Straddle,
+                    ActionKind::Bet(Bet::Fold) => ActionType::Fold,
+                    ActionKind::Bet(Bet::Check) => ActionType::Check,
+                    ActionKind::Bet(_) if total <= highest => ActionType::Call,
+                    ActionKind::Bet(_) if highest == 0 => ActionType::Bet,
+                    ActionKind::Bet(_) => ActionType::Raise,
+                };
+                highest = highest.max(total);
+                actions.push(OhhAction {
+                    action_number: next_number(),
+                    player_id: id_of(&a.player),
+                    action,
+                    amount: a.amount as f64,
+                    is_allin: matches!(a.action, ActionKind::Bet(Bet::AllIn(_))),
+                    cards: Vec::new(),
+                });
+            }
+        }
+        rounds.push(OhhRound {
+            id,
+            street: name,
+            cards: cards(&history.board[dealt]),
+            actions,
+        });
+    }
+    if !history.showdown.is_empty() {
+        let mut actions: Vec<OhhAction> = history
+            .showdown
+            .iter()
+            .filter_map(|ph| {
+                let seat = history.seats.iter().find(|s| s.player == ph.name)?;
+                let (c1, c2) = seat.hole_cards?;
+                Some(OhhAction {
+                    action_number: next_number(),
+                    player_id: id_of(&ph.name),
+                    action: ActionType::ShowsCards,
+                    amount: 0.0,
+                    is_allin: false,
+                    cards: cards(&[c1, c2]),
+                })
+            })
+            .collect();
+        actions.extend(history.mucked.iter().map(|player| OhhAction {
+            action_number: next_number(),
+            player_id: id_of(player),
+            action: ActionType::MucksCards,
+            amount: 0.0,
+            is_allin: false,
+            cards: Vec::new(),
+        }));
+        rounds.push(OhhRound {
+            id: rounds.len(),
+            street: Street::Showdown,
+            cards: Vec::new(),
+            actions,
+        });
+    }
+
+    let mut pots: Vec<OhhPot> = Vec::new();
+    for award in &history.awards {
+        if !pots.iter().any(|p| p.number == award.pot) {
+            pots.push(OhhPot {
+                number: award.pot,
+                amount: 0.0,
+                rake: 0.0,
+                player_wins: Vec::new(),
+            });
+        }
+        if let Some(pot) = pots.iter_mut().find(|p| p.number == award.pot) {
+            pot.amount += award.amount as f64;
+            pot.player_wins.push(OhhWin {
+                player_id: id_of(&award.player),
+                win_amount: award.amount as f64,
+                contributed_rake: 0.0,
+            });
+        }
+    }
+    // The rake came out of the main pot, whose amount is from before it was taken.
+    if let Some(pot) = pots.iter_mut().min_by_key(|p| p.number) {
+        pot.rake = history.rake as f64;
+        pot.amount += history.rake as f64;
+    }
+
+    OpenHandHistory {
+        spec_version: SPEC_VERSION.to_string(),
+        site_name: SITE_NAME.to_string(),
+        network_name: SITE_NAME.to_string(),
+        internal_version: env!("CARGO_PKG_VERSION").to_string(),
+        tournament: false,
+        game_number: history.hand_number.to_string(),
+        start_date_utc: format!(
+            "{}-{:02}-{:02}T{:02}:{:02}:{:02}Z",
+            year, month, day, hour, minute, second
+        ),
+        table_name: history.game.to_string(),
+        table_size: history.seats.len(),
+        game_type: "Holdem".to_string(),
+        bet_limit: match history.betting_structure {
+            BettingStructure::NoLimit => BetLimit {
+                bet_type: "NL".to_string(),
+                bet_cap: 0.0,
+                small_bet: None,
+                big_bet: None,
+            },
+            BettingStructure::PotLimit => BetLimit {
+                bet_type: "PL".to_string(),
+                bet_cap: 0.0,
+                small_bet: None,
+                big_bet: None,
+            },
+            BettingStructure::FixedLimit {
+                small_bet,
+                big_bet,
+                cap,
+            } => BetLimit {
+                bet_type: "FL".to_string(),
+                bet_cap: cap as f64,
+                small_bet: Some(small_bet as f64),
+                big_bet: Some(big_bet as f64),
+            },
+        },
+        dealer_seat: id_of(&history.button) + 1,
+        small_blind_amount: history.small_blind as f64,
+        big_blind_amount: history.big_blind as f64,
+        ante_amount: history.ante as f64,
+        hero_player_id: None,
+        players: history
+            .seats
+            .iter()
+            .enumerate()
+            .map(|(i, s)| OhhPlayer {
+                id: i,
+                seat: i + 1,
+                name: s.player.clone(),
+                starting_stack: s.stack as f64,
+            })
+            .collect(),
+        rounds,
+        pots,
+    }
+}
+
+/// Convert an amount to a whole number of chips.
+fn chips(amount: f64) -> Result<usize, OhhError> {
+    if amount < 0.0 || amount.fract() != 0.0 {
+        return Err(OhhError::Amount(amount));
+    }
+    Ok(amount as usize)
+}
+
+/// Read a card from its short form, e.g. `Ah`.
+fn card(name: &str) -> Result<Card, OhhError> {
+    Card::from_short_name(name).ok_or_else(|| OhhError::Card(name.to_string()))
+}
+
+/// Read a time in ISO 8601 format, e.g. `2023-11-14T22:13:20Z`, as seconds since the
+/// Unix epoch. Fractions of a second are ignored.
+fn parse_time(time: &str) -> Result<u64, OhhError> {
+    let error = || OhhError::Field {
+        field: "start_date_utc".to_string(),
+        value: time.to_string(),
+    };
+    let fields: Vec<u64> = time
+        .get(..19)
+        .ok_or_else(error)?
+        .split(['-', 'T', ':'])
+        .map(|f| f.parse::<u64>())
+        .collect::<Result<Vec<u64>, _>>()
+        .map_err(|_| error())?;
+    match fields.as_slice() {
+        [
+            year,
+            month @ 1..=12,
+            day @ 1..=31,
+            hour @ 0..=23,
+            minute @ 0..=59,
+            second @ 0..=60,
+        ] if *year >= 1970 => Ok(from_date_time(*year, *month, *day, *hour, *minute, *second)),
+        _ => Err(error()),
+    }
+}
+
+/// Convert a hand in the OHH standard to a hand history. The seats start to the left of
+/// the dealer. The table name is used as the game's uuid if it is one, otherwise the
+/// game's uuid is nil. Actions the library doesn't model are skipped. The winner of the
+/// round is worked out from the hands shown at the showdown, and is None if there was
+/// no showdown because everyone else folded. The small and big bets of a fixed-limit
+/// hand are taken from the `BetLimit` extension fields, or are the big blind and twice
+/// the big blind if they aren't given, and the cap is four bets and raises if none is
+/// given.
+pub fn import(ohh: &OpenHandHistory) -> Result<HandHistory, OhhError> {
+    let big_blind = chips(ohh.big_blind_amount)?;
+    let betting_structure = match ohh.bet_limit.bet_type.as_str() {
+        "NL" => BettingStructure::NoLimit,
+        "PL" => BettingStructure::PotLimit,
+        "FL" => BettingStructure::FixedLimit {
+            small_bet: ohh.bet_limit.small_bet.map_or(Ok(big_blind), chips)?,
+            big_bet: ohh.bet_limit.big_bet.map_or(Ok(big_blind * 2), chips)?,
+            cap: match chips(ohh.bet_limit.bet_cap)? {
+                0 => 4,
+                cap => u8::try_from(cap).unwrap_or(u8::MAX),
+            },
+        },
+        other => {
+            return Err(OhhError::Field {
+                field: "bet_type".to_string(),
+                value: other.to_string(),
+            });
+        }
+    };
+    let mut players: Vec<&OhhPlayer> = ohh.players.iter().collect();
+    players.sort_by_key(|p| p.seat);
+    let left_of_dealer = players
+        .iter()
+        .position(|p| p.seat > ohh.dealer_seat)
+        .unwrap_or(0);
+    players.rotate_left(left_of_dealer);
+    let name_of = |id: usize| {
+        players
+            .iter()
+            .find(|p| p.id == id)
+            .map(|p| p.name.clone())
+            .ok_or(OhhError::UnknownPlayer(id))
+    };
+    let button = players
+        .iter()
+        .find(|p| p.seat == ohh.dealer_seat)
+        .map(|p| p.name.clone())
+        .ok_or_else(|| OhhError::Field {
+            field: "dealer_seat".to_string(),
+            value: ohh.dealer_seat.to_string(),
+        })?;
+    let mut seats: Vec<Seat> = players
+        .iter()
+        .map(|p| {
+            Ok(Seat {
+                player: p.name.clone(),
+                stack: chips(p.starting_stack)?,
+                hole_cards: None,
+            })
+        })
+        .collect::<Result<Vec<Seat>, OhhError>>()?;
+
+    let mut actions: Vec<Action> = Vec::new();
+    let mut board: Vec<Card> = Vec::new();
+    let mut shown: Vec<(String, (Card, Card))> = Vec::new();
+    let mut mucked: Vec<String> = Vec::new();
+    for round in &ohh.rounds {
+        for c in &round.cards {
+            board.push(card(c)?);
+        }
+        let stage = match round.street {
+            Street::Preflop => Stage::PreFlop,
+            Street::Flop => Stage::Flop,
+            Street::Turn => Stage::Turn,
+            Street::River => Stage::River,
+            Street::Showdown => Stage::ShowDown,
+        };
+        for a in &round.actions {
+            let player = name_of(a.player_id)?;
+            let amount = chips(a.amount)?;
+            let (stage, action) = match a.action {
+                ActionType::DealtCards | ActionType::ShowsCards => {
+                    if let [c1, c2] = a.cards.as_slice() {
+                        let hole = (card(c1)?, card(c2)?);
+                        if let Some(seat) = seats.iter_mut().find(|s| s.player == player) {
+                            seat.hole_cards = Some(hole);
+                        }
+                        if a.action == ActionType::ShowsCards {
+                            shown.push((player, hole));
+                        }
+                    }
+                    continue;
+                }
+                ActionType::MucksCards => {
+                    mucked.push(player);
+                    continue;
+                }
+                ActionType::Other => continue,
+                ActionType::PostAnte => (Stage::Blinds, ActionKind::Ante),
+                ActionType::PostSmallBlind => (Stage::Blinds, ActionKind::SmallBlind),
+                ActionType::PostBigBlind => (Stage::Blinds, ActionKind::BigBlind),
+                ActionType::Straddle => (Stage::Blinds, ActionKind::Straddle),
+                ActionType::Fold => (stage, ActionKind::Bet(Bet::Fold)),
+                ActionType::Check => (stage, ActionKind::Bet(Bet::Check)),
+                _ if a.is_allin => (stage, ActionKind::Bet(Bet::AllIn(amount))),
+                ActionType::Call => (stage, ActionKind::Bet(Bet::Call)),
+                ActionType::Bet | ActionType::Raise => (stage, ActionKind::Bet(Bet::Raise(amount))),
+            };
+            actions.push(Action {
+                stage,
+                player,
+                action,
+                amount,
+            });
+        }
+    }
+
+    let player_hand = |name: &str, (c1, c2): (Card, Card)| {
+        let mut cards = board.clone();
+        cards.extend([c1, c2]);
+        PlayerHand {
+            name: name.to_string(),
+            hand: compare::best_hand(&cards),
+            cards,
+        }
+    };
+    let showdown: Vec<PlayerHand> = shown
+        .iter()
+        .map(|(name, hole)| player_hand(name, *hole))
+        .collect();
+    // The winner of a hand everyone else folded doesn't show a hand.
+    let winner = Game::determine_winner(showdown.clone()).ok();
+
+    let mut awards: Vec<PotAward> = Vec::new();
+    let mut rake = 0;
+    for pot in &ohh.pots {
+        rake += chips(pot.rake)?;
+        for win in &pot.player_wins {
+            awards.push(PotAward {
+                pot: pot.number,
+                player: name_of(win.player_id)?,
+                amount: chips(win.win_amount)?,
+            });
+        }
+    }
+
+    Ok(HandHistory {
+        game: Uuid::parse_str(&ohh.table_name).unwrap_or(Uuid::nil()),
+        hand_number: ohh.game_number.parse().map_err(|_| OhhError::Field {
+            field: "game_number".to_string(),
+            value: ohh.game_number.clone(),
+        })?,
+        started: parse_time(&ohh.start_date_utc)?,
+        button,
+        small_blind: chips(ohh.small_blind_amount)?,
+        big_blind,
+        ante: chips(ohh.ante_amount)?,
+        betting_structure,
+        seats,
+        actions,
+        board,
+        second_board: Vec::new(),
+        showdown,
+        mucked,
+        awards,
+        rake,
+        winner,
+    })
+}
+
+/// Write hand histories as an OHH file.
+pub fn to_json(histories: &[HandHistory]) -> Result<String, serde_json::Error> {
+    let hands = histories
+        .iter()
+        .map(|h| serde_json::to_string_pretty(&OhhDocument { ohh: export(h) }))
+        .collect::<Result<Vec<String>, _>>()?;
+    Ok(hands.join("\n\n") + "\n")
+}
+
+/// Read the hands in an OHH file.
+pub fn parse(json: &str) -> Result<Vec<OpenHandHistory>, OhhError> {
+    serde_json::Deserializer::from_str(json)
+        .into_iter::<OhhDocument>()
+        .map(|doc| {
+            doc.map(|doc| doc.ohh)
+                .map_err(|e| OhhError::Json(e.to_string()))
+        })
+        .collect()
+}
+
+/// Read the hands in an OHH file as hand histories.
+pub fn from_json(json: &str) -> Result<Vec<HandHistory>, OhhError> {
+    parse(json)?.iter().map(import).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::{
+        autoactor::AutoActor,
+        betting_strategy::{modest_betting_strategy, six_max},
+        config::GameConfig,
+        history::HandHistoryRecorder,
+        player::Player,
+    };
+
+    fn recorded_histories() -> Vec<HandHistory> {
+        let recorder = HandHistoryRecorder::new();
+        let config = GameConfig::builder()
+            .big_blind(20)
+            .max_players(3)
+            .seed(11)
+            .build()
+            .unwrap();
+        let mut game = Game::from_config(config).unwrap();
+        game.subscribe(recorder.clone());
+        let _ = game.join(Player::build("Alice", AutoActor::build(six_max)));
+        let _ = game.join(Player::build(
+            "Bob",
+            AutoActor::build(modest_betting_strategy),
+        ));
+        let _ = game.join(Player::build("Carol", AutoActor::new()));
+        game.play().unwrap();
+        recorder.histories()
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let mut histories = recorded_histories();
+        // The rake is taken from the main pot.
+        let mut raked = histories[0].clone();
+        raked.rake = 5;
+        raked.awards[0].amount -= 5;
+        histories.push(raked);
+        let json = to_json(&histories).unwrap();
+        let imported = from_json(&json).unwrap();
+        assert!(
+            imported.len() == histories.len(),
+            "Expected {} hands, was {}",
+            histories.len(),
+            imported.len()
+        );
+        for (h, i) in histories.iter().zip(&imported) {
+            assert!(
+                i.to_json().unwrap() == h.to_json().unwrap(),
+                "Expected hand {} to survive a round trip through OHH",
+                h.hand_number
+            );
+        }
+        assert!(
+            to_json(&imported).unwrap() == json,
+            "Expected the OHH file to survive a round trip"
+        );
+    }
+
+    #[test]
+    fn test_export() {
+        let history = recorded_histories().remove(0);
+        let ohh = export(&history);
+        assert!(
+            ohh.players[ohh.dealer_seat - 1].name == history.button,
+            "Expected the dealer seat to be the button"
+        );
+        let preflop = &ohh.rounds[0];
+        let posts: Vec<ActionType> = preflop.actions.iter().take(2).map(|a| a.action).collect();
+        assert!(
+            preflop.street == Street::Preflop
+                && posts == vec![ActionType::PostSmallBlind, ActionType::PostBigBlind],
+            "Expected the blinds to be posted preflop, was {:?}",
+            posts
+        );
+        let won: f64 = ohh.pots.iter().map(|p| p.amount).sum();
+        assert!(
+            won == history.total_pot() as f64,
+            "Expected pots of {}, was {}",
+            history.total_pot(),
+            won
+        );
+    }
+
+    #[test]
+    fn test_fixed_limit_bets() {
+        let mut history = recorded_histories().remove(0);
+        let structure = BettingStructure::FixedLimit {
+            small_bet: 30,
+            big_bet: 60,
+            cap: 3,
+        };
+        history.betting_structure = structure;
+        let mut ohh = export(&history);
+        let imported = import(&ohh).unwrap().betting_structure;
+        assert!(
+            imported == structure,
+            "Expected the bets to survive a round trip, was {:?}",
+            imported
+        );
+        // Other sites don't give the bets, which are taken from the big blind.
+        ohh.bet_limit.small_bet = None;
+        ohh.bet_limit.big_bet = None;
+        let imported = import(&ohh).unwrap().betting_structure;
+        let big_blind = history.big_blind;
+        assert!(
+            imported
+                == BettingStructure::FixedLimit {
+                    small_bet: big_blind,
+                    big_bet: big_blind * 2,
+                    cap: 3
+                },
+            "Expected bets of the big blind and twice the big blind, was {:?}",
+            imported
+        );
+    }
+
+    #[test]
+    fn test_import_errors() {
+        let history = recorded_histories().remove(0);
+        let mut ohh = export(&history);
+        ohh.small_blind_amount = 0.5;
+        assert!(import(&ohh).unwrap_err() == OhhError::Amount(0.5));
+        let mut ohh = export(&history);
+        ohh.rounds[0].actions[0].player_id = 99;
+        assert!(import(&ohh).unwrap_err() == OhhError::UnknownPlayer(99));
+        let mut ohh = export(&history);
+        ohh.start_date_utc = "yesterday".to_string();
+        assert!(matches!(import(&ohh), Err(OhhError::Field { .. })));
+        assert!(matches!(parse("{\"ohh\": 1}"), Err(OhhError::Json(_))));
+    }
+
+    #[test]
+    fn test_import_other_sites() {
+        // A hand from another site, with ids that aren't seat numbers, decimal amounts
+        // that are whole numbers and actions the library doesn't model.
+        let json = r#"{"ohh": {
+            "spec_version": "1.4.6", "site_name": "Elsewhere", "game_number": "8231",
+            "start_date_utc": "2024-02-29T12:00:00.250Z", "table_name": "Ruby",
+            "table_size": 6, "game_type": "Holdem", "bet_limit": {"bet_type": "NL"},
+            "dealer_seat": 5, "small_blind_amount": 1.0, "big_blind_amount": 2.0,
+            "players": [
+                {"id": 7, "seat": 2, "name": "Dan", "starting_stack": 100.0},
+                {"id": 3, "seat": 5, "name": "Eve", "starting_stack": 80.0}
+            ],
+            "rounds": [{"id": 0, "street": "Preflop", "actions": [
+                {"action_number": 1, "player_id": 7, "action": "Post SB", "amount": 1.0},
+                {"action_number": 2, "player_id": 3, "action": "Post BB", "amount": 2.0},
+                {"action_number": 3, "player_id": 3, "action": "Sits Out"},
+                {"action_number": 4, "player_id": 7, "action": "Fold"}
+            ]}],
+            "pots": [{"number": 0, "amount": 3.0,
+                "player_wins": [{"player_id": 3, "win_amount": 3.0}]}]
+        }}"#;
+        let h = from_json(json).unwrap().remove(0);
+        assert!(
+            h.button == "Eve" && h.seats[0].player == "Dan",
+            "Expected Dan to sit to the left of the button"
+        );
+        assert!(h.hand_number == 8231 && h.started == 1_709_208_000 && h.game.is_nil());
+        assert!(
+            h.actions.len() == 3 && h.awards[0].player == "Eve" && h.total_pot() == 3,
+            "Unexpected actions {:?}",
+            h.actions
+        );
+    }
+}
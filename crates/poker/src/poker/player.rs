@@ -2,6 +2,8 @@
 use crate::poker::{
     betting_strategy::BetArgs,
     card::{BestHand, Card},
+    error::GameError,
+    fair::ShuffleSeeds,
     game::{Bet, Stage},
 };
 use serde::{Deserialize, Serialize};
@@ -33,9 +35,87 @@ pub enum Msg {
         players: Vec<(String, usize)>,
         dealer: String,
     }, // (name, bank roll)
-    GameWinner(Winner),
+    /// The named player won the game.
+    GameWinner(String),
     RoundWinner(Winner),
+    /// A player showed their hand at the showdown.
+    HandShown(PlayerHand),
+    /// A player mucked their hand at the showdown without showing it.
+    HandMucked {
+        player: String,
+    },
+    /// The part of a bet that nobody called was returned to the player who made it.
+    UncalledBet {
+        player: String,
+        amount: usize,
+    },
+    /// Everyone else folded, so the player won the pot without showing their cards.
+    RoundWonUncontested {
+        player: String,
+        pot: usize,
+    },
+    /// The community cards that would have come if the hand hadn't ended early.
+    RabbitHunt(Vec<Card>),
+    /// The hole cards of the players still in the round, shown when nobody can bet any
+    /// more.
+    HandsRevealed(Vec<(String, (Card, Card))>),
+    /// The second board, when the players agreed to run it twice.
+    SecondBoard(Vec<Card>),
+    /// The winner on the second board.
+    SecondBoardWinner(Winner),
     StageDeclare(Stage, Vec<Card>),
+    /// A player straddled, posting a blind of twice the big blind.
+    Straddle {
+        player: String,
+        amount: usize,
+    },
+    /// The round is a bomb pot: every player pays the ante and the betting starts on
+    /// the flop.
+    BombPot {
+        ante: usize,
+    },
+    BlindLevel {
+        level: usize,
+        small_blind: usize,
+        big_blind: usize,
+        ante: usize,
+    },
+    /// A player joined the table, and will be dealt in from the next round.
+    PlayerJoined {
+        player: String,
+        bank_roll: usize,
+    },
+    /// A player left the table with their chips.
+    PlayerLeft {
+        player: String,
+        bank_roll: usize,
+    },
+    /// A player is sitting out from the next round.
+    SatOut {
+        player: String,
+    },
+    /// A player who was sitting out will be dealt in again from the next round.
+    SatIn {
+        player: String,
+    },
+    /// A player who went broke bought more chips.
+    Rebuy {
+        player: String,
+        amount: usize,
+    },
+    /// A player bought the add-on.
+    AddOn {
+        player: String,
+        amount: usize,
+    },
+    /// The commitment to the server seed for a hand, sent before the deal when the game
+    /// is provably fair.
+    ShuffleCommitment {
+        hand: usize,
+        commitment: String,
+    },
+    /// The seeds for a hand, revealed after the hand so the deck can be verified.
+    ShuffleRevealed(ShuffleSeeds),
 }
 /// Implementation of Display trait for Msg.
 impl Display for Msg {
@@ -65,6 +145,43 @@ impl Display for Msg {
             }
             Msg::GameWinner(winner) => write!(f, "Won the game: {}", winner),
             Msg::RoundWinner(winner) => write!(f, "Won the round: {}", winner),
+            Msg::HandShown(PlayerHand { name, hand, .. }) => {
+                write!(f, "{} shows {}", name, hand)
+            }
+            Msg::HandMucked { player } => write!(f, "{} mucks", player),
+            Msg::UncalledBet { player, amount } => {
+                write!(f, "Uncalled bet of {} returned to {}", amount, player)
+            }
+            Msg::RoundWonUncontested { player, pot } => {
+                write!(f, "{} won the pot of {} uncontested", player, pot)
+            }
+            Msg::RabbitHunt(cards) => {
+                let cards_str = cards
+                    .iter()
+                    .map(|c| std::format!("{}", c))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "The rest of the board would have been: {}", cards_str)
+            }
+            Msg::HandsRevealed(hands) => {
+                let hands_str = hands
+                    .iter()
+                    .map(|(name, (c1, c2))| std::format!("{} shows {}, {}", name, c1, c2))
+                    .collect::<Vec<String>>()
+                    .join("; ");
+                write!(f, "All in: {}", hands_str)
+            }
+            Msg::SecondBoard(cards) => {
+                let cards_str = cards
+                    .iter()
+                    .map(|c| std::format!("{}", c))
+                    .collect::<Vec<String>>()
+                    .join(", ");
+                write!(f, "Second board: {}", cards_str)
+            }
+            Msg::SecondBoardWinner(winner) => {
+                write!(f, "Won on the second board: {}", winner)
+            }
             Msg::StageDeclare(stage, community_cards) => {
                 let cards_str = community_cards
                     .iter()
@@ -73,6 +190,44 @@ impl Display for Msg {
                     .join(", ");
                 write!(f, "{}, community cards: {}", stage, cards_str)
             }
+            Msg::Straddle { player, amount } => write!(f, "{} straddled for {}", player, amount),
+            Msg::BombPot { ante } => write!(f, "Bomb pot! Everyone antes {}", ante),
+            Msg::BlindLevel {
+                level,
+                small_blind,
+                big_blind,
+                ante,
+            } => {
+                write!(f, "Blinds are now {}/{}", small_blind, big_blind)?;
+                if *ante > 0 {
+                    write!(f, " with an ante of {}", ante)?;
+                }
+                write!(f, " (level {})", level)
+            }
+            Msg::PlayerJoined { player, bank_roll } => {
+                write!(f, "{} joined the table ({})", player, bank_roll)
+            }
+            Msg::PlayerLeft { player, bank_roll } => {
+                write!(f, "{} left the table ({})", player, bank_roll)
+            }
+            Msg::SatOut { player } => write!(f, "{} is sitting out", player),
+            Msg::SatIn { player } => write!(f, "{} is back", player),
+            Msg::Rebuy { player, amount } => write!(f, "{} rebought {} chips", player, amount),
+            Msg::AddOn { player, amount } => {
+                write!(f, "{} took the add-on of {} chips", player, amount)
+            }
+            Msg::ShuffleCommitment { hand, commitment } => {
+                write!(
+                    f,
+                    "Commitment to the shuffle for hand {}: {}",
+                    hand, commitment
+                )
+            }
+            Msg::ShuffleRevealed(seeds) => write!(
+                f,
+                "Server seed for hand {}: {}",
+                seeds.hand, seeds.server_seed
+            ),
         }
     }
 }
@@ -121,6 +276,44 @@ pub trait Actor: Debug {
     /// Receive an update message, e.g. the status of the game or information about the
     /// winner of a round or game.
     fn update(&self, msg: &Msg) -> ();
+
+    /// Decide whether to buy the given number of chips after going broke, when the game
+    /// allows rebuys.
+    fn rebuy(&mut self, _amount: usize) -> bool {
+        false
+    }
+
+    /// Decide whether to buy the add-on, the given number of chips that every player
+    /// can buy once at the end of the rebuy period.
+    fn add_on(&mut self, _amount: usize) -> bool {
+        false
+    }
+
+    /// Decide whether to deal the rest of the board twice, when the players still in
+    /// the round are all in and the game allows it. Every player has to agree.
+    fn run_it_twice(&mut self) -> bool {
+        false
+    }
+
+    /// Decide whether to straddle for the given amount, when the game allows it and
+    /// the player is in the straddling position. This is asked before the cards are
+    /// dealt.
+    fn straddle(&mut self, _amount: usize) -> bool {
+        false
+    }
+
+    /// Decide whether to show a hand at the showdown that is beaten by the given hand,
+    /// which has already been shown. A mucked hand forfeits the pot. Hands that could
+    /// still win are always shown, and beaten hands are shown by default.
+    fn show_hand(&mut self, _beaten_by: &PlayerHand) -> bool {
+        true
+    }
+
+    /// Supply a seed for shuffling the next hand's deck, in a provably fair game. This is
+    /// asked for after the commitment to the server seed has been sent.
+    fn client_seed(&self) -> Option<String> {
+        None
+    }
 }
 /// The Player struct.
 #[derive(Debug)]
@@ -128,9 +321,19 @@ pub struct Player {
     pub name: String,
     pub hole: Option<(Card, Card)>,
     pub bet: usize,
+    pub total_bet: usize,
     pub bank_roll: usize,
     pub all_in: bool,
     pub folded: bool,
+    /// Whether the player is sitting out, i.e. isn't dealt in to new rounds.
+    pub sitting_out: bool,
+    /// Whether the player missed a blind while sitting out, and needs to post the big
+    /// blind when they sit in again.
+    pub missed_blinds: bool,
+    /// Whether the player is leaving the table at the end of the round.
+    pub leaving: bool,
+    /// The number of times the player has rebought.
+    pub rebuys: usize,
     pub actor: Box<dyn Actor + 'static>,
 }
 /// Implementation of Player.
@@ -141,9 +344,14 @@ impl Player {
             name: name.to_string(),
             hole: None,
             bet: 0,
+            total_bet: 0,
             bank_roll: 0,
             all_in: false,
             folded: false,
+            sitting_out: false,
+            missed_blinds: false,
+            leaving: false,
+            rebuys: 0,
             actor: Box::new(actor),
         }
     }
@@ -163,39 +371,107 @@ impl Player {
         self.actor.hole_cards((h1, h2));
     }
 
-    /// Place a bet by asking the actor to do it.
-    pub fn place_bet(&mut self, args: BetArgs) -> Option<Bet> {
-        if !self.all_in && !self.folded {
-            let bet_opt = self
-                .actor
-                .place_bet(args.clone(), self.hole.unwrap(), self.bank_roll);
-            if let Some(bet) = bet_opt {
-                match bet {
-                    Bet::Fold => {
-                        self.folded = true;
-                        Some(Bet::Fold)
-                    }
-                    Bet::Check => Some(Bet::Check),
-                    Bet::Call => {
-                        self.bank_roll -= args.call;
-                        Some(Bet::Call)
-                    }
-                    Bet::Raise(n) => {
-                        self.bank_roll -= n;
-                        Some(Bet::Raise(n))
-                    }
-                    Bet::AllIn(n) => {
-                        self.bank_roll = 0;
-                        self.all_in = true;
-                        Some(Bet::AllIn(n))
-                    }
-                }
-            } else {
-                panic!("No bet received from player.");
+    /// Ask the actor for a bet. Returns an error if the player hasn't been dealt their
+    /// hole cards or the actor doesn't respond.
+    pub fn request_bet(&mut self, args: BetArgs) -> Result<Bet, GameError> {
+        let hole = self.hole.ok_or(GameError::NotEnoughCards {
+            needed: 2,
+            remaining: 0,
+        })?;
+        self.actor
+            .place_bet(args, hole, self.bank_roll)
+            .ok_or_else(|| GameError::ActorFailed {
+                player: self.name.clone(),
+            })
+    }
+
+    /// Make a bet, given the amount needed to call. The bet is normalised against the
+    /// amount to call and the player's bank roll, so that the returned bet describes
+    /// what actually happened: a call or raise that takes all of the player's chips
+    /// becomes `AllIn`, and a raise that doesn't exceed the amount to call becomes `Call`.
+    /// The amounts in `Raise` and `AllIn` are the number of chips put into the pot.
+    /// Returns an error, without changing the player, for a check when there is a bet
+    /// to call.
+    pub fn make_bet(&mut self, bet: Bet, call: usize) -> Result<Bet, GameError> {
+        let bet = match bet {
+            Bet::Fold => {
+                self.folded = true;
+                Bet::Fold
             }
-        } else {
-            None
+            Bet::Check if call > 0 => {
+                return Err(GameError::IllegalAction {
+                    player: self.name.clone(),
+                    bet,
+                    reason: format!("there is a bet of {} to call", call),
+                });
+            }
+            Bet::Check => Bet::Check,
+            Bet::Call | Bet::Raise(_) if call >= self.bank_roll => {
+                Bet::AllIn(self.commit(self.bank_roll))
+            }
+            Bet::Raise(n) if n >= self.bank_roll => Bet::AllIn(self.commit(self.bank_roll)),
+            Bet::Call => {
+                self.commit(call);
+                Bet::Call
+            }
+            Bet::Raise(n) if n <= call => {
+                self.commit(call);
+                Bet::Call
+            }
+            Bet::Raise(n) => Bet::Raise(self.commit(n)),
+            Bet::AllIn(_) => Bet::AllIn(self.commit(self.bank_roll)),
+        };
+        Ok(bet)
+    }
+
+    /// Move chips from the bank roll into the current bet, marking the player
+    /// as all in if that empties the bank roll. Returns the amount committed.
+    fn commit(&mut self, amount: usize) -> usize {
+        self.bank_roll -= amount;
+        self.bet += amount;
+        self.total_bet += amount;
+        if self.bank_roll == 0 {
+            self.all_in = true;
+        }
+        amount
+    }
+
+    /// Ask the actor whether to rebuy, adding the chips to the bank roll if so.
+    pub fn offer_rebuy(&mut self, amount: usize) -> bool {
+        let rebuy = self.actor.rebuy(amount);
+        if rebuy {
+            self.bank_roll += amount;
+            self.rebuys += 1;
+        }
+        rebuy
+    }
+
+    /// Ask the actor whether to buy the add-on, adding the chips to the bank roll if so.
+    pub fn offer_add_on(&mut self, amount: usize) -> bool {
+        let add_on = self.actor.add_on(amount);
+        if add_on {
+            self.bank_roll += amount;
         }
+        add_on
+    }
+
+    /// Ask the actor whether to run it twice.
+    pub fn offer_run_it_twice(&mut self) -> bool {
+        self.actor.run_it_twice()
+    }
+
+    /// Ask the actor whether to straddle.
+    pub fn offer_straddle(&mut self, amount: usize) -> bool {
+        self.actor.straddle(amount)
+    }
+
+    /// Ask the actor whether to show a beaten hand at the showdown, folding it if not.
+    pub fn offer_show(&mut self, beaten_by: &PlayerHand) -> bool {
+        let show = self.actor.show_hand(beaten_by);
+        if !show {
+            self.folded = true;
+        }
+        show
     }
 
     /// Respond to an incoming message by asking the actor to do it.
@@ -203,18 +479,24 @@ impl Player {
         self.actor.update(msg);
     }
 
-    /// Pay the required amount to join a round.
+    /// Pay an ante. Unlike a blind, the ante doesn't count towards the player's bet in
+    /// the first round of betting.
+    pub fn pay_ante(&mut self, ante: usize) -> Option<usize> {
+        let paid = self.ante_up(ante)?;
+        self.bet -= paid;
+        Some(paid)
+    }
+
+    /// Pay a blind. If the player has too few chips to pay the full amount they
+    /// pay what they have and are all in. A player with no chips who isn't already
+    /// all in is marked as folded.
     pub fn ante_up(&mut self, blind: usize) -> Option<usize> {
-        if self.bank_roll > blind {
-            self.bank_roll -= blind;
-            Some(blind)
-        } else if self.bank_roll > 0 {
-            self.all_in = true;
-            let bank_roll = self.bank_roll;
-            self.bank_roll = 0;
-            Some(bank_roll)
+        if self.bank_roll > 0 {
+            Some(self.commit(std::cmp::min(blind, self.bank_roll)))
         } else {
-            self.folded = true;
+            if !self.all_in {
+                self.folded = true;
+            }
             None
         }
     }
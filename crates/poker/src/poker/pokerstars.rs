@@ -0,0 +1,1356 @@
+/// Hand histories in the text format used by PokerStars, which is read by most hand
+/// tracking and replaying tools, and a parser for histories from PokerStars itself.
+use crate::poker::{
+    card::{BestHand, Card, Hand, Rank},
+    compare,
+    config::BettingStructure,
+    game::{Bet, Game, Stage},
+    history::{Action, ActionKind, HandHistory, PotAward, Seat, from_date_time, to_date_time},
+    player::PlayerHand,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::HashMap,
+    fmt::{self, Display},
+};
+use uuid::Uuid;
+
+/// A list of cards in square brackets, e.g. `[Ah Kd]`.
+fn cards_to_string(cards: &[Card]) -> String {
+    let cards: Vec<String> = cards.iter().map(Card::short_name).collect();
+    format!("[{}]", cards.join(" "))
+}
+
+/// The name of a rank, and the plural used for pairs and sets.
+fn rank_names(rank: Rank) -> (&'static str, &'static str) {
+    match rank {
+        Rank::Rank2 => ("Deuce", "Deuces"),
+        Rank::Rank3 => ("Three", "Threes"),
+        Rank::Rank4 => ("Four", "Fours"),
+        Rank::Rank5 => ("Five", "Fives"),
+        Rank::Rank6 => ("Six", "Sixes"),
+        Rank::Rank7 => ("Seven", "Sevens"),
+        Rank::Rank8 => ("Eight", "Eights"),
+        Rank::Rank9 => ("Nine", "Nines"),
+        Rank::Rank10 => ("Ten", "Tens"),
+        Rank::Jack => ("Jack", "Jacks"),
+        Rank::Queen => ("Queen", "Queens"),
+        Rank::King => ("King", "Kings"),
+        Rank::Ace => ("Ace", "Aces"),
+    }
+}
+
+/// The description of a hand, e.g. `a pair of Kings`.
+fn describe_hand(best: &BestHand) -> String {
+    let name = |r: Rank| rank_names(r).0;
+    let plural = |r: Rank| rank_names(r).1;
+    // the top of a straight, from the cards in the sequence.
+    let high = best.cards.iter().map(|c| c.rank).max();
+    let straight = |r: Rank| {
+        let high = high.unwrap_or(r);
+        let low = Rank::values()
+            .into_iter()
+            .find(|l| l.value() + 4 == high.value())
+            .unwrap_or(Rank::Ace);
+        format!("{} to {}", name(low), name(high))
+    };
+    match best.hand {
+        Hand::HighCard(r) => format!("high card {}", name(r)),
+        Hand::OnePair(r) => format!("a pair of {}", plural(r)),
+        Hand::TwoPair(r1, r2) => format!(
+            "two pair, {} and {}",
+            plural(r1.max(r2)),
+            plural(r1.min(r2))
+        ),
+        Hand::ThreeOfAKind(r) => format!("three of a kind, {}", plural(r)),
+        Hand::Straight(r) => format!("a straight, {}", straight(r)),
+        Hand::Flush(r1, r2, r3, r4, r5) => {
+            format!("a flush, {} high", name(r1.max(r2).max(r3).max(r4).max(r5)))
+        }
+        Hand::FullHouse(r1, r2) => format!("a full house, {} full of {}", plural(r1), plural(r2)),
+        Hand::FourOfAKind(r) => format!("four of a kind, {}", plural(r)),
+        Hand::StraightFlush(r) => format!("a straight flush, {}", straight(r)),
+    }
+}
+
+/// Format a time in seconds since the Unix epoch as `YYYY/MM/DD HH:MM:SS`, in UTC.
+fn format_time(secs: u64) -> String {
+    let (year, month, day, hour, minute, second) = to_date_time(secs);
+    format!(
+        "{}/{:02}/{:02} {:02}:{:02}:{:02}",
+        year, month, day, hour, minute, second
+    )
+}
+
+/// The name of a pot, numbered from zero for the main pot.
+fn pot_name(pot: usize, num_pots: usize) -> String {
+    match (pot, num_pots) {
+        (_, 1) => "pot".to_string(),
+        (0, _) => "main pot".to_string(),
+        (_, 2) => "side pot".to_string(),
+        (n, _) => format!("side pot-{}", n),
+    }
+}
+
+/// The uncalled part of the largest bet in the round, which is returned to the player
+/// who made it rather than won. Returns the player and the amount.
+fn uncalled_bet(history: &HandHistory) -> Option<(String, usize)> {
+    let mut totals: HashMap<&str, usize> = HashMap::new();
+    for a in &history.actions {
+        *totals.entry(a.player.as_str()).or_default() += a.amount;
+    }
+    let mut totals: Vec<(&str, usize)> = totals.into_iter().collect();
+    totals.sort_by_key(|(_, total)| std::cmp::Reverse(*total));
+    match totals.as_slice() {
+        [(player, first), (_, second), ..] if first > second => {
+            Some((player.to_string(), first - second))
+        }
+        _ => None,
+    }
+}
+
+/// The number of bets and raises allowed in each round of betting in PokerStars'
+/// fixed-limit games, which isn't written in the hand histories.
+const FIXED_LIMIT_CAP: u8 = 4;
+
+/// Write a hand history in the PokerStars text format. Every player's hole cards are
+/// included, as `Dealt to` lines. Bets that nobody called are shown as returned to the
+/// player who made them. The stakes of a fixed-limit game are the small and big bets,
+/// and the cap on raises isn't written.
+pub fn export(history: &HandHistory) -> String {
+    let mut lines: Vec<String> = Vec::new();
+    let seat_of = |player: &str| {
+        history
+            .seats
+            .iter()
+            .position(|s| s.player == player)
+            .map_or(0, |i| i + 1)
+    };
+    let (limit, stakes) = match history.betting_structure {
+        BettingStructure::NoLimit => ("No Limit", (history.small_blind, history.big_blind)),
+        BettingStructure::PotLimit => ("Pot Limit", (history.small_blind, history.big_blind)),
+        BettingStructure::FixedLimit {
+            small_bet, big_bet, ..
+        } => ("Limit", (small_bet, big_bet)),
+    };
+    lines.push(format!(
+        "PokerStars Hand #{}: Hold'em {} ({}/{}) - {} UTC",
+        history.hand_number,
+        limit,
+        stakes.0,
+        stakes.1,
+        format_time(history.started)
+    ));
+    lines.push(format!(
+        "Table '{}' {}-max Seat #{} is the button",
+        history.game,
+        history.seats.len(),
+        seat_of(&history.button)
+    ));
+    for (i, seat) in history.seats.iter().enumerate() {
+        lines.push(format!(
+            "Seat {}: {} ({} in chips)",
+            i + 1,
+            seat.player,
+            seat.stack
+        ));
+    }
+
+    // Chips left in each player's stack, the amount each player has bet on the current
+    // street and the highest bet on the current street.
+    let mut stacks: HashMap<&str, usize> = history
+        .seats
+        .iter()
+        .map(|s| (s.player.as_str(), s.stack))
+        .collect();
+    let mut street: HashMap<&str, usize> = HashMap::new();
+    let mut highest = 0;
+    let uncalled = uncalled_bet(history);
+    // The small blind of a player who posts both blinds is dead, so isn't part of their
+    // bet.
+    let dead: HashMap<&str, usize> = history
+        .actions_in(Stage::Blinds)
+        .filter(|a| {
+            a.action == ActionKind::SmallBlind
+                && history
+                    .actions_in(Stage::Blinds)
+                    .any(|b| b.player == a.player && b.action == ActionKind::BigBlind)
+        })
+        .map(|a| (a.player.as_str(), a.amount))
+        .collect();
+    let streets = [
+        (Stage::Blinds, None),
+        (Stage::PreFlop, Some("*** HOLE CARDS ***".to_string())),
+        (Stage::Flop, None),
+        (Stage::Turn, None),
+        (Stage::River, None),
+    ];
+    for (stage, heading) in streets {
+        let heading = match stage {
+            Stage::Flop if history.board.len() >= 3 => Some(format!(
+                "*** FLOP *** {}",
+                cards_to_string(&history.board[..3])
+            )),
+            Stage::Turn if history.board.len() >= 4 => Some(format!(
+                "*** TURN *** {} {}",
+                cards_to_string(&history.board[..3]),
+                cards_to_string(&history.board[3..4])
+            )),
+            Stage::River if history.board.len() >= 5 => Some(format!(
+                "*** RIVER *** {} {}",
+                cards_to_string(&history.board[..4]),
+                cards_to_string(&history.board[4..5])
+            )),
+            _ => heading,
+        };
+        if let Some(heading) = heading {
+            lines.push(heading);
+        } else if stage != Stage::Blinds {
+            continue;
+        }
+        if stage == Stage::PreFlop {
+            for seat in &history.seats {
+                if let Some((c1, c2)) = seat.hole_cards {
+                    lines.push(format!(
+                        "Dealt to {} {}",
+                        seat.player,
+                        cards_to_string(&[c1, c2])
+                    ));
+                }
+            }
+        } else if stage != Stage::Blinds {
+            street.clear();
+            highest = 0;
+        }
+        for a in history.actions_in(stage) {
+            let player = a.player.as_str();
+            let stack = stacks.entry(player).or_default();
+            *stack = stack.saturating_sub(a.amount);
+            if a.action == ActionKind::SmallBlind && dead.contains_key(player) {
+                continue;
+            }
+            let all_in = if *stack == 0 && a.amount > 0 {
+                " and is all-in"
+            } else {
+                ""
+            };
+            let bet = street.entry(player).or_default();
+            let before = *bet;
+            if a.action != ActionKind::Ante {
+                *bet += a.amount;
+            }
+            let total = *bet;
+            let text = match a.action {
+                ActionKind::Ante => format!("posts the ante {}", a.amount),
+                ActionKind::SmallBlind => format!("posts small blind {}", a.amount),
+                ActionKind::BigBlind => match dead.get(player) {
+                    Some(small_blind) => {
+                        format!("posts small & big blinds {}", small_blind + a.amount)
+                    }
+                    None => format!("posts big blind {}", a.amount),
+                },
+                ActionKind::Straddle => format!("posts straddle {}", a.amount),
+                ActionKind::Bet(Bet::Fold) => "folds".to_string(),
+                ActionKind::Bet(Bet::Check) => "checks".to_string(),
+                ActionKind::Bet(_) if total <= highest => format!("calls {}", total - before),
+                ActionKind::Bet(_) if highest == 0 => format!("bets {}", a.amount),
+                ActionKind::Bet(_) => format!("raises {} to {}", total - highest, total),
+            };
+            highest = highest.max(total);
+            lines.push(format!("{}: {}{}", player, text, all_in));
+        }
+    }
+
+    // Take the uncalled bet out of the awards, starting with the last side pot.
+    let mut awards: Vec<PotAward> = history.awards.clone();
+    if let Some((player, amount)) = &uncalled {
+        lines.push(format!("Uncalled bet ({}) returned to {}", amount, player));
+        let mut left = *amount;
+        for award in awards.iter_mut().rev().filter(|a| &a.player == player) {
+            let returned = left.min(award.amount);
+            award.amount -= returned;
+            left -= returned;
+        }
+        awards.retain(|a| a.amount > 0);
+    }
+    let num_pots = awards.iter().map(|a| a.pot + 1).max().unwrap_or(1);
+    let mut pots: Vec<usize> = vec![0; num_pots];
+    awards.iter().for_each(|a| pots[a.pot] += a.amount);
+
+    let showdown = &history.showdown;
+    if !showdown.is_empty() {
+        lines.push("*** SHOW DOWN ***".to_string());
+        for ph in showdown {
+            let hole = history
+                .seats
+                .iter()
+                .find(|s| s.player == ph.name)
+                .and_then(|s| s.hole_cards);
+            if let Some((c1, c2)) = hole {
+                lines.push(format!(
+                    "{}: shows {} ({})",
+                    ph.name,
+                    cards_to_string(&[c1, c2]),
+                    describe_hand(&ph.hand)
+                ));
+            }
+        }
+        for player in &history.mucked {
+            lines.push(format!("{}: mucks hand", player));
+        }
+    }
+    for a in &awards {
+        lines.push(format!(
+            "{} collected {} from {}",
+            a.player,
+            a.amount,
+            pot_name(a.pot, num_pots)
+        ));
+    }
+
+    // The pots are shown before the rake was taken, which came out of the main pot.
+    pots[0] += history.rake;
+    lines.push("*** SUMMARY ***".to_string());
+    let total: usize = pots.iter().sum();
+    if num_pots > 1 {
+        let pots: Vec<String> = pots
+            .iter()
+            .enumerate()
+            .map(|(i, pot)| {
+                let mut name = pot_name(i, num_pots);
+                name[..1].make_ascii_uppercase();
+                format!("{} {}.", name, pot)
+            })
+            .collect();
+        lines.push(format!(
+            "Total pot {} {} | Rake {}",
+            total,
+            pots.join(" "),
+            history.rake
+        ));
+    } else {
+        lines.push(format!("Total pot {} | Rake {}", total, history.rake));
+    }
+    if !history.board.is_empty() {
+        lines.push(format!("Board {}", cards_to_string(&history.board)));
+    }
+    for (i, seat) in history.seats.iter().enumerate() {
+        let player = seat.player.as_str();
+        let mut labels = String::new();
+        if seat.player == history.button {
+            labels += " (button)";
+        }
+        for a in history.actions_in(Stage::Blinds) {
+            if a.player == player && a.action == ActionKind::SmallBlind {
+                labels += " (small blind)";
+            } else if a.player == player && a.action == ActionKind::BigBlind {
+                labels += " (big blind)";
+            }
+        }
+        let won: usize = awards
+            .iter()
+            .filter(|a| a.player == player)
+            .map(|a| a.amount)
+            .sum();
+        let fold = history
+            .actions
+            .iter()
+            .find(|a| a.player == player && a.action == ActionKind::Bet(Bet::Fold));
+        let outcome = if let Some(fold) = fold {
+            let bet: usize = history
+                .actions
+                .iter()
+                .filter(|a| a.player == player && a.action != ActionKind::Ante)
+                .map(|a| a.amount)
+                .sum();
+            match fold.stage {
+                Stage::Flop => "folded on the Flop".to_string(),
+                Stage::Turn => "folded on the Turn".to_string(),
+                Stage::River => "folded on the River".to_string(),
+                _ if bet == 0 => "folded before Flop (didn't bet)".to_string(),
+                _ => "folded before Flop".to_string(),
+            }
+        } else if let Some(ph) = showdown.iter().find(|ph| ph.name == player) {
+            let hole = seat
+                .hole_cards
+                .map(|(c1, c2)| cards_to_string(&[c1, c2]))
+                .unwrap_or_default();
+            if won > 0 {
+                format!(
+                    "showed {} and won ({}) with {}",
+                    hole,
+                    won,
+                    describe_hand(&ph.hand)
+                )
+            } else {
+                format!("showed {} and lost with {}", hole, describe_hand(&ph.hand))
+            }
+        } else if won > 0 {
+            format!("collected ({})", won)
+        } else {
+            "mucked".to_string()
+        };
+        lines.push(format!("Seat {}: {}{} {}", i + 1, player, labels, outcome));
+    }
+    lines.join("\n") + "\n"
+}
+
+/// Write several hand histories in the PokerStars text format, separated by blank lines.
+pub fn export_all(histories: &[HandHistory]) -> String {
+    histories
+        .iter()
+        .map(export)
+        .collect::<Vec<String>>()
+        .join("\n\n")
+}
+
+/// An error in a PokerStars hand history, with the number of the line it was found on,
+/// counting from one.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ParseError {
+    pub line: usize,
+    pub message: String,
+}
+/// Implementation of Display trait for ParseError.
+impl Display for ParseError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Line {}: {}", self.line, self.message)
+    }
+}
+/// Implementation of Error trait for ParseError.
+impl std::error::Error for ParseError {}
+
+/// Read an amount of chips, e.g. `1500`. Amounts of money, e.g. `$0.25`, are read as
+/// a number of cents.
+fn parse_amount(amount: &str) -> Option<usize> {
+    let amount = amount.trim_start_matches(['$', '€', '£']);
+    match amount.split_once('.') {
+        Some((whole, cents)) if cents.len() <= 2 => {
+            let cents = format!("{:0<2}", cents);
+            Some(whole.parse::<usize>().ok()? * 100 + cents.parse::<usize>().ok()?)
+        }
+        Some(_) => None,
+        None => amount.parse().ok(),
+    }
+}
+
+/// Read the cards in the last pair of square brackets in a line, e.g. `[Ah Kd]`.
+fn parse_cards(line: &str) -> Option<Vec<Card>> {
+    let start = line.rfind('[')?;
+    let end = start + line[start..].find(']')?;
+    line[start + 1..end]
+        .split_whitespace()
+        .map(Card::from_short_name)
+        .collect()
+}
+
+/// Read the first time in a line in the format `YYYY/MM/DD HH:MM:SS`, preferring one
+/// marked as UTC. Times are taken to be in UTC.
+fn parse_time(line: &str) -> Option<u64> {
+    let words: Vec<&str> = line.split_whitespace().collect();
+    let times: Vec<(u64, bool)> = words
+        .windows(2)
+        .enumerate()
+        .filter_map(|(i, pair)| {
+            let date: Vec<u64> = pair[0]
+                .trim_start_matches('[')
+                .split('/')
+                .map(|f| f.parse().ok())
+                .collect::<Option<Vec<u64>>>()?;
+            let time: Vec<u64> = pair[1]
+                .split(':')
+                .map(|f| f.parse().ok())
+                .collect::<Option<Vec<u64>>>()?;
+            match (date.as_slice(), time.as_slice()) {
+                ([y, m @ 1..=12, d @ 1..=31], [h @ 0..=23, min @ 0..=59, s @ 0..=60])
+                    if *y >= 1970 =>
+                {
+                    let utc = words.get(i + 2).is_some_and(|w| w.starts_with("UTC"));
+                    Some((from_date_time(*y, *m, *d, *h, *min, *s), utc))
+                }
+                _ => None,
+            }
+        })
+        .collect();
+    times
+        .iter()
+        .find(|(_, utc)| *utc)
+        .or(times.first())
+        .map(|(time, _)| *time)
+}
+
+/// A hand being read, with the state needed to make sense of the next line.
+#[derive(Default)]
+struct HandParser {
+    history: Option<HandHistory>,
+    /// The number of the button's seat and the seat numbers of the players.
+    button_seat: usize,
+    seat_numbers: Vec<usize>,
+    stage: Option<Stage>,
+    /// The amount each player has bet on the current street.
+    street: HashMap<String, usize>,
+    uncalled: Vec<(String, usize)>,
+    /// The players who showed their hole cards at the showdown.
+    shown: Vec<String>,
+    summary: bool,
+}
+/// Implementation for HandParser.
+impl HandParser {
+    /// Start a hand from its first line, e.g.
+    /// `PokerStars Hand #1: Hold'em No Limit (10/20) - 2023/11/14 22:13:20 UTC`.
+    fn start(line: &str) -> Result<Self, String> {
+        let number = line
+            .split_once('#')
+            .and_then(|(_, rest)| rest.split(':').next())
+            .and_then(|n| n.parse::<usize>().ok())
+            .ok_or("Expected a hand number")?;
+        // The stakes are the last amounts in brackets separated by a slash, which are
+        // the blinds, or the small and big bets in a fixed-limit game.
+        let (small, big) = line
+            .split('(')
+            .filter_map(|part| {
+                let blinds = part.split(')').next()?.split_whitespace().next()?;
+                let (sb, bb) = blinds.split_once('/')?;
+                Some((parse_amount(sb)?, parse_amount(bb)?))
+            })
+            .next_back()
+            .ok_or("Expected the blinds")?;
+        let betting_structure = if line.contains("Pot Limit") {
+            BettingStructure::PotLimit
+        } else if line.contains("No Limit") {
+            BettingStructure::NoLimit
+        } else if line.contains(" Limit") {
+            BettingStructure::FixedLimit {
+                small_bet: small,
+                big_bet: big,
+                cap: FIXED_LIMIT_CAP,
+            }
+        } else {
+            return Err("Expected the betting structure".to_string());
+        };
+        // The blinds of a fixed-limit game are read from the lines posting them, and
+        // are usually half the small bet and the small bet.
+        let (small_blind, big_blind) = match betting_structure {
+            BettingStructure::FixedLimit { .. } => (small / 2, small),
+            _ => (small, big),
+        };
+        let started = parse_time(line).ok_or("Expected the time the hand started")?;
+        Ok(HandParser {
+            history: Some(HandHistory {
+                game: Uuid::nil(),
+                hand_number: number,
+                started,
+                button: String::new(),
+                small_blind,
+                big_blind,
+                ante: 0,
+                betting_structure,
+                seats: Vec::new(),
+                actions: Vec::new(),
+                board: Vec::new(),
+                second_board: Vec::new(),
+                showdown: Vec::new(),
+                mucked: Vec::new(),
+                awards: Vec::new(),
+                rake: 0,
+                winner: None,
+            }),
+            ..Default::default()
+        })
+    }
+
+    /// Read the next line of the hand.
+    fn read(&mut self, line: &str) -> Result<(), String> {
+        let Some(history) = self.history.as_mut() else {
+            return Ok(());
+        };
+        if let Some(rest) = line.strip_prefix("*** ") {
+            let heading = rest.split(" ***").next().unwrap_or_default();
+            let stage = match heading {
+                "HOLE CARDS" => Stage::PreFlop,
+                "FLOP" | "TURN" | "RIVER" => {
+                    let cards = parse_cards(line).ok_or("Expected community cards")?;
+                    let stage = match heading {
+                        "FLOP" => Stage::Flop,
+                        "TURN" => Stage::Turn,
+                        _ => Stage::River,
+                    };
+                    if stage == Stage::Flop {
+                        history.board = cards;
+                    } else {
+                        history.board.extend(cards);
+                    }
+                    stage
+                }
+                "SHOW DOWN" => Stage::ShowDown,
+                "SUMMARY" => {
+                    self.summary = true;
+                    return Ok(());
+                }
+                _ => return Err(format!("Unknown section {}", heading)),
+            };
+            self.stage = Some(stage);
+            if stage != Stage::PreFlop {
+                self.street.clear();
+            }
+            return Ok(());
+        }
+        if self.summary {
+            return self.read_summary(line);
+        }
+        if let Some(table) = line.strip_prefix("Table '") {
+            let button = line
+                .split_once("Seat #")
+                .and_then(|(_, rest)| rest.split_whitespace().next())
+                .and_then(|n| n.parse().ok())
+                .ok_or("Expected the button's seat")?;
+            self.button_seat = button;
+            if let Some(name) = table.split('\'').next() {
+                history.game = Uuid::parse_str(name).unwrap_or(Uuid::nil());
+            }
+            return Ok(());
+        }
+        if let Some(rest) = line.strip_prefix("Seat ")
+            && self.stage.is_none()
+        {
+            let (number, rest) = rest.split_once(": ").ok_or("Expected a seat number")?;
+            let number: usize = number.parse().map_err(|_| "Expected a seat number")?;
+            let chips = rest.find(" in chips").ok_or("Expected a stack")?;
+            let open = rest[..chips].rfind(" (").ok_or("Expected a stack")?;
+            if rest.ends_with("is sitting out") {
+                return Ok(());
+            }
+            let stack = parse_amount(&rest[open + 2..chips]).ok_or("Invalid stack")?;
+            history.seats.push(Seat {
+                player: rest[..open].to_string(),
+                stack,
+                hole_cards: None,
+            });
+            self.seat_numbers.push(number);
+            return Ok(());
+        }
+        if let Some(rest) = line.strip_prefix("Dealt to ") {
+            let Some(seat) = history.seats.iter_mut().find(|s| {
+                rest.strip_prefix(s.player.as_str())
+                    .is_some_and(|r| r.starts_with(" ["))
+            }) else {
+                return Ok(());
+            };
+            seat.hole_cards = Some(hole_cards(line)?);
+            return Ok(());
+        }
+        if let Some(rest) = line.strip_prefix("Uncalled bet (") {
+            let (amount, player) = rest
+                .split_once(") returned to ")
+                .ok_or("Expected an uncalled bet")?;
+            let amount = parse_amount(amount).ok_or("Invalid amount")?;
+            self.uncalled.push((player.to_string(), amount));
+            return Ok(());
+        }
+        // Lines that start with a player's name.
+        let Some(player) = history
+            .seats
+            .iter()
+            .map(|s| s.player.clone())
+            .filter(|p| line.starts_with(p.as_str()))
+            .max_by_key(|p| p.len())
+        else {
+            return Ok(());
+        };
+        let rest = &line[player.len()..];
+        if let Some(pot) = rest.strip_prefix(" collected ") {
+            let (amount, pot) = pot.split_once(" from ").ok_or("Expected a pot")?;
+            let pot = match pot.trim() {
+                "pot" | "main pot" => 0,
+                "side pot" => 1,
+                side => side
+                    .strip_prefix("side pot-")
+                    .and_then(|n| n.parse().ok())
+                    .ok_or("Unknown pot")?,
+            };
+            history.awards.push(PotAward {
+                pot,
+                player,
+                amount: parse_amount(amount).ok_or("Invalid amount")?,
+            });
+            return Ok(());
+        }
+        let Some(rest) = rest.strip_prefix(": ") else {
+            return Ok(());
+        };
+        let (rest, all_in) = match rest.strip_suffix(" and is all-in") {
+            Some(rest) => (rest, true),
+            None => (rest, false),
+        };
+        let words: Vec<&str> = rest.split_whitespace().collect();
+        let amount = |word: &str| parse_amount(word).ok_or(format!("Invalid amount {}", word));
+        let bet = self.street.entry(player.clone()).or_default();
+        let (stage, action, amount) = match words.as_slice() {
+            ["posts", "the", "ante", n] => {
+                let n = amount(n)?;
+                history.ante = history.ante.max(n);
+                (Stage::Blinds, ActionKind::Ante, n)
+            }
+            ["posts", "small", "blind", n] => (Stage::Blinds, ActionKind::SmallBlind, amount(n)?),
+            ["posts", "big", "blind", n] => (Stage::Blinds, ActionKind::BigBlind, amount(n)?),
+            // A player posting both blinds posts the small blind dead, into the pot
+            // rather than as part of their bet.
+            ["posts", "small", "&", "big", "blinds", n] => {
+                let n = amount(n)?;
+                let big_blind = n.min(history.big_blind);
+                history.actions.push(Action {
+                    stage: Stage::Blinds,
+                    player: player.clone(),
+                    action: ActionKind::SmallBlind,
+                    amount: n - big_blind,
+                });
+                (Stage::Blinds, ActionKind::BigBlind, big_blind)
+            }
+            ["posts", "straddle", n] => (Stage::Blinds, ActionKind::Straddle, amount(n)?),
+            ["folds", ..] => (Stage::PreFlop, ActionKind::Bet(Bet::Fold), 0),
+            ["checks"] => (Stage::PreFlop, ActionKind::Bet(Bet::Check), 0),
+            ["calls", n] => (Stage::PreFlop, ActionKind::Bet(Bet::Call), amount(n)?),
+            ["bets", n] => {
+                let n = amount(n)?;
+                (Stage::PreFlop, ActionKind::Bet(Bet::Raise(n)), n)
+            }
+            ["raises", _, "to", n] => {
+                let n = amount(n)?
+                    .checked_sub(*bet)
+                    .ok_or("Raise is less than the bet")?;
+                (Stage::PreFlop, ActionKind::Bet(Bet::Raise(n)), n)
+            }
+            ["shows", ..] => {
+                let hole = hole_cards(line)?;
+                if let Some(seat) = history.seats.iter_mut().find(|s| s.player == player) {
+                    seat.hole_cards = Some(hole);
+                }
+                self.shown.push(player);
+                return Ok(());
+            }
+            ["mucks", ..] => {
+                history.mucked.push(player);
+                return Ok(());
+            }
+            ["doesn't", "show", "hand"]
+            | ["is", "sitting", "out"]
+            | ["sits", "out"]
+            | ["is", "back"] => return Ok(()),
+            _ => return Err(format!("Unknown action by {}: {}", player, rest)),
+        };
+        let action = match action {
+            ActionKind::Bet(Bet::Call | Bet::Raise(_)) if all_in => {
+                ActionKind::Bet(Bet::AllIn(amount))
+            }
+            action => action,
+        };
+        if action != ActionKind::Ante {
+            *bet += amount;
+        }
+        let stage = match action {
+            ActionKind::Bet(_) => self.stage.unwrap_or(Stage::PreFlop),
+            _ => stage,
+        };
+        history.actions.push(Action {
+            stage,
+            player,
+            action,
+            amount,
+        });
+        Ok(())
+    }
+
+    /// Read a line of the summary, which can show the hole cards of players who didn't
+    /// show them at the showdown.
+    fn read_summary(&mut self, line: &str) -> Result<(), String> {
+        let Some(history) = self.history.as_mut() else {
+            return Ok(());
+        };
+        if line.starts_with("Total pot ")
+            && let Some((_, rake)) = line.split_once("| Rake ")
+        {
+            history.rake = rake
+                .split_whitespace()
+                .next()
+                .and_then(parse_amount)
+                .ok_or("Expected the rake")?;
+        }
+        if line.starts_with("Seat ") && (line.contains(" showed [") || line.contains(" mucked [")) {
+            let end = line.find(']').ok_or("Expected hole cards")?;
+            let hole = hole_cards(&line[..=end])?;
+            if let Some(seat) = history.seats.iter_mut().find(|s| {
+                line.split_once(": ")
+                    .is_some_and(|(_, rest)| rest.starts_with(&format!("{} ", s.player)))
+            }) && seat.hole_cards.is_none()
+            {
+                seat.hole_cards = Some(hole);
+            }
+        }
+        Ok(())
+    }
+
+    /// Finish the hand, putting the seats in order and working out the winner.
+    fn finish(mut self) -> Result<HandHistory, String> {
+        let mut history = self.history.take().ok_or("No hand")?;
+        // A player who is all in for less than a blind doesn't show what it was, in
+        // which case it is worked out from the other blind.
+        if let BettingStructure::FixedLimit { .. } = history.betting_structure {
+            let (mut small_blind, mut big_blind) = (None, None);
+            for a in &history.actions {
+                let stack = history.seats.iter().find(|s| s.player == a.player);
+                if stack.is_none_or(|s| s.stack <= a.amount) {
+                    continue;
+                }
+                match a.action {
+                    ActionKind::SmallBlind => small_blind = Some(a.amount),
+                    ActionKind::BigBlind => big_blind = Some(a.amount),
+                    _ => {}
+                }
+            }
+            match (small_blind, big_blind) {
+                (Some(sb), Some(bb)) => (history.small_blind, history.big_blind) = (sb, bb),
+                (Some(sb), None) => (history.small_blind, history.big_blind) = (sb, sb * 2),
+                (None, Some(bb)) => (history.small_blind, history.big_blind) = (bb / 2, bb),
+                (None, None) => {}
+            }
+        }
+        // A dead button is at an empty seat, in which case the player in the nearest
+        // seat before it is the dealer.
+        let button = self
+            .seat_numbers
+            .iter()
+            .enumerate()
+            .max_by_key(|(_, n)| (**n <= self.button_seat, **n))
+            .map(|(i, _)| i)
+            .ok_or("Expected some seats")?;
+        history.button = history.seats[button].player.clone();
+        let mut seats: Vec<(usize, Seat)> = self
+            .seat_numbers
+            .iter()
+            .copied()
+            .zip(history.seats)
+            .collect();
+        seats.sort_by_key(|(n, _)| (*n <= self.button_seat, *n));
+        history.seats = seats.into_iter().map(|(_, s)| s).collect();
+
+        // Uncalled bets were taken out of the pot, so give them back to the player
+        // along with the pot they won.
+        for (player, amount) in self.uncalled {
+            let last = history.awards.iter().map(|a| a.pot).max();
+            match history
+                .awards
+                .iter_mut()
+                .find(|a| Some(a.pot) == last && a.player == player)
+            {
+                Some(award) => award.amount += amount,
+                None => history.awards.push(PotAward {
+                    pot: last.map_or(0, |pot| pot + 1),
+                    player,
+                    amount,
+                }),
+            }
+        }
+
+        let board = history.board.clone();
+        let player_hand = |name: &str, (c1, c2): (Card, Card)| {
+            let mut cards = board.clone();
+            cards.extend([c1, c2]);
+            PlayerHand {
+                name: name.to_string(),
+                hand: compare::best_hand(&cards),
+                cards,
+            }
+        };
+        history.showdown = self
+            .shown
+            .iter()
+            .filter_map(|name| {
+                let seat = history.seats.iter().find(|s| &s.player == name)?;
+                Some(player_hand(name, seat.hole_cards?))
+            })
+            .collect();
+        // The winner of a hand everyone else folded doesn't show a hand.
+        history.winner = Game::determine_winner(history.showdown.clone()).ok();
+        Ok(history)
+    }
+}
+
+/// Read two hole cards in square brackets.
+fn hole_cards(line: &str) -> Result<(Card, Card), String> {
+    match parse_cards(line).as_deref() {
+        Some([c1, c2]) => Ok((*c1, *c2)),
+        _ => Err("Expected two hole cards".to_string()),
+    }
+}
+
+/// Read the hands in a PokerStars hand history file. Each hand starts with a line
+/// beginning `PokerStars Hand #` or `PokerStars Game #`, and anything before the first
+/// hand is ignored. Amounts of money are read as a number of cents. Uncalled bets are
+/// added to the player's winnings from the last pot, or make a pot of their own if the
+/// player didn't win the last pot. The seats start to the left of the button, which if
+/// it is dead, at an empty seat, is taken to be at the nearest seat before it. Players
+/// who are sitting out are left out and the game's uuid is the table name if it is one,
+/// otherwise nil. The winner of each round is worked out from the hands shown at the
+/// showdown, and is None if there was no showdown because everyone else folded.
+/// Fixed-limit hands are read with PokerStars' cap of four bets and raises a round.
+pub fn parse(text: &str) -> Result<Vec<HandHistory>, ParseError> {
+    let mut hands: Vec<HandHistory> = Vec::new();
+    let mut parser: Option<(usize, HandParser)> = None;
+    for (i, line) in text.lines().enumerate() {
+        let line = line.trim_start_matches('\u{feff}').trim();
+        let error = |message: String| ParseError {
+            line: i + 1,
+            message,
+        };
+        if line.starts_with("PokerStars ") && (line.contains("Hand #") || line.contains("Game #")) {
+            if let Some((start, p)) = parser.take() {
+                hands.push(p.finish().map_err(|message| ParseError {
+                    line: start,
+                    message,
+                })?);
+            }
+            parser = Some((i + 1, HandParser::start(line).map_err(error)?));
+        } else if let Some((_, p)) = parser.as_mut()
+            && !line.is_empty()
+        {
+            p.read(line).map_err(error)?;
+        }
+    }
+    if let Some((start, p)) = parser {
+        hands.push(p.finish().map_err(|message| ParseError {
+            line: start,
+            message,
+        })?);
+    }
+    Ok(hands)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::{
+        autoactor::AutoActor,
+        betting_strategy::{modest_betting_strategy, six_max},
+        config::GameConfig,
+        history::HandHistoryRecorder,
+        player::{Player, Winner},
+    };
+
+    fn cards(s: &str) -> Vec<Card> {
+        s.split(' ')
+            .map(|c| Card::from_short_name(c).unwrap())
+            .collect()
+    }
+
+    fn seat(player: &str, stack: usize, hole: &str) -> Seat {
+        let hole = cards(hole);
+        Seat {
+            player: player.to_string(),
+            stack,
+            hole_cards: Some((hole[0], hole[1])),
+        }
+    }
+
+    fn action(stage: Stage, player: &str, action: ActionKind, amount: usize) -> Action {
+        Action {
+            stage,
+            player: player.to_string(),
+            action,
+            amount,
+        }
+    }
+
+    fn shown(history: &HandHistory, player: &str) -> PlayerHand {
+        let seat = history.seats.iter().find(|s| s.player == player).unwrap();
+        let (c1, c2) = seat.hole_cards.unwrap();
+        let mut all_cards = history.board.clone();
+        all_cards.extend([c1, c2]);
+        PlayerHand {
+            name: player.to_string(),
+            hand: compare::best_hand(&all_cards),
+            cards: all_cards,
+        }
+    }
+
+    fn award(pot: usize, player: &str, amount: usize) -> PotAward {
+        PotAward {
+            pot,
+            player: player.to_string(),
+            amount,
+        }
+    }
+
+    fn history(seats: Vec<Seat>, actions: Vec<Action>, board: &str) -> HandHistory {
+        HandHistory {
+            game: Uuid::nil(),
+            hand_number: 1,
+            started: 1_700_000_000,
+            button: seats.last().unwrap().player.clone(),
+            small_blind: 10,
+            big_blind: 20,
+            ante: 0,
+            betting_structure: BettingStructure::NoLimit,
+            seats,
+            actions,
+            board: cards(board),
+            second_board: Vec::new(),
+            showdown: Vec::new(),
+            mucked: Vec::new(),
+            awards: Vec::new(),
+            rake: 0,
+            winner: None,
+        }
+    }
+
+    fn showdown_hand() -> HandHistory {
+        use ActionKind::{BigBlind, SmallBlind};
+        use Stage::*;
+        let mut h = history(
+            vec![
+                seat("Bob", 2000, "5c 6d"),
+                seat("Carol", 2000, "9c 9s"),
+                seat("Alice", 2000, "Ah Kd"),
+            ],
+            vec![
+                action(Blinds, "Bob", SmallBlind, 10),
+                action(Blinds, "Carol", BigBlind, 20),
+                action(PreFlop, "Alice", ActionKind::Bet(Bet::Raise(60)), 60),
+                action(PreFlop, "Bob", ActionKind::Bet(Bet::Fold), 0),
+                action(PreFlop, "Carol", ActionKind::Bet(Bet::Call), 40),
+                action(Flop, "Carol", ActionKind::Bet(Bet::Check), 0),
+                action(Flop, "Alice", ActionKind::Bet(Bet::Raise(60)), 60),
+                action(Flop, "Carol", ActionKind::Bet(Bet::Raise(180)), 180),
+                action(Flop, "Alice", ActionKind::Bet(Bet::Call), 120),
+                action(Turn, "Carol", ActionKind::Bet(Bet::Check), 0),
+                action(Turn, "Alice", ActionKind::Bet(Bet::Check), 0),
+                action(River, "Carol", ActionKind::Bet(Bet::Check), 0),
+                action(River, "Alice", ActionKind::Bet(Bet::Check), 0),
+            ],
+            "2c 7d 9h Js Kc",
+        );
+        h.showdown = vec![shown(&h, "Carol"), shown(&h, "Alice")];
+        h.awards = vec![award(0, "Carol", 490)];
+        h.winner = Some(Winner::SoleWinner(shown(&h, "Carol")));
+        h
+    }
+
+    fn side_pot_hand() -> HandHistory {
+        use ActionKind::{BigBlind, SmallBlind};
+        use Stage::*;
+        let mut h = history(
+            vec![
+                seat("Bob", 50, "Ah Ad"),
+                seat("Carol", 2000, "Qc Jd"),
+                seat("Alice", 2000, "8s 8d"),
+            ],
+            vec![
+                action(Blinds, "Bob", SmallBlind, 10),
+                action(Blinds, "Carol", BigBlind, 20),
+                action(PreFlop, "Alice", ActionKind::Bet(Bet::Raise(100)), 100),
+                action(PreFlop, "Bob", ActionKind::Bet(Bet::AllIn(40)), 40),
+                action(PreFlop, "Carol", ActionKind::Bet(Bet::Call), 80),
+                action(Flop, "Carol", ActionKind::Bet(Bet::Check), 0),
+                action(Flop, "Alice", ActionKind::Bet(Bet::Check), 0),
+                action(Turn, "Carol", ActionKind::Bet(Bet::Check), 0),
+                action(Turn, "Alice", ActionKind::Bet(Bet::Check), 0),
+                action(River, "Carol", ActionKind::Bet(Bet::Raise(200)), 200),
+                action(River, "Alice", ActionKind::Bet(Bet::Fold), 0),
+            ],
+            "2c 7d 9h 4s 3c",
+        );
+        h.showdown = vec![shown(&h, "Bob"), shown(&h, "Carol")];
+        h.awards = vec![award(0, "Bob", 150), award(1, "Carol", 300)];
+        h.winner = Some(Winner::SoleWinner(shown(&h, "Bob")));
+        h
+    }
+
+    #[test]
+    fn test_export_showdown() {
+        let text = export(&showdown_hand());
+        assert!(
+            text == include_str!("../../tests/data/pokerstars_showdown.txt"),
+            "Unexpected hand history:\n{}",
+            text
+        );
+    }
+
+    #[test]
+    fn test_export_side_pot() {
+        let text = export(&side_pot_hand());
+        assert!(
+            text == include_str!("../../tests/data/pokerstars_side_pot.txt"),
+            "Unexpected hand history:\n{}",
+            text
+        );
+    }
+
+    #[test]
+    fn test_parse_exported() {
+        let recorded = HandHistoryRecorder::new();
+        let config = GameConfig::builder()
+            .big_blind(20)
+            .max_players(3)
+            .seed(5)
+            .build()
+            .unwrap();
+        let mut game = Game::from_config(config).unwrap();
+        game.subscribe(recorded.clone());
+        let _ = game.join(Player::build("Alice", AutoActor::build(six_max)));
+        let _ = game.join(Player::build(
+            "Bob",
+            AutoActor::build(modest_betting_strategy),
+        ));
+        let _ = game.join(Player::build("Carol", AutoActor::new()));
+        game.play().unwrap();
+        let mut raked = showdown_hand();
+        raked.hand_number = 2;
+        raked.rake = 10;
+        raked.awards[0].amount -= 10;
+        let mut mucked = showdown_hand();
+        mucked.hand_number = 3;
+        mucked.showdown.pop();
+        mucked.mucked = vec!["Alice".to_string()];
+        let mut histories = vec![showdown_hand(), side_pot_hand(), raked, mucked];
+        histories.append(&mut recorded.histories());
+        let parsed = parse(&export_all(&histories)).unwrap();
+        assert!(
+            parsed.len() == histories.len(),
+            "Expected {} hands, was {}",
+            histories.len(),
+            parsed.len()
+        );
+        for (h, p) in histories.iter_mut().zip(&parsed) {
+            // A side pot made up of an uncalled bet is returned rather than collected, so
+            // only the amount each player won can be compared.
+            let won = |h: &HandHistory| {
+                let mut won: Vec<(String, usize)> = h
+                    .seats
+                    .iter()
+                    .map(|s| {
+                        let awards = h.awards.iter().filter(|a| a.player == s.player);
+                        (s.player.clone(), awards.map(|a| a.amount).sum())
+                    })
+                    .collect();
+                won.sort();
+                won
+            };
+            assert!(
+                won(p) == won(h),
+                "Expected the same winnings in hand {}, was {:?}",
+                h.hand_number,
+                p.awards
+            );
+            let mut p = p.clone();
+            p.awards = h.awards.clone();
+            assert!(
+                p.to_json().unwrap() == h.to_json().unwrap(),
+                "Expected hand {} to survive a round trip, was {:?}",
+                h.hand_number,
+                p
+            );
+        }
+    }
+
+    #[test]
+    fn test_parse_pokerstars() {
+        let text = include_str!("../../tests/data/pokerstars_real.txt");
+        let hands = parse(text).unwrap();
+        assert!(hands.len() == 2, "Expected 2 hands, was {}", hands.len());
+        let h = &hands[0];
+        let seats: Vec<&str> = h.seats.iter().map(|s| s.player.as_str()).collect();
+        assert!(
+            seats == vec!["Mr. Pink (AU)", "velvet99", "hero"],
+            "Expected the seats to start left of the button, was {:?}",
+            seats
+        );
+        assert!(
+            h.hand_number == 212345678901
+                && h.small_blind == 1
+                && h.big_blind == 2
+                && h.button == "hero"
+                && h.started == 1_588_374_245,
+            "Unexpected header {:?}",
+            h
+        );
+        assert!(
+            h.seats[2].hole_cards.is_some() && h.seats[0].hole_cards.is_none(),
+            "Expected only the hero's hole cards"
+        );
+        let bets: Vec<ActionKind> = h.actions.iter().map(|a| a.action).collect();
+        assert!(
+            bets == vec![
+                ActionKind::SmallBlind,
+                ActionKind::BigBlind,
+                ActionKind::Bet(Bet::Raise(6)),
+                ActionKind::Bet(Bet::Fold),
+                ActionKind::Bet(Bet::Raise(16)),
+                ActionKind::Bet(Bet::Fold),
+            ],
+            "Unexpected actions {:?}",
+            bets
+        );
+        assert!(
+            h.awards.len() == 1 && h.awards[0].amount == 25 && h.total_pot() == 25,
+            "Expected velvet99 to win 13 as well as the uncalled 12, was {:?}",
+            h.awards
+        );
+        let h = &hands[1];
+        assert!(
+            h.board.len() == 5 && h.showdown.len() == 2 && h.ante == 5,
+            "Expected a showdown with a full board, was {:?}",
+            h
+        );
+        assert!(
+            matches!(&h.winner, Some(Winner::SoleWinner(ph)) if ph.name == "velvet99"),
+            "Expected velvet99 to win, was {:?}",
+            h.winner
+        );
+        assert!(
+            h.actions.iter().any(|a| a.player == "hero"
+                && a.stage == Stage::River
+                && a.action == ActionKind::Bet(Bet::AllIn(95))),
+            "Expected the hero to go all in on the river"
+        );
+    }
+
+    #[test]
+    fn test_parse_dead_button() {
+        // The button is at seat 3, which is empty, so the player at seat 2 is the dealer.
+        let text = export(&showdown_hand()).replace("Seat 3: Alice", "Seat 4: Alice");
+        let h = parse(&text).unwrap().remove(0);
+        let seats: Vec<&str> = h.seats.iter().map(|s| s.player.as_str()).collect();
+        assert!(
+            h.button == "Carol" && seats == vec!["Alice", "Bob", "Carol"],
+            "Expected Carol to be the dealer, was {} with seats {:?}",
+            h.button,
+            seats
+        );
+        // Before the first occupied seat, the dealer is at the last one.
+        let text = export(&showdown_hand())
+            .replace("3-max Seat #3", "6-max Seat #1")
+            .replace("Seat 1: Bob", "Seat 2: Bob")
+            .replace("Seat 2: Carol", "Seat 3: Carol")
+            .replace("Seat 3: Alice", "Seat 5: Alice");
+        let h = parse(&text).unwrap().remove(0);
+        assert!(
+            h.button == "Alice",
+            "Expected Alice to be the dealer, was {}",
+            h.button
+        );
+    }
+
+    #[test]
+    fn test_parse_small_and_big_blinds() {
+        use ActionKind::{BigBlind, SmallBlind};
+        use Stage::*;
+        // Dave posts both blinds to come back in, and only the big blind counts towards
+        // his raise.
+        let mut h = history(
+            vec![
+                seat("Bob", 2000, "5c 6d"),
+                seat("Carol", 2000, "9c 9s"),
+                seat("Dave", 2000, "Qh Qd"),
+                seat("Alice", 2000, "Ah Kd"),
+            ],
+            vec![
+                action(Blinds, "Bob", SmallBlind, 10),
+                action(Blinds, "Carol", BigBlind, 20),
+                action(Blinds, "Dave", SmallBlind, 10),
+                action(Blinds, "Dave", BigBlind, 20),
+                action(PreFlop, "Dave", ActionKind::Bet(Bet::Raise(40)), 40),
+                action(PreFlop, "Alice", ActionKind::Bet(Bet::Fold), 0),
+                action(PreFlop, "Bob", ActionKind::Bet(Bet::Fold), 0),
+                action(PreFlop, "Carol", ActionKind::Bet(Bet::Fold), 0),
+            ],
+            "2c 7d 9h Js Kc",
+        );
+        h.board.clear();
+        h.awards = vec![award(0, "Dave", 100)];
+        let text = export(&h);
+        assert!(
+            text.contains("Dave: posts small & big blinds 30")
+                && text.contains("Dave: raises 40 to 60"),
+            "Expected Dave to post both blinds and raise to 60, was\n{}",
+            text
+        );
+        let parsed = parse(&text).unwrap().remove(0);
+        let actions: Vec<(ActionKind, usize)> = parsed
+            .actions
+            .iter()
+            .filter(|a| a.player == "Dave")
+            .map(|a| (a.action, a.amount))
+            .collect();
+        assert!(
+            actions
+                == vec![
+                    (SmallBlind, 10),
+                    (BigBlind, 20),
+                    (ActionKind::Bet(Bet::Raise(40)), 40)
+                ],
+            "Expected a dead small blind and a raise of 40, was {:?}",
+            actions
+        );
+    }
+
+    #[test]
+    fn test_parse_fixed_limit_blinds() {
+        use ActionKind::{BigBlind, SmallBlind};
+        use Stage::*;
+        // Carol is all in for less than the big blind, so it is twice the small blind.
+        let mut h = history(
+            vec![
+                seat("Bob", 2000, "5c 6d"),
+                seat("Carol", 15, "9c 9s"),
+                seat("Alice", 2000, "Ah Kd"),
+            ],
+            vec![
+                action(Blinds, "Bob", SmallBlind, 10),
+                action(Blinds, "Carol", BigBlind, 15),
+                action(PreFlop, "Alice", ActionKind::Bet(Bet::Fold), 0),
+                action(PreFlop, "Bob", ActionKind::Bet(Bet::Call), 5),
+            ],
+            "2c 7d 9h Js Kc",
+        );
+        h.betting_structure = BettingStructure::FixedLimit {
+            small_bet: 40,
+            big_bet: 80,
+            cap: FIXED_LIMIT_CAP,
+        };
+        h.showdown = vec![shown(&h, "Bob"), shown(&h, "Carol")];
+        h.awards = vec![award(0, "Carol", 30)];
+        h.winner = Some(Winner::SoleWinner(shown(&h, "Carol")));
+        let parsed = parse(&export(&h)).unwrap().remove(0);
+        assert!(
+            (parsed.small_blind, parsed.big_blind) == (10, 20)
+                && parsed.betting_structure == h.betting_structure,
+            "Expected blinds of 10/20 in a 40/80 game, was {}/{} in {:?}",
+            parsed.small_blind,
+            parsed.big_blind,
+            parsed.betting_structure
+        );
+    }
+
+    #[test]
+    fn test_parse_errors() {
+        let text = export(&showdown_hand());
+        let broken = text.replace("Alice: bets 60", "Alice: dances");
+        let line = text.lines().position(|l| l == "Alice: bets 60").unwrap() + 1;
+        let error = parse(&broken).unwrap_err();
+        assert!(
+            error.line == line,
+            "Expected an error on line {}, was {}",
+            line,
+            error
+        );
+        let broken = text.replace("[2c 7d 9h]", "[2c 7x 9h]");
+        assert!(parse(&broken).is_err(), "Expected an invalid card error");
+        let broken = format!("{}\n{}", text, text.replace("Hand #1:", "Hand #one:"));
+        let error = parse(&broken).unwrap_err();
+        assert!(
+            error.line == text.lines().count() + 2,
+            "Expected the error at the start of the second hand, was {}",
+            error
+        );
+        assert!(parse("").unwrap().is_empty());
+    }
+}
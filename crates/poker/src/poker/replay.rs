@@ -0,0 +1,467 @@
+/// Replaying recorded hands through the game engine, to check that the engine still
+/// deals with them in the same way.
+use crate::poker::{
+    betting_strategy::BetArgs,
+    card::{self, Card},
+    config::{BombPotRules, ConfigError, GameConfig, RakeRules, Straddle},
+    error::GameError,
+    game::{Bet, Game},
+    history::{ActionKind, HandHistory, HandHistoryRecorder},
+    player::{Actor, Msg, Player, PlayerHand, Winner},
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    collections::VecDeque,
+    fmt::{self, Display},
+};
+
+/// Errors found when replaying a hand.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum ReplayError {
+    /// The hand's blinds or stacks can't be used to configure a game.
+    Config(ConfigError),
+    /// The engine failed to play the hand, e.g. because a recorded bet is illegal.
+    Game(GameError),
+    /// The engine played the hand differently from the recording.
+    Mismatch {
+        what: String,
+        expected: String,
+        actual: String,
+    },
+}
+/// Implementation of Display trait for ReplayError.
+impl Display for ReplayError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::Config(e) => write!(f, "Can't configure the game: {}", e),
+            ReplayError::Game(e) => write!(f, "The game failed: {}", e),
+            ReplayError::Mismatch {
+                what,
+                expected,
+                actual,
+            } => write!(f, "Expected {} to be {}, was {}", what, expected, actual),
+        }
+    }
+}
+/// Implementation of Error trait for ReplayError.
+impl std::error::Error for ReplayError {}
+
+/// An actor that makes a fixed list of bets in order, e.g. the bets recorded for a
+/// player in a hand history. It stops responding when the list runs out. It always
+/// agrees to straddle and to run it twice, which a replay only offers if the recorded
+/// hand did, and shows a beaten hand unless it was made to muck.
+#[derive(Debug, Default)]
+pub struct ScriptedActor {
+    bets: VecDeque<Bet>,
+    muck: bool,
+}
+/// Implementation for ScriptedActor.
+impl ScriptedActor {
+    pub fn new(bets: impl IntoIterator<Item = Bet>) -> Self {
+        ScriptedActor {
+            bets: bets.into_iter().collect(),
+            muck: false,
+        }
+    }
+
+    /// Muck a beaten hand at the showdown rather than showing it.
+    pub fn mucking(mut self) -> Self {
+        self.muck = true;
+        self
+    }
+}
+/// Implementation of Actor for ScriptedActor.
+impl Actor for ScriptedActor {
+    fn set_name_and_bank_roll(&self, _name: &str, _bank_roll: usize) {}
+    fn hole_cards(&self, _hole_cards: (Card, Card)) {}
+    fn place_bet(
+        &mut self,
+        _args: BetArgs,
+        _hole_cards: (Card, Card),
+        _bank_roll: usize,
+    ) -> Option<Bet> {
+        self.bets.pop_front()
+    }
+    fn update(&self, _msg: &Msg) {}
+    fn straddle(&mut self, _amount: usize) -> bool {
+        true
+    }
+    fn run_it_twice(&mut self) -> bool {
+        true
+    }
+    fn show_hand(&mut self, _beaten_by: &PlayerHand) -> bool {
+        !self.muck
+    }
+}
+
+/// The deck that deals a recorded hand: the players' hole cards and the board, followed
+/// by the rest of the cards. Cards that weren't recorded, such as the hole cards of
+/// players who didn't show them, are taken from the rest of the deck.
+pub fn deck_for(history: &HandHistory) -> Vec<Card> {
+    let known: Vec<Card> = history
+        .seats
+        .iter()
+        .filter_map(|s| s.hole_cards)
+        .flat_map(|(c1, c2)| [c1, c2])
+        .chain(history.board.iter().copied())
+        .chain(history.second_board.iter().copied())
+        .collect();
+    let mut rest = card::new_deck().into_iter().filter(|c| !known.contains(c));
+    let mut next = |card: Option<Card>| card.or_else(|| rest.next());
+    // The hole cards are taken from the front of the deck and dealt from the back of
+    // those taken, so the first player's are the last two.
+    let n = history.seats.len();
+    let mut hole: Vec<Option<Card>> = vec![None; 2 * n];
+    for (i, seat) in history.seats.iter().enumerate() {
+        if let Some((c1, c2)) = seat.hole_cards {
+            hole[2 * n - 1 - 2 * i] = Some(c1);
+            hole[2 * n - 2 - 2 * i] = Some(c2);
+        }
+    }
+    let mut deck: Vec<Card> = hole.into_iter().filter_map(&mut next).collect();
+    for i in 0..5 {
+        if let Some(c) = next(history.board.get(i).copied()) {
+            deck.push(c);
+        }
+    }
+    // The cards only on the second board are dealt after the first board.
+    deck.extend(
+        history
+            .second_board
+            .iter()
+            .filter(|c| !history.board.contains(c)),
+    );
+    deck.extend(rest);
+    deck
+}
+
+/// The names of the winners of a round.
+fn winner_names(winner: &Option<Winner>) -> Vec<String> {
+    let mut names: Vec<String> = match winner {
+        Some(Winner::SoleWinner(ph)) => vec![ph.name.clone()],
+        Some(Winner::Draw(phs)) => phs.iter().map(|ph| ph.name.clone()).collect(),
+        None => Vec::new(),
+    };
+    names.sort();
+    names
+}
+
+/// Check that a value from the replay matches the recording.
+fn check<T: PartialEq + fmt::Debug>(what: &str, expected: T, actual: T) -> Result<(), ReplayError> {
+    if expected == actual {
+        Ok(())
+    } else {
+        Err(ReplayError::Mismatch {
+            what: what.to_string(),
+            expected: format!("{:?}", expected),
+            actual: format!("{:?}", actual),
+        })
+    }
+}
+
+/// Replay a recorded hand through the engine. A game is set up with the hand's seats,
+/// stacks, button, blinds and betting structure and a deck stacked to deal the recorded
+/// cards, and each player bets as recorded using a `ScriptedActor`. The actions, board, pots, final
+/// stacks and winners of the replay are checked against the recording, and the history
+/// of the replay is returned.
+pub fn replay(history: &HandHistory) -> Result<HandHistory, ReplayError> {
+    let n = history.seats.len();
+    let mut config = GameConfig::builder()
+        .max_players(u8::try_from(n).unwrap_or(u8::MAX))
+        .small_blind(history.small_blind)
+        .big_blind(history.big_blind)
+        .ante(history.ante)
+        .betting_structure(history.betting_structure)
+        .run_it_twice(!history.second_board.is_empty());
+    if history.rake > 0 {
+        // Take exactly the recorded rake, whatever the rules were.
+        config = config.rake(RakeRules {
+            percent: 100,
+            cap: Some(history.rake),
+            no_flop_no_drop: false,
+        });
+    }
+    if let Some(straddle) = history
+        .actions
+        .iter()
+        .find(|a| a.action == ActionKind::Straddle)
+    {
+        config = config.straddle(if straddle.player == history.button {
+            Straddle::Button
+        } else {
+            Straddle::UnderTheGun
+        });
+    }
+    if let Some(ante) = history.bomb_pot() {
+        config = config.bomb_pot(BombPotRules { ante, every: 1 });
+    }
+    for seat in &history.seats {
+        config = config.starting_stack(&seat.player, seat.stack);
+    }
+    let config = config.build().map_err(ReplayError::Config)?;
+    let mut game = Game::from_config(config).map_err(ReplayError::Config)?;
+    let recorder = HandHistoryRecorder::new();
+    game.subscribe(recorder.clone());
+    game.set_deck(deck_for(history));
+    // The first player to join is the dealer in the first round.
+    let button = history
+        .seats
+        .iter()
+        .position(|s| s.player == history.button)
+        .unwrap_or(n.saturating_sub(1));
+    let mut seats = history.seats.clone();
+    seats.rotate_left(button);
+    for seat in seats {
+        let bets = history.actions.iter().filter_map(|a| match a.action {
+            ActionKind::Bet(bet) if a.player == seat.player => Some(bet),
+            _ => None,
+        });
+        let mut actor = ScriptedActor::new(bets);
+        if history.mucked.contains(&seat.player) {
+            actor = actor.mucking();
+        }
+        game.join(Player::build(&seat.player, actor))
+            .map_err(ReplayError::Game)?;
+    }
+    game.play_round().map_err(ReplayError::Game)?;
+    let replayed = recorder
+        .histories()
+        .pop()
+        .ok_or(ReplayError::Game(GameError::NoWinner))?;
+
+    check("the button", &history.button, &replayed.button)?;
+    for (i, (a, b)) in history.actions.iter().zip(&replayed.actions).enumerate() {
+        check(
+            &format!("action {}", i + 1),
+            (a.stage, &a.player, a.action, a.amount),
+            (b.stage, &b.player, b.action, b.amount),
+        )?;
+    }
+    check(
+        "the number of actions",
+        history.actions.len(),
+        replayed.actions.len(),
+    )?;
+    check(
+        "the board",
+        history.board.as_slice(),
+        &replayed.board[..history.board.len().min(replayed.board.len())],
+    )?;
+    let pots = |h: &HandHistory| {
+        let mut pots: Vec<(usize, String, usize)> = h
+            .awards
+            .iter()
+            .map(|a| (a.pot, a.player.clone(), a.amount))
+            .collect();
+        pots.sort();
+        pots
+    };
+    check(
+        "the second board",
+        &history.second_board,
+        &replayed.second_board,
+    )?;
+    check("the mucked hands", &history.mucked, &replayed.mucked)?;
+    check("the rake", history.rake, replayed.rake)?;
+    check("the pots", pots(history), pots(&replayed))?;
+    let stacks: Vec<(String, usize)> = history
+        .seats
+        .iter()
+        .map(|s| {
+            let stack = game
+                .bank_rolls()
+                .into_iter()
+                .find(|(name, _)| name == &s.player)
+                .map_or(0, |(_, stack)| stack);
+            (s.player.clone(), stack)
+        })
+        .collect();
+    check("the final stacks", history.final_stacks(), stacks)?;
+    if history.winner.is_some() {
+        check(
+            "the winners",
+            winner_names(&history.winner),
+            winner_names(&replayed.winner),
+        )?;
+    }
+    Ok(replayed)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::{
+        autoactor::AutoActor,
+        betting_strategy::{modest_betting_strategy, six_max},
+        config::BettingStructure,
+        ohh, pokerstars,
+    };
+    use std::{fs, path::Path};
+
+    fn recorded_histories() -> Vec<HandHistory> {
+        recorded_game(BettingStructure::NoLimit)
+    }
+
+    fn recorded_game(structure: BettingStructure) -> Vec<HandHistory> {
+        let recorder = HandHistoryRecorder::new();
+        let config = GameConfig::builder()
+            .big_blind(20)
+            .max_players(4)
+            .betting_structure(structure)
+            .seed(3)
+            .build()
+            .unwrap();
+        let mut game = Game::from_config(config).unwrap();
+        game.subscribe(recorder.clone());
+        let _ = game.join(Player::build("Alice", AutoActor::build(six_max)));
+        let _ = game.join(Player::build(
+            "Bob",
+            AutoActor::build(modest_betting_strategy),
+        ));
+        let _ = game.join(Player::build("Carol", AutoActor::new()));
+        let _ = game.join(Player::build(
+            "Dave",
+            AutoActor::build(modest_betting_strategy),
+        ));
+        // Games with limits on raising can go on for thousands of rounds.
+        while recorder.histories().len() < 200 && game.play_round().unwrap().is_none() {}
+        recorder.histories()
+    }
+
+    #[test]
+    fn test_deck_for() {
+        let history = recorded_histories().remove(0);
+        let deck = deck_for(&history);
+        let mut sorted = deck.clone();
+        sorted.sort();
+        let mut full = card::new_deck();
+        full.sort();
+        assert!(sorted == full, "Expected a full deck");
+        let (c1, c2) = history.seats[0].hole_cards.unwrap();
+        let n = history.seats.len();
+        assert!(
+            deck[2 * n - 1] == c1 && deck[2 * n - 2] == c2,
+            "Expected the first player's hole cards at the end of those dealt"
+        );
+        assert!(
+            deck[2 * n..2 * n + 5] == history.board[..],
+            "Expected the board after the hole cards"
+        );
+    }
+
+    #[test]
+    fn test_replay_recorded() {
+        let histories = recorded_histories();
+        assert!(histories.len() > 1, "Expected a game of several rounds");
+        for h in &histories {
+            let replayed = replay(h);
+            assert!(
+                replayed.is_ok(),
+                "Expected hand {} to replay, was {:?}",
+                h.hand_number,
+                replayed
+            );
+        }
+    }
+
+    #[test]
+    fn test_replay_betting_structures() {
+        let structures = [
+            BettingStructure::PotLimit,
+            BettingStructure::FixedLimit {
+                small_bet: 20,
+                big_bet: 40,
+                cap: 4,
+            },
+            BettingStructure::FixedLimit {
+                small_bet: 40,
+                big_bet: 80,
+                cap: 4,
+            },
+        ];
+        for structure in structures {
+            for h in recorded_game(structure) {
+                let exported = [
+                    pokerstars::parse(&pokerstars::export(&h))
+                        .unwrap()
+                        .remove(0),
+                    ohh::import(&ohh::export(&h)).unwrap(),
+                ];
+                assert!(
+                    exported.iter().all(|e| e.betting_structure == structure
+                        && (e.small_blind, e.big_blind) == (h.small_blind, h.big_blind)),
+                    "Expected hand {} to be exported as {:?} with the same blinds",
+                    h.hand_number,
+                    structure
+                );
+                let replayed = replay(&h);
+                assert!(
+                    matches!(&replayed, Ok(r) if r.betting_structure == structure),
+                    "Expected hand {} to replay as {:?}, was {:?}",
+                    h.hand_number,
+                    structure,
+                    replayed
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_replay_mismatch() {
+        let mut history = recorded_histories().remove(0);
+        history.awards[0].amount += 1;
+        let result = replay(&history);
+        assert!(
+            matches!(result, Err(ReplayError::Mismatch { .. })),
+            "Expected the replay not to match, was {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_replay_illegal_bet() {
+        let mut history = recorded_histories().remove(0);
+        // The first player to act before the flop has the big blind to call.
+        let first = history
+            .actions
+            .iter()
+            .position(|a| matches!(a.action, ActionKind::Bet(_)))
+            .unwrap();
+        history.actions[first].action = ActionKind::Bet(Bet::Check);
+        let result = replay(&history);
+        assert!(
+            matches!(
+                result,
+                Err(ReplayError::Game(GameError::IllegalAction { .. }))
+            ),
+            "Expected the replay to fail, was {:?}",
+            result
+        );
+    }
+
+    /// Replays the hands in the files in tests/data/replay, which are PokerStars hand
+    /// histories. Add a file there to make a regression test from a bug report.
+    #[test]
+    fn test_replay_regressions() {
+        let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/data/replay");
+        let mut files: Vec<_> = fs::read_dir(dir)
+            .unwrap()
+            .map(|f| f.unwrap().path())
+            .collect();
+        files.sort();
+        assert!(!files.is_empty(), "Expected some hands to replay");
+        for file in files {
+            let hands = pokerstars::parse(&fs::read_to_string(&file).unwrap()).unwrap();
+            for h in hands {
+                let replayed = replay(&h);
+                assert!(
+                    replayed.is_ok(),
+                    "Expected hand {} in {} to replay, was {}",
+                    h.hand_number,
+                    file.display(),
+                    replayed.unwrap_err()
+                );
+            }
+        }
+    }
+}
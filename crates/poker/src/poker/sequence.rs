@@ -90,7 +90,7 @@ pub fn longest_sequence(cards: &[Card]) -> Vec<Card> {
     final_sequence_cards
 }
 
-/// Group a collection of cards by their rank.
+/// Group a collection of cards by their rank, largest groups first.
 pub fn group_by_rank(cards: &[Card]) -> Vec<Vec<Card>> {
     let mut grouped_by_rank: HashMap<Rank, Vec<Card>> = HashMap::new();
 
@@ -98,7 +98,8 @@ pub fn group_by_rank(cards: &[Card]) -> Vec<Vec<Card>> {
         grouped_by_rank.entry(card.rank).or_default().push(*card);
     }
     let mut cs: Vec<Vec<Card>> = grouped_by_rank.into_values().collect();
-    cs.sort_by_key(|b| Reverse(b.len()));
+    // Sort by size, then by rank so that e.g. the higher of two pairs comes first.
+    cs.sort_by_key(|b| Reverse((b.len(), b[0].rank)));
     cs
 }
 
@@ -112,7 +113,7 @@ pub fn group_by_suit(cards: &[Card]) -> Vec<Vec<Card>> {
     let mut cs: Vec<Vec<Card>> = grouped_by_suit.into_values().collect();
     // Sort inner lists by rank descending.
     cs.iter_mut()
-        .for_each(|inner| inner.sort_by(|a, b| b.rank.cmp(&a.rank)));
+        .for_each(|inner| inner.sort_by_key(|c| Reverse(c.rank)));
     // Sort outer lists by length.
     cs.sort_by_key(|b| Reverse(b.len()));
     cs
@@ -169,9 +170,9 @@ mod tests {
                 c.len()
             );
             assert!(
-                c.get(0).unwrap().rank == Rank::Rank2,
+                c.first().unwrap().rank == Rank::Rank2,
                 "group_by_rank(ONE_PAIR): longest group should have Rank2 cards, was {:?}",
-                c.get(0).unwrap().rank
+                c.first().unwrap().rank
             );
         } else {
             panic!("group_by_rank(ONE_PAIR): Nothing in the longest group")
@@ -199,6 +200,39 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_group_by_rank_two_pairs() {
+        // The lower pair comes first, and groups of the same size should be ordered
+        // by rank whatever order the cards are in.
+        let cards = [
+            (Rank::Rank4, Suit::Clubs),
+            (Rank::Rank4, Suit::Hearts),
+            (Rank::King, Suit::Spades),
+            (Rank::Jack, Suit::Clubs),
+            (Rank::Jack, Suit::Diamonds),
+            (Rank::Rank9, Suit::Hearts),
+            (Rank::Rank9, Suit::Spades),
+        ]
+        .map(|(rank, suit)| Card { rank, suit });
+        for _ in 0..20 {
+            let ranks: Vec<(Rank, usize)> = group_by_rank(&cards)
+                .iter()
+                .map(|g| (g[0].rank, g.len()))
+                .collect();
+            assert!(
+                ranks
+                    == vec![
+                        (Rank::Jack, 2),
+                        (Rank::Rank9, 2),
+                        (Rank::Rank4, 2),
+                        (Rank::King, 1)
+                    ],
+                "group_by_rank(THREE_PAIRS): expected the pairs highest first, was {:?}",
+                ranks
+            );
+        }
+    }
+
     #[test]
     fn test_same_suit() {
         let h1: [Card; 3] = [
@@ -0,0 +1,227 @@
+/// Cash game sessions, which are played for a number of hands or a length of time
+/// rather than until one player has all the chips. See `Game::play_session`.
+use crate::poker::events::{GameEvent, Subscriber};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    fmt::{self, Display},
+    rc::Rc,
+    sync::{
+        Arc, Mutex,
+        atomic::{AtomicBool, Ordering},
+    },
+    time::Duration,
+};
+
+/// A handle for controlling a session from another thread. Requests are dealt with
+/// between hands, so the hand being played is always finished. Clones share the same
+/// session.
+#[derive(Debug, Clone, Default)]
+pub struct SessionHandle {
+    stopped: Arc<AtomicBool>,
+    cash_outs: Arc<Mutex<Vec<String>>>,
+}
+/// Implementation for SessionHandle.
+impl SessionHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Ask the session to stop after the hand being played.
+    pub fn stop(&self) {
+        self.stopped.store(true, Ordering::SeqCst);
+    }
+
+    /// Whether the session has been asked to stop.
+    pub fn stopped(&self) -> bool {
+        self.stopped.load(Ordering::SeqCst)
+    }
+
+    /// Ask for the named player to cash out and leave the table after the hand being
+    /// played.
+    pub fn cash_out(&self, name: &str) {
+        self.cash_outs.lock().unwrap().push(name.to_string());
+    }
+
+    /// Remove and return the players who have asked to cash out.
+    pub(crate) fn take_cash_outs(&self) -> Vec<String> {
+        std::mem::take(&mut *self.cash_outs.lock().unwrap())
+    }
+}
+
+/// How a player got on in a session.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerResult {
+    pub name: String,
+    /// The chips the player had at the start of the session or when they joined, plus
+    /// any they bought since.
+    pub bought: usize,
+    /// The chips the player had at the end of the session or when they left.
+    pub chips: usize,
+    pub hands_played: usize,
+    /// Whether the player left the table or was knocked out before the end.
+    pub left: bool,
+}
+/// Implementation for PlayerResult.
+impl PlayerResult {
+    /// The chips won, or lost if negative.
+    pub fn net(&self) -> i64 {
+        self.chips as i64 - self.bought as i64
+    }
+}
+
+/// A summary of a session, returned by `Game::play_session`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SessionSummary {
+    pub hands: usize,
+    pub duration: Duration,
+    /// The chips awarded in the biggest hand.
+    pub biggest_pot: usize,
+    /// The chips taken by the house over the session.
+    #[serde(default)]
+    pub rake: usize,
+    /// The players in the order they sat down.
+    pub players: Vec<PlayerResult>,
+}
+/// Implementation for SessionSummary.
+impl SessionSummary {
+    /// The result for the named player.
+    pub fn player(&self, name: &str) -> Option<&PlayerResult> {
+        self.players.iter().find(|p| p.name == name)
+    }
+}
+/// Implementation of Display trait for SessionSummary.
+impl Display for SessionSummary {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(
+            f,
+            "{} hands in {}s, biggest pot {}, rake {}",
+            self.hands,
+            self.duration.as_secs(),
+            self.biggest_pot,
+            self.rake
+        )?;
+        for p in &self.players {
+            writeln!(
+                f,
+                "{}: {:+} ({} hands{})",
+                p.name,
+                p.net(),
+                p.hands_played,
+                if p.left { ", left" } else { "" }
+            )?;
+        }
+        Ok(())
+    }
+}
+
+/// The results collected so far in a session.
+#[derive(Debug, Default)]
+struct Tally {
+    hands: usize,
+    pot: usize,
+    biggest_pot: usize,
+    rake: usize,
+    players: Vec<PlayerResult>,
+}
+/// Implementation for Tally.
+impl Tally {
+    /// The named player's result, or their latest one if they left and came back.
+    fn player(&mut self, name: &str) -> Option<&mut PlayerResult> {
+        self.players.iter_mut().rev().find(|p| p.name == name)
+    }
+
+    fn add_player(&mut self, name: &str, bank_roll: usize) {
+        self.players.push(PlayerResult {
+            name: name.to_string(),
+            bought: bank_roll,
+            chips: 0,
+            hands_played: 0,
+            left: false,
+        });
+    }
+}
+
+/// A subscriber that keeps the results of a session from the game's events.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct SessionTracker {
+    tally: Rc<RefCell<Tally>>,
+}
+/// Implementation for SessionTracker.
+impl SessionTracker {
+    /// Start tracking a session with the players at the table and their bank rolls.
+    pub(crate) fn new(players: Vec<(String, usize)>) -> Self {
+        let tracker = Self::default();
+        players
+            .iter()
+            .for_each(|(name, bank_roll)| tracker.tally.borrow_mut().add_player(name, *bank_roll));
+        tracker
+    }
+
+    /// The number of hands started so far.
+    pub(crate) fn hands(&self) -> usize {
+        self.tally.borrow().hands
+    }
+
+    /// Finish the session with the players still at the table and their bank rolls.
+    pub(crate) fn summary(
+        &self,
+        players: Vec<(String, usize)>,
+        duration: Duration,
+    ) -> SessionSummary {
+        let mut tally = self.tally.borrow_mut();
+        for (name, bank_roll) in players {
+            if let Some(p) = tally.player(&name) {
+                p.chips = bank_roll;
+            }
+        }
+        SessionSummary {
+            hands: tally.hands,
+            duration,
+            biggest_pot: tally.biggest_pot,
+            rake: tally.rake,
+            players: tally.players.clone(),
+        }
+    }
+}
+/// Implementation of Subscriber for SessionTracker.
+impl Subscriber for SessionTracker {
+    fn notify(&self, event: &GameEvent) {
+        let mut tally = self.tally.borrow_mut();
+        match event {
+            GameEvent::PlayerJoined { player, bank_roll } => tally.add_player(player, *bank_roll),
+            GameEvent::Rebuy { player, amount } | GameEvent::AddOn { player, amount } => {
+                if let Some(p) = tally.player(player) {
+                    p.bought += amount;
+                }
+            }
+            GameEvent::RoundStarted { players, .. } => {
+                tally.hands += 1;
+                tally.pot = 0;
+                for (name, _) in players {
+                    if let Some(p) = tally.player(name) {
+                        p.hands_played += 1;
+                    }
+                }
+            }
+            GameEvent::PotAwarded { amount, .. } => {
+                tally.pot += amount;
+                tally.biggest_pot = tally.biggest_pot.max(tally.pot);
+            }
+            GameEvent::RakeTaken { amount } => tally.rake += amount,
+            GameEvent::PlayerLeft { player, bank_roll } => {
+                if let Some(p) = tally.player(player) {
+                    p.chips = *bank_roll;
+                    p.left = true;
+                }
+            }
+            GameEvent::PlayerEliminated { player } => {
+                if let Some(p) = tally.player(player) {
+                    p.chips = 0;
+                    p.left = true;
+                }
+            }
+            _ => {}
+        }
+    }
+}
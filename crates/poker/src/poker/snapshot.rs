@@ -0,0 +1,126 @@
+/// Saving the state of a game so that it can be restored later, e.g. after a restart.
+use crate::poker::{
+    audit::ChipAudit,
+    blinds::{BlindScheduleError, ScheduleState},
+    card::Card,
+    config::{ConfigError, GameConfig},
+    fair::ShuffleSeeds,
+    game::{Betting, SidePot, Stage},
+    player::Winner,
+};
+use serde::{Deserialize, Serialize};
+use std::fmt::{self, Display};
+
+/// Errors found when restoring a game from a snapshot.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum SnapshotError {
+    /// The snapshot couldn't be read.
+    Json(String),
+    /// The snapshot's configuration isn't valid.
+    Config(ConfigError),
+    /// The snapshot's blind schedule isn't valid.
+    BlindSchedule(BlindScheduleError),
+    /// No player was supplied for a seat in the snapshot.
+    MissingPlayer(String),
+    /// A player was supplied who isn't in the snapshot.
+    UnknownPlayer(String),
+}
+/// Implementation of Display trait for SnapshotError.
+impl Display for SnapshotError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SnapshotError::Json(e) => write!(f, "Can't read the snapshot: {}", e),
+            SnapshotError::Config(e) => write!(f, "Invalid configuration: {}", e),
+            SnapshotError::BlindSchedule(e) => write!(f, "Invalid blind schedule: {}", e),
+            SnapshotError::MissingPlayer(name) => {
+                write!(f, "No player was supplied for {}", name)
+            }
+            SnapshotError::UnknownPlayer(name) => {
+                write!(f, "{} isn't playing in the saved game", name)
+            }
+        }
+    }
+}
+/// Implementation of Error trait for SnapshotError.
+impl std::error::Error for SnapshotError {}
+
+/// The state of a player in a snapshot. The player's actor isn't saved.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct PlayerState {
+    pub name: String,
+    pub hole: Option<(Card, Card)>,
+    pub bet: usize,
+    pub total_bet: usize,
+    pub bank_roll: usize,
+    pub all_in: bool,
+    pub folded: bool,
+    #[serde(default)]
+    pub sitting_out: bool,
+    #[serde(default)]
+    pub missed_blinds: bool,
+    #[serde(default)]
+    pub leaving: bool,
+    #[serde(default)]
+    pub rebuys: usize,
+}
+
+/// The complete state of a game, taken with `Game::snapshot` and restored with
+/// `Game::restore`. Snapshots can be taken between rounds or in the middle of one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct GameSnapshot {
+    pub uuid: uuid::Uuid,
+    pub config: GameConfig,
+    /// The players in the order they sit at the table.
+    pub players: Vec<PlayerState>,
+    pub dealer: Option<String>,
+    pub small_blind: usize,
+    pub big_blind: usize,
+    pub ante: usize,
+    pub blind_schedule: Option<ScheduleState>,
+    pub stage: Stage,
+    pub num_rounds: usize,
+    pub pot: usize,
+    pub deck: Vec<Card>,
+    pub community_cards: Vec<Card>,
+    pub winner: Option<Winner>,
+    pub game_winner: Option<String>,
+    /// The seed for shuffling the decks of later rounds.
+    pub rng_seed: u64,
+    /// The seeds for the current round's deck in a provably fair game, which are secret
+    /// until the end of the round.
+    pub shuffle_seeds: Option<ShuffleSeeds>,
+    #[serde(default)]
+    pub audit: ChipAudit,
+    /// Whether the add-on has been offered to the players.
+    #[serde(default)]
+    pub add_ons_offered: bool,
+    /// Whether the rest of the board is being dealt without betting.
+    #[serde(default)]
+    pub runout: bool,
+    /// The second board if the players are running it twice.
+    #[serde(default)]
+    pub second_board: Option<Vec<Card>>,
+    /// The last player to bet or raise in the current round of betting.
+    #[serde(default)]
+    pub last_aggressor: Option<String>,
+    /// The player who straddled in the current round.
+    #[serde(default)]
+    pub straddle: Option<String>,
+    /// Whether the current round is a bomb pot.
+    #[serde(default)]
+    pub bomb_pot: bool,
+    pub(crate) side_pots: Vec<SidePot>,
+    pub(crate) betting: Option<Betting>,
+}
+/// Implementation for GameSnapshot.
+impl GameSnapshot {
+    /// Write the snapshot as JSON.
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+
+    /// Read a snapshot written by `to_json`.
+    pub fn from_json(json: &str) -> Result<Self, SnapshotError> {
+        serde_json::from_str(json).map_err(|e| SnapshotError::Json(e.to_string()))
+    }
+}
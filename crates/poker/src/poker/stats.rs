@@ -0,0 +1,494 @@
+/// Statistics about how each player plays, built up from the hands they have played,
+/// so that bots and players can take account of their opponents' tendencies.
+use crate::poker::{
+    events::{GameEvent, Subscriber},
+    game::{Bet, Stage},
+    history::{ActionKind, HandHistory, HandHistoryRecorder},
+    player::Winner,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::RefCell,
+    collections::{BTreeMap, HashMap, HashSet},
+    fmt::{self, Display},
+    rc::Rc,
+};
+
+/// The counts behind a player's statistics. Most statistics are the number of times
+/// the player did something as a percentage of the times they had the chance to.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct PlayerStats {
+    pub hands: usize,
+    /// Hands in which the player called or raised before the flop.
+    pub voluntarily_put_in: usize,
+    /// Hands in which the player raised before the flop.
+    pub preflop_raised: usize,
+    /// Hands in which the player faced a single raise before the flop.
+    pub three_bet_chances: usize,
+    /// Hands in which the player re-raised a single raise before the flop.
+    pub three_bets: usize,
+    /// Bets and raises after the flop was dealt.
+    pub bets_and_raises: usize,
+    /// Calls after the flop was dealt.
+    pub calls: usize,
+    pub saw_flop: usize,
+    pub went_to_showdown: usize,
+    pub won_at_showdown: usize,
+    /// Hands in which the player made the last raise before the flop and was first to
+    /// have the chance to bet on it.
+    pub cbet_chances: usize,
+    /// Hands in which the player took the chance to make a continuation bet.
+    pub cbets: usize,
+}
+/// Implementation for PlayerStats.
+impl PlayerStats {
+    /// Voluntarily put money in pot: the percentage of hands in which the player
+    /// called or raised before the flop.
+    pub fn vpip(&self) -> f64 {
+        percent(self.voluntarily_put_in, self.hands)
+    }
+
+    /// Pre-flop raise: the percentage of hands in which the player raised before the
+    /// flop.
+    pub fn pfr(&self) -> f64 {
+        percent(self.preflop_raised, self.hands)
+    }
+
+    /// The percentage of chances to re-raise before the flop that the player took.
+    pub fn three_bet(&self) -> f64 {
+        percent(self.three_bets, self.three_bet_chances)
+    }
+
+    /// Bets and raises per call after the flop, or None if the player has not called.
+    pub fn aggression_factor(&self) -> Option<f64> {
+        (self.calls > 0).then(|| self.bets_and_raises as f64 / self.calls as f64)
+    }
+
+    /// Went to showdown: the percentage of hands in which the player saw the flop
+    /// and went to the showdown.
+    pub fn wtsd(&self) -> f64 {
+        percent(self.went_to_showdown, self.saw_flop)
+    }
+
+    /// Won at showdown: the percentage of showdowns the player won.
+    pub fn wsd(&self) -> f64 {
+        percent(self.won_at_showdown, self.went_to_showdown)
+    }
+
+    /// Continuation bet: the percentage of chances to bet the flop after raising
+    /// before it that the player took.
+    pub fn cbet(&self) -> f64 {
+        percent(self.cbets, self.cbet_chances)
+    }
+}
+/// Implementation of Display trait for PlayerStats.
+impl Display for PlayerStats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "{} hands, VPIP {:.0}%, PFR {:.0}%, 3-bet {:.0}%, AF {}, WTSD {:.0}%, W$SD {:.0}%, c-bet {:.0}%",
+            self.hands,
+            self.vpip(),
+            self.pfr(),
+            self.three_bet(),
+            self.aggression_factor()
+                .map_or("-".to_string(), |af| format!("{:.1}", af)),
+            self.wtsd(),
+            self.wsd(),
+            self.cbet()
+        )
+    }
+}
+
+/// A number as a percentage of another, or zero if the other is zero.
+fn percent(count: usize, of: usize) -> f64 {
+    if of == 0 {
+        0.0
+    } else {
+        100.0 * count as f64 / of as f64
+    }
+}
+
+/// The statistics of every player seen, by name.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct Stats {
+    players: BTreeMap<String, PlayerStats>,
+}
+/// Implementation for Stats.
+impl Stats {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// The named player's statistics.
+    pub fn player(&self, name: &str) -> Option<&PlayerStats> {
+        self.players.get(name)
+    }
+
+    /// The players' statistics, in order of name.
+    pub fn players(&self) -> impl Iterator<Item = (&String, &PlayerStats)> {
+        self.players.iter()
+    }
+
+    /// Serialise the statistics as JSON.
+    pub fn to_json(&self) -> Result<String, serde_json::Error> {
+        serde_json::to_string_pretty(self)
+    }
+
+    /// Read statistics from JSON.
+    pub fn from_json(json: &str) -> Result<Self, serde_json::Error> {
+        serde_json::from_str(json)
+    }
+
+    /// Add a hand to the statistics of the players in it.
+    pub fn record(&mut self, history: &HandHistory) {
+        for seat in &history.seats {
+            self.stats(&seat.player).hands += 1;
+        }
+
+        // Before the flop, counting the blinds and any straddle as the first bet.
+        let mut bets: HashMap<&str, usize> = HashMap::new();
+        let mut highest = 0;
+        let mut raises = 0;
+        let mut aggressor: Option<&str> = None;
+        let mut folded = HashSet::new();
+        let mut vpip = HashSet::new();
+        let mut pfr = HashSet::new();
+        let mut three_bet_chances = HashSet::new();
+        let mut three_bets = HashSet::new();
+        for a in history
+            .actions
+            .iter()
+            .filter(|a| matches!(a.stage, Stage::Blinds | Stage::PreFlop))
+        {
+            let player = a.player.as_str();
+            let total = bets.get(player).copied().unwrap_or_default() + a.amount;
+            match a.action {
+                ActionKind::Ante => continue,
+                ActionKind::SmallBlind | ActionKind::BigBlind | ActionKind::Straddle => {}
+                ActionKind::Bet(bet) => {
+                    let raised = total > highest;
+                    // Only the first chance to re-raise in a hand is counted.
+                    let chance = raises == 1
+                        && aggressor != Some(player)
+                        && three_bet_chances.insert(player);
+                    if chance && raised {
+                        three_bets.insert(player);
+                    }
+                    match bet {
+                        Bet::Fold => {
+                            folded.insert(player);
+                        }
+                        Bet::Check => {}
+                        Bet::Call | Bet::Raise(_) | Bet::AllIn(_) => {
+                            if a.amount > 0 {
+                                vpip.insert(player);
+                            }
+                        }
+                    }
+                    if raised {
+                        pfr.insert(player);
+                        raises += 1;
+                        aggressor = Some(player);
+                    }
+                }
+            }
+            highest = highest.max(total);
+            bets.insert(player, total);
+        }
+
+        // After the flop, where the bets start again on each street.
+        let mut cbet_chance = None;
+        let mut aggressor_acted = false;
+        for stage in [Stage::Flop, Stage::Turn, Stage::River] {
+            let mut bets: HashMap<&str, usize> = HashMap::new();
+            let mut highest = 0;
+            for a in history.actions_in(stage) {
+                let ActionKind::Bet(bet) = a.action else {
+                    continue;
+                };
+                let player = a.player.as_str();
+                let total = bets.get(player).copied().unwrap_or_default() + a.amount;
+                let raised = total > highest;
+                if stage == Stage::Flop && aggressor == Some(player) && !aggressor_acted {
+                    aggressor_acted = true;
+                    // Someone else betting first takes away the chance to c-bet.
+                    if highest == 0 {
+                        cbet_chance = Some(raised);
+                    }
+                }
+                if matches!(bet, Bet::Call | Bet::Raise(_) | Bet::AllIn(_)) && a.amount > 0 {
+                    if raised {
+                        self.stats(player).bets_and_raises += 1;
+                    } else {
+                        self.stats(player).calls += 1;
+                    }
+                }
+                highest = highest.max(total);
+                bets.insert(player, total);
+            }
+        }
+
+        let winners: Vec<&str> = match &history.winner {
+            Some(Winner::SoleWinner(hand)) => vec![hand.name.as_str()],
+            Some(Winner::Draw(hands)) => hands.iter().map(|h| h.name.as_str()).collect(),
+            None => Vec::new(),
+        };
+        for seat in &history.seats {
+            let player = seat.player.as_str();
+            let saw_flop = history.board.len() >= 3 && !folded.contains(player);
+            let showdown = history.showdown.iter().any(|h| h.name == player)
+                || history.mucked.iter().any(|m| m == player);
+            let stats = self.stats(player);
+            stats.voluntarily_put_in += vpip.contains(player) as usize;
+            stats.preflop_raised += pfr.contains(player) as usize;
+            stats.three_bet_chances += three_bet_chances.contains(player) as usize;
+            stats.three_bets += three_bets.contains(player) as usize;
+            stats.saw_flop += saw_flop as usize;
+            stats.went_to_showdown += showdown as usize;
+            stats.won_at_showdown += (showdown && winners.contains(&player)) as usize;
+            if aggressor == Some(player)
+                && let Some(cbet) = cbet_chance
+            {
+                stats.cbet_chances += 1;
+                stats.cbets += cbet as usize;
+            }
+        }
+    }
+
+    /// The named player's statistics, starting them if they are new.
+    fn stats(&mut self, name: &str) -> &mut PlayerStats {
+        self.players.entry(name.to_string()).or_default()
+    }
+}
+/// Implementation of Display trait for Stats.
+impl Display for Stats {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        for (name, stats) in &self.players {
+            writeln!(f, "{}: {}", name, stats)?;
+        }
+        Ok(())
+    }
+}
+
+/// A subscriber that keeps the statistics of the players in a game over a session.
+/// Clones share the same statistics, so a clone can be given to the game and the
+/// original used to read them.
+#[derive(Debug, Clone)]
+pub struct StatsTracker {
+    stats: Rc<RefCell<Stats>>,
+    recorder: HandHistoryRecorder,
+}
+/// Implementation for StatsTracker.
+impl StatsTracker {
+    /// Construct a tracker with no statistics.
+    pub fn new() -> Self {
+        Self::from_stats(Stats::default())
+    }
+
+    /// Construct a tracker that adds to statistics kept from earlier sessions.
+    pub fn from_stats(stats: Stats) -> Self {
+        let stats = Rc::new(RefCell::new(stats));
+        let recorded = stats.clone();
+        let recorder =
+            HandHistoryRecorder::with_callback(move |h| recorded.borrow_mut().record(&h));
+        StatsTracker { stats, recorder }
+    }
+
+    /// The statistics of the hands finished so far.
+    pub fn stats(&self) -> Stats {
+        self.stats.borrow().clone()
+    }
+
+    /// The named player's statistics from the hands finished so far.
+    pub fn player(&self, name: &str) -> Option<PlayerStats> {
+        self.stats.borrow().player(name).cloned()
+    }
+}
+/// Implementation of Default trait for StatsTracker.
+impl Default for StatsTracker {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+/// Implementation of Subscriber for StatsTracker.
+impl Subscriber for StatsTracker {
+    fn notify(&self, event: &GameEvent) {
+        self.recorder.notify(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::{
+        config::GameConfig, game::Game, player::Player, pokerstars, replay::ScriptedActor,
+    };
+
+    /// Play a round between three players who bet as scripted, returning the
+    /// statistics. player1 is the dealer, so acts first before the flop and last after
+    /// it.
+    fn scripted_stats(scripts: Vec<Vec<Bet>>) -> Stats {
+        let config = GameConfig::builder()
+            .big_blind(20)
+            .max_players(3)
+            .build()
+            .unwrap();
+        let mut game = Game::from_config(config).unwrap();
+        let tracker = StatsTracker::new();
+        game.subscribe(tracker.clone());
+        for (i, bets) in scripts.into_iter().enumerate() {
+            let name = format!("player{}", i + 1);
+            let _ = game.join(Player::build(&name, ScriptedActor::new(bets)));
+        }
+        game.play_round().unwrap();
+        tracker.stats()
+    }
+
+    #[test]
+    fn test_record() {
+        let text = include_str!("../../tests/data/pokerstars_showdown.txt");
+        let mut stats = Stats::new();
+        pokerstars::parse(text)
+            .unwrap()
+            .iter()
+            .for_each(|h| stats.record(h));
+        let alice = PlayerStats {
+            hands: 1,
+            voluntarily_put_in: 1,
+            preflop_raised: 1,
+            bets_and_raises: 1,
+            calls: 1,
+            saw_flop: 1,
+            went_to_showdown: 1,
+            cbet_chances: 1,
+            cbets: 1,
+            ..Default::default()
+        };
+        let bob = PlayerStats {
+            hands: 1,
+            three_bet_chances: 1,
+            ..Default::default()
+        };
+        let carol = PlayerStats {
+            hands: 1,
+            voluntarily_put_in: 1,
+            three_bet_chances: 1,
+            bets_and_raises: 1,
+            saw_flop: 1,
+            went_to_showdown: 1,
+            won_at_showdown: 1,
+            ..Default::default()
+        };
+        for (name, expected) in [("Alice", alice), ("Bob", bob), ("Carol", carol)] {
+            let actual = stats.player(name);
+            assert!(
+                actual == Some(&expected),
+                "Expected {} to have {:?}, was {:?}",
+                name,
+                expected,
+                actual
+            );
+        }
+        let carol = stats.player("Carol").unwrap();
+        assert!(
+            carol.vpip() == 100.0 && carol.three_bet() == 0.0 && carol.wsd() == 100.0,
+            "Expected Carol to have played every hand and won every showdown, was {}",
+            carol
+        );
+        assert!(
+            carol.aggression_factor().is_none(),
+            "Expected no aggression factor without calls, was {:?}",
+            carol.aggression_factor()
+        );
+    }
+
+    #[test]
+    fn test_three_bet() {
+        // player1 opens, player2 re-raises and player3 faces two raises.
+        let stats = scripted_stats(vec![
+            vec![Bet::Raise(60), Bet::Fold],
+            vec![Bet::Raise(180)],
+            vec![Bet::Fold],
+        ]);
+        let chances: Vec<(usize, usize)> = ["player1", "player2", "player3"]
+            .iter()
+            .map(|name| {
+                let p = stats.player(name).unwrap();
+                (p.three_bet_chances, p.three_bets)
+            })
+            .collect();
+        assert!(
+            chances == vec![(0, 0), (1, 1), (0, 0)],
+            "Expected only player2 to have a chance to 3-bet, and take it, was {:?}",
+            chances
+        );
+        let player2 = stats.player("player2").unwrap();
+        assert!(
+            player2.three_bet() == 100.0 && player2.pfr() == 100.0,
+            "Expected player2 to have 3-bet, was {}",
+            player2
+        );
+    }
+
+    #[test]
+    fn test_cbet() {
+        // player1 raises before the flop and bets it when checked to.
+        let stats = scripted_stats(vec![
+            vec![Bet::Raise(60), Bet::Raise(40)],
+            vec![Bet::Fold],
+            vec![Bet::Call, Bet::Check, Bet::Fold],
+        ]);
+        let player1 = stats.player("player1").unwrap();
+        assert!(
+            player1.cbet_chances == 1 && player1.cbets == 1,
+            "Expected player1 to c-bet, was {:?}",
+            player1
+        );
+        // player2 bets the flop before player1, the raiser, has the chance to.
+        let stats = scripted_stats(vec![
+            vec![Bet::Raise(60), Bet::Call, Bet::Check, Bet::Check],
+            vec![Bet::Call, Bet::Raise(60), Bet::Check, Bet::Check],
+            vec![Bet::Call, Bet::Fold],
+        ]);
+        let player1 = stats.player("player1").unwrap();
+        assert!(
+            player1.cbet_chances == 0 && player1.cbets == 0,
+            "Expected the donk bet to take away player1's chance to c-bet, was {:?}",
+            player1
+        );
+    }
+
+    #[test]
+    fn test_aggression_factor() {
+        // After the flop, player2 bets the flop and river and calls the turn, player1
+        // calls the flop and river and bets the turn, and player3 calls once.
+        let stats = scripted_stats(vec![
+            vec![Bet::Raise(60), Bet::Call, Bet::Raise(100), Bet::Call],
+            vec![
+                Bet::Call,
+                Bet::Raise(60),
+                Bet::Check,
+                Bet::Call,
+                Bet::Raise(100),
+            ],
+            vec![Bet::Call, Bet::Call, Bet::Check, Bet::Fold],
+        ]);
+        let factors: Vec<Option<f64>> = ["player1", "player2", "player3"]
+            .iter()
+            .map(|name| stats.player(name).unwrap().aggression_factor())
+            .collect();
+        assert!(
+            factors == vec![Some(0.5), Some(2.0), Some(0.0)],
+            "Expected aggression factors of 0.5, 2 and 0, was {:?}",
+            factors
+        );
+        let json = stats.to_json().unwrap();
+        let read = Stats::from_json(&json).unwrap();
+        assert!(
+            read == stats,
+            "Expected the statistics to survive JSON, was {:?}",
+            read
+        );
+    }
+}
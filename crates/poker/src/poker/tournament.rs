@@ -0,0 +1,565 @@
+/// Sit and go tournaments, which are played at a single table by a fixed number of
+/// players who all buy in for the same amount and receive the same starting stack.
+/// The blinds go up on a schedule until one player has all the chips, and the prize
+/// pool is shared out between the top finishers.
+use crate::poker::{
+    blinds::{BlindLevel, BlindSchedule, BlindScheduleError},
+    config::{ConfigError, GameConfig},
+    error::GameError,
+    events::{GameEvent, Subscriber},
+    game::Game,
+    player::Player,
+};
+use serde::{Deserialize, Serialize};
+use std::{
+    cell::{Cell, RefCell},
+    cmp::Reverse,
+    fmt::{self, Display},
+    rc::Rc,
+};
+
+/// Errors found when setting up a tournament.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub enum TournamentError {
+    /// The game configuration built for the tournament was invalid.
+    Config(ConfigError),
+    /// The blind schedule was invalid.
+    BlindSchedule(BlindScheduleError),
+    /// The payouts don't add up to 100 percent of the prize pool.
+    Payouts(Vec<u32>),
+    /// There are more places paid than players.
+    TooManyPlaces { places: usize, players: usize },
+    /// A player couldn't be seated at a table.
+    Join { player: String, error: GameError },
+}
+/// Implementation of Display trait for TournamentError.
+impl Display for TournamentError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            TournamentError::Config(e) => write!(f, "Invalid game configuration: {}", e),
+            TournamentError::BlindSchedule(e) => write!(f, "Invalid blind schedule: {}", e),
+            TournamentError::Payouts(payouts) => write!(
+                f,
+                "The payouts {:?} must add up to 100 percent of the prize pool",
+                payouts
+            ),
+            TournamentError::TooManyPlaces { places, players } => write!(
+                f,
+                "Can't pay {} places in a tournament of {} players",
+                places, players
+            ),
+            TournamentError::Join { player, error } => {
+                write!(f, "Couldn't seat {}: {}", player, error)
+            }
+        }
+    }
+}
+/// Implementation of Error trait for TournamentError.
+impl std::error::Error for TournamentError {}
+
+/// The payouts usually used for a tournament of the given number of players, as
+/// percentages of the prize pool for each place starting from first.
+pub fn standard_payouts(players: usize) -> Vec<u32> {
+    match players {
+        0..=3 => vec![100],
+        4..=6 => vec![65, 35],
+        _ => vec![50, 30, 20],
+    }
+}
+
+/// Share out a prize pool by the percentages for each place. Any chips left over from
+/// rounding down go to first place.
+pub fn prizes(prize_pool: usize, payouts: &[u32]) -> Vec<usize> {
+    let mut prizes: Vec<usize> = payouts
+        .iter()
+        .map(|pct| prize_pool * *pct as usize / 100)
+        .collect();
+    let paid: usize = prizes.iter().sum();
+    if let Some(first) = prizes.first_mut() {
+        *first += prize_pool - paid;
+    }
+    prizes
+}
+
+/// Check that payouts add up to 100 percent and don't pay more places than there are
+/// players.
+pub fn validate_payouts(payouts: &[u32], players: usize) -> Result<(), TournamentError> {
+    if payouts.iter().sum::<u32>() != 100 {
+        return Err(TournamentError::Payouts(payouts.to_vec()));
+    }
+    if payouts.len() > players {
+        return Err(TournamentError::TooManyPlaces {
+            places: payouts.len(),
+            players,
+        });
+    }
+    Ok(())
+}
+
+/// The payouts for a tournament of the given number of players, which are the
+/// `standard_payouts` if none are given.
+pub(crate) fn payouts_for(payouts: &[u32], players: usize) -> Result<Vec<u32>, TournamentError> {
+    let payouts = if payouts.is_empty() {
+        standard_payouts(players)
+    } else {
+        payouts.to_vec()
+    };
+    validate_payouts(&payouts, players)?;
+    Ok(payouts)
+}
+
+/// Set up a tournament table with the blinds following the schedule, reporting the
+/// players knocked out to the tracker.
+pub(crate) fn new_table(
+    blind_levels: &[BlindLevel],
+    starting_stack: usize,
+    max_players: u8,
+    seed: Option<u64>,
+    tracker: &EliminationTracker,
+) -> Result<Game, TournamentError> {
+    let schedule =
+        BlindSchedule::build(blind_levels.to_vec()).map_err(TournamentError::BlindSchedule)?;
+    let first = schedule.current_level();
+    let mut builder = GameConfig::builder()
+        .max_players(max_players)
+        .buy_in(starting_stack)
+        .small_blind(first.small_blind)
+        .big_blind(first.big_blind)
+        .ante(first.ante);
+    if let Some(seed) = seed {
+        builder = builder.seed(seed);
+    }
+    let mut game = Game::from_config(builder.build().map_err(TournamentError::Config)?)
+        .map_err(TournamentError::Config)?;
+    game.set_blind_schedule(schedule);
+    game.subscribe(tracker.clone());
+    Ok(game)
+}
+
+/// The settings for a sit and go.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SitAndGoConfig {
+    /// The amount each player pays to enter, which goes into the prize pool.
+    pub buy_in: usize,
+    /// The number of chips each player starts with.
+    pub starting_stack: usize,
+    /// The blind schedule, which must have at least one level.
+    pub blind_levels: Vec<BlindLevel>,
+    /// The percentage of the prize pool paid to each place, starting from first. If
+    /// empty, `standard_payouts` are used.
+    #[serde(default)]
+    pub payouts: Vec<u32>,
+    /// Seed for shuffling the deck.
+    #[serde(default)]
+    pub seed: Option<u64>,
+}
+
+/// A player's finishing place in a tournament.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Standing {
+    /// The place, starting from one for the winner.
+    pub place: usize,
+    pub player: String,
+    /// The hand the player was knocked out in, counting the hands started at every
+    /// table, or None if they weren't.
+    pub eliminated_in: Option<usize>,
+    pub prize: usize,
+}
+
+/// The final standings of a tournament.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Standings {
+    pub prize_pool: usize,
+    /// The players in finishing order, starting with the winner.
+    pub places: Vec<Standing>,
+}
+/// Implementation for Standings.
+impl Standings {
+    /// The winner of the tournament.
+    pub fn winner(&self) -> Option<&str> {
+        self.places.first().map(|s| s.player.as_str())
+    }
+
+    /// The named player's standing.
+    pub fn player(&self, name: &str) -> Option<&Standing> {
+        self.places.iter().find(|s| s.player == name)
+    }
+}
+/// Implementation of Display trait for Standings.
+impl Display for Standings {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        writeln!(f, "Prize pool: {}", self.prize_pool)?;
+        for s in &self.places {
+            write!(f, "{}. {}", s.place, s.player)?;
+            if s.prize > 0 {
+                write!(f, " ({})", s.prize)?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// A player knocked out of a tournament.
+#[derive(Debug, Clone, PartialEq)]
+struct Elimination {
+    player: String,
+    hand: usize,
+    /// The player's chips at the start of the hand they were knocked out in.
+    stack: usize,
+}
+
+/// A subscriber that keeps the order in which players are knocked out. The same
+/// tracker can be subscribed to several tables that play their hands in turn.
+#[derive(Debug, Clone, Default)]
+pub(crate) struct EliminationTracker {
+    /// The number of hands started.
+    hands: Rc<Cell<usize>>,
+    /// The players' stacks at the start of the hand being played.
+    stacks: Rc<RefCell<Vec<(String, usize)>>>,
+    eliminations: Rc<RefCell<Vec<Elimination>>>,
+}
+/// Implementation for EliminationTracker.
+impl EliminationTracker {
+    /// The players knocked out so far, starting with the last to go. Players knocked
+    /// out in the same hand are placed by the chips they started it with.
+    fn finishing_order(&self) -> Vec<Elimination> {
+        let mut eliminations = self.eliminations.borrow().clone();
+        eliminations.sort_by_key(|e| Reverse((e.hand, e.stack)));
+        eliminations
+    }
+
+    /// Rank the players still in the game by their chips, followed by the players
+    /// knocked out, and pay the prizes.
+    pub(crate) fn standings(
+        &self,
+        remaining: Vec<(String, usize)>,
+        prize_pool: usize,
+        payouts: &[u32],
+    ) -> Standings {
+        let mut remaining = remaining;
+        remaining.sort_by_key(|(_, chips)| Reverse(*chips));
+        let prizes = prizes(prize_pool, payouts);
+        let players = remaining
+            .into_iter()
+            .map(|(player, _)| (player, None))
+            .chain(
+                self.finishing_order()
+                    .into_iter()
+                    .map(|e| (e.player, Some(e.hand))),
+            );
+        let places = players
+            .enumerate()
+            .map(|(i, (player, eliminated_in))| Standing {
+                place: i + 1,
+                player,
+                eliminated_in,
+                prize: prizes.get(i).copied().unwrap_or(0),
+            })
+            .collect();
+        Standings { prize_pool, places }
+    }
+}
+/// Implementation of Subscriber for EliminationTracker.
+impl Subscriber for EliminationTracker {
+    fn notify(&self, event: &GameEvent) {
+        match event {
+            GameEvent::RoundStarted { players, .. } => {
+                self.hands.set(self.hands.get() + 1);
+                *self.stacks.borrow_mut() = players.clone();
+            }
+            GameEvent::PlayerEliminated { player } => {
+                let stack = self
+                    .stacks
+                    .borrow()
+                    .iter()
+                    .find(|(name, _)| name == player)
+                    .map_or(0, |(_, stack)| *stack);
+                self.eliminations.borrow_mut().push(Elimination {
+                    player: player.clone(),
+                    hand: self.hands.get(),
+                    stack,
+                });
+            }
+            _ => {}
+        }
+    }
+}
+
+/// A single table tournament.
+#[derive(Debug)]
+pub struct SitAndGo {
+    game: Game,
+    config: SitAndGoConfig,
+    payouts: Vec<u32>,
+    prize_pool: usize,
+    tracker: EliminationTracker,
+}
+/// Implementation for SitAndGo.
+impl SitAndGo {
+    /// Seat the players at a table of the right size, with the blinds starting at the
+    /// first level of the schedule. Returns an error if they can't all be seated.
+    pub fn build(config: SitAndGoConfig, players: Vec<Player>) -> Result<Self, TournamentError> {
+        let num_players = players.len();
+        let payouts = payouts_for(&config.payouts, num_players)?;
+        let tracker = EliminationTracker::default();
+        let mut game = new_table(
+            &config.blind_levels,
+            config.starting_stack,
+            u8::try_from(num_players).unwrap_or(u8::MAX),
+            config.seed,
+            &tracker,
+        )?;
+        for p in players {
+            let player = p.name.clone();
+            game.join(p)
+                .map_err(|error| TournamentError::Join { player, error })?;
+        }
+        Ok(SitAndGo {
+            game,
+            prize_pool: config.buy_in * num_players,
+            config,
+            payouts,
+            tracker,
+        })
+    }
+
+    /// The game being played, e.g. to add subscribers.
+    pub fn game(&mut self) -> &mut Game {
+        &mut self.game
+    }
+
+    pub fn config(&self) -> &SitAndGoConfig {
+        &self.config
+    }
+
+    /// The places paid, as percentages of the prize pool.
+    pub fn payouts(&self) -> &[u32] {
+        &self.payouts
+    }
+
+    /// Play the tournament until one player has all the chips, returning the final
+    /// standings. Errors are dealt with as in `Game::play`, so the tournament can be
+    /// carried on by calling `play` again.
+    pub fn play(&mut self) -> Result<Standings, GameError> {
+        self.game.play()?;
+        Ok(self.standings())
+    }
+
+    /// The standings so far, with the players still in ranked by their chips.
+    pub fn standings(&self) -> Standings {
+        self.tracker
+            .standings(self.game.bank_rolls(), self.prize_pool, &self.payouts)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::poker::{
+        autoactor::AutoActor,
+        betting_strategy::BetArgs,
+        blinds::LevelDuration,
+        card::Card,
+        events::EventLog,
+        game::Bet,
+        player::{Actor, Msg},
+    };
+
+    /// An actor that always calls, so that unlike with `AutoActor`s a tournament is
+    /// played out the same way every time for a seed.
+    #[derive(Debug)]
+    struct CallingActor;
+    impl Actor for CallingActor {
+        fn set_name_and_bank_roll(&self, _name: &str, _bank_roll: usize) {}
+        fn hole_cards(&self, _hole_cards: (Card, Card)) {}
+        fn place_bet(
+            &mut self,
+            args: BetArgs,
+            _hole_cards: (Card, Card),
+            _bank_roll: usize,
+        ) -> Option<Bet> {
+            Some(if args.call > 0 { Bet::Call } else { Bet::Check })
+        }
+        fn update(&self, _msg: &Msg) {}
+    }
+
+    fn config() -> SitAndGoConfig {
+        SitAndGoConfig {
+            buy_in: 10,
+            starting_stack: 1500,
+            blind_levels: vec![
+                BlindLevel {
+                    small_blind: 10,
+                    big_blind: 20,
+                    ante: 0,
+                    duration: LevelDuration::Hands(5),
+                },
+                BlindLevel {
+                    small_blind: 50,
+                    big_blind: 100,
+                    ante: 10,
+                    duration: LevelDuration::Hands(5),
+                },
+                BlindLevel {
+                    small_blind: 200,
+                    big_blind: 400,
+                    ante: 50,
+                    duration: LevelDuration::Hands(5),
+                },
+            ],
+            payouts: vec![],
+            seed: Some(3),
+        }
+    }
+
+    fn players(n: usize) -> Vec<Player> {
+        (1..=n)
+            .map(|i| Player::build(&format!("player{}", i), AutoActor::new()))
+            .collect()
+    }
+
+    fn calling_players(n: usize) -> Vec<Player> {
+        (1..=n)
+            .map(|i| Player::build(&format!("player{}", i), CallingActor))
+            .collect()
+    }
+
+    #[test]
+    fn test_prizes() {
+        let p = prizes(1000, &[50, 30, 20]);
+        assert!(p == vec![500, 300, 200], "Unexpected prizes {:?}", p);
+        let p = prizes(101, &[65, 35]);
+        assert!(
+            p == vec![66, 35] && p.iter().sum::<usize>() == 101,
+            "Expected the odd chip to go to first place, was {:?}",
+            p
+        );
+    }
+
+    #[test]
+    fn test_validation() {
+        let result = SitAndGo::build(
+            SitAndGoConfig {
+                payouts: vec![50, 30],
+                ..config()
+            },
+            players(4),
+        );
+        assert!(
+            matches!(result, Err(TournamentError::Payouts(_))),
+            "Expected payouts that don't add up to be rejected, was {:?}",
+            result
+        );
+        let result = SitAndGo::build(
+            SitAndGoConfig {
+                payouts: vec![50, 30, 20],
+                ..config()
+            },
+            players(2),
+        );
+        assert!(
+            matches!(
+                result,
+                Err(TournamentError::TooManyPlaces {
+                    places: 3,
+                    players: 2
+                })
+            ),
+            "Expected too many places to be rejected, was {:?}",
+            result
+        );
+        let result = SitAndGo::build(
+            SitAndGoConfig {
+                blind_levels: vec![],
+                ..config()
+            },
+            players(2),
+        );
+        assert!(
+            matches!(
+                result,
+                Err(TournamentError::BlindSchedule(BlindScheduleError::NoLevels))
+            ),
+            "Expected an empty blind schedule to be rejected, was {:?}",
+            result
+        );
+        // More players than fit at a table can't all be seated.
+        let result = SitAndGo::build(config(), players(266));
+        assert!(
+            matches!(
+                result,
+                Err(TournamentError::Config(ConfigError::TableSize(_)))
+            ),
+            "Expected too many players to be rejected, was {:?}",
+            result
+        );
+    }
+
+    #[test]
+    fn test_sit_and_go() {
+        let log = EventLog::new();
+        let mut sng = SitAndGo::build(config(), calling_players(6)).unwrap();
+        sng.game().subscribe(log.clone());
+        let standings = sng.play().unwrap();
+        assert!(
+            standings.prize_pool == 60 && standings.places.len() == 6,
+            "Expected six places and a prize pool of 60, was {:?}",
+            standings
+        );
+        let prizes: Vec<usize> = standings.places.iter().map(|s| s.prize).collect();
+        assert!(
+            prizes == vec![39, 21, 0, 0, 0, 0],
+            "Expected the standard payouts for six players, was {:?}",
+            prizes
+        );
+        let winner = standings.winner().unwrap();
+        assert!(
+            standings.places[0].eliminated_in.is_none()
+                && sng.game().bank_rolls() == vec![(winner.to_string(), 9000)],
+            "Expected the winner to have every chip, was {:?}",
+            sng.game().bank_rolls()
+        );
+        // The last player knocked out comes second, and so on.
+        let knocked_out: Vec<String> = log
+            .events()
+            .into_iter()
+            .filter_map(|e| match e {
+                GameEvent::PlayerEliminated { player } => Some(player),
+                _ => None,
+            })
+            .collect();
+        let hands: Vec<usize> = standings.places[1..]
+            .iter()
+            .map(|s| s.eliminated_in.unwrap())
+            .collect();
+        assert!(
+            knocked_out.len() == 5
+                && knocked_out.iter().all(|p| standings.player(p).is_some())
+                && hands.windows(2).all(|w| w[0] >= w[1]),
+            "Expected the players to be placed in the order they were knocked out, was {:?}",
+            standings
+        );
+        // With the seed the players always finish in the same order. player1 and
+        // player4 are knocked out in the same hand, and are placed by the chips they
+        // started it with.
+        let places: Vec<(&str, Option<usize>)> = standings
+            .places
+            .iter()
+            .map(|s| (s.player.as_str(), s.eliminated_in))
+            .collect();
+        assert!(
+            places
+                == vec![
+                    ("player2", None),
+                    ("player5", Some(135)),
+                    ("player3", Some(16)),
+                    ("player4", Some(14)),
+                    ("player1", Some(14)),
+                    ("player6", Some(12)),
+                ],
+            "Expected the finishing order for the seed, was {:?}",
+            places
+        );
+    }
+}
@@ -3,7 +3,7 @@ use poker::poker::{
     card::Card,
     compare::best_hand,
     game::Bet,
-    player::{Actor, Msg},
+    player::{Actor, Msg, PlayerHand},
 };
 
 /// The struct that represents a CLI player.
@@ -36,30 +36,71 @@ impl Actor for CLIPlayer {
             println!("Community cards:",);
             args.community_cards.iter().for_each(|c| println!("{}", c));
         }
-        println!(
-            "The bet stands at {} (minimum amount to bet {})",
-            args.call, args.min
-        );
+        match args.max {
+            Some(0) => println!("The bet stands at {} and can't be raised", args.call),
+            Some(max) => println!(
+                "The bet stands at {} (raise by {} to {})",
+                args.call, args.min, max
+            ),
+            None => println!(
+                "The bet stands at {} (minimum amount to bet {})",
+                args.call, args.min
+            ),
+        }
         println!("Bank roll: {}. Best hand: {}", bank_roll, bh);
-        println!("Enter R(aise) <amount>, C(all), Ch(eck), A(ll in), F(old)");
-        let mut input = String::new(); // A mutable String to hold the user input
-        std::io::stdin()
-            .read_line(&mut input) // Read input into the `input` variable
-            .expect("Failed to read line");
+        loop {
+            println!("Enter R(aise) <amount>, C(all), Ch(eck), A(ll in), F(old)");
+            let mut input = String::new(); // A mutable String to hold the user input
+            std::io::stdin()
+                .read_line(&mut input) // Read input into the `input` variable
+                .expect("Failed to read line");
 
-        if let Some(bet) = parse_bet_string(input, bank_roll) {
-            match bet {
-                Bet::Fold => Some(Bet::Fold),
-                Bet::Check => Some(Bet::Check),
-                Bet::Call => Some(Bet::Call),
-                Bet::Raise(n) => Some(Bet::Raise(n)),
-                Bet::AllIn(n) => Some(Bet::AllIn(n)),
+            match parse_bet_string(input, bank_roll) {
+                Some(Bet::Check) if args.call > 0 => {
+                    println!("You can't check, there is a bet of {} to call.", args.call);
+                }
+                Some(Bet::Raise(n))
+                    if n > args.call
+                        && n < bank_roll
+                        && (n - args.call < args.min
+                            || args.max.is_some_and(|max| n - args.call > max)) =>
+                {
+                    println!("You can't raise by {}.", n - args.call);
+                }
+                Some(bet) => return Some(bet),
+                None => println!("Sorry, that isn't a valid bet."),
             }
-        } else {
-            None
         }
     }
 
+    /// Ask whether to rebuy after going broke.
+    fn rebuy(&mut self, amount: usize) -> bool {
+        ask_yes_no(&format!("You're out of chips. Rebuy for {} chips?", amount))
+    }
+
+    /// Ask whether to buy the add-on.
+    fn add_on(&mut self, amount: usize) -> bool {
+        ask_yes_no(&format!("Take the add-on of {} chips?", amount))
+    }
+
+    /// Ask whether to deal the rest of the board twice.
+    fn run_it_twice(&mut self) -> bool {
+        ask_yes_no("Everyone is all in. Run it twice?")
+    }
+
+    /// Ask whether to straddle before the cards are dealt.
+    fn straddle(&mut self, amount: usize) -> bool {
+        ask_yes_no(&format!("Straddle for {} chips?", amount))
+    }
+
+    /// Ask whether to show a beaten hand at the showdown.
+    fn show_hand(&mut self, beaten_by: &PlayerHand) -> bool {
+        ask_yes_no(&format!(
+            "{} shows {}. Show your hand?",
+            beaten_by.name, beaten_by.hand
+        ))
+    }
+
     fn update(&self, msg: &Msg) {
         match msg {
             Msg::Player { name, bank_roll } => {
@@ -93,12 +134,50 @@ impl Actor for CLIPlayer {
             Msg::RoundWinner(w) => {
                 println!("##############\n## {}.\n##############", w,);
             }
+            Msg::RoundWonUncontested { .. } | Msg::SecondBoardWinner(_) => {
+                println!("##############\n## {}.\n##############", msg);
+            }
             Msg::StageDeclare(stage, _community_cards) => {
                 println!(
                     "##############\n## The {} stage is beginning.\n##############",
                     stage,
                 );
             }
+            Msg::BlindLevel { .. }
+            | Msg::Straddle { .. }
+            | Msg::BombPot { .. }
+            | Msg::PlayerJoined { .. }
+            | Msg::PlayerLeft { .. }
+            | Msg::SatOut { .. }
+            | Msg::SatIn { .. }
+            | Msg::Rebuy { .. }
+            | Msg::AddOn { .. }
+            | Msg::UncalledBet { .. }
+            | Msg::RabbitHunt(_)
+            | Msg::HandsRevealed(_)
+            | Msg::HandShown(_)
+            | Msg::HandMucked { .. }
+            | Msg::SecondBoard(_)
+            | Msg::ShuffleCommitment { .. }
+            | Msg::ShuffleRevealed(_) => {
+                println!("{}", msg);
+            }
+        }
+    }
+}
+
+/// Ask a question until the answer is Y(es) or N(o).
+fn ask_yes_no(question: &str) -> bool {
+    loop {
+        println!("{} Enter Y(es) or N(o)", question);
+        let mut input = String::new();
+        std::io::stdin()
+            .read_line(&mut input)
+            .expect("Failed to read line");
+        match input.trim() {
+            "Y" | "y" => return true,
+            "N" | "n" => return false,
+            _ => println!("Sorry, that isn't a valid answer."),
         }
     }
 }
@@ -108,8 +187,8 @@ fn parse_bet_string(input: String, all_in_amount: usize) -> Option<Bet> {
     if parts.len() == 2 {
         let amount: usize = parts[1]
             .trim() // Remove whitespace
-            .parse() // Convert to i32
-            .expect("Please enter a valid number");
+            .parse() // Convert to usize
+            .ok()?;
         Some(Bet::Raise(amount))
     } else {
         match parts[0] {
@@ -4,10 +4,13 @@ use cli::player::CLIPlayer;
 use poker::poker::{
     autoactor::AutoActor,
     betting_strategy::{modest_betting_strategy, six_max},
+    config::GameConfig,
     game::Game,
     new_game_with_players,
     player::Player,
+    snapshot::GameSnapshot,
 };
+use std::{env, fs};
 
 fn main() {
     let players = vec![
@@ -16,9 +19,49 @@ fn main() {
         Player::build("Dileas", AutoActor::build(modest_betting_strategy)),
         Player::build("Evie", AutoActor::build(six_max)),
     ];
-    let mut g: Game = new_game_with_players(players, 100);
+    // The game is saved after every round to the file given as an argument, if any,
+    // and carried on from there the next time.
+    let save_file = env::args().nth(1);
+    let saved = save_file.as_ref().and_then(|f| fs::read_to_string(f).ok());
+    let mut g: Game = match saved {
+        Some(json) => GameSnapshot::from_json(&json)
+            .and_then(|snapshot| Game::restore(snapshot, players))
+            .expect("Couldn't restore the saved game"),
+        None => {
+            let config = GameConfig::builder()
+                .big_blind(100)
+                .max_players(players.len() as u8)
+                .build()
+                .expect("Invalid game config");
+            new_game_with_players(players, config).expect("Couldn't create game")
+        }
+    };
 
-    let winner = g.play();
-    println!("{}", winner);
-    println!("{:?}", winner);
+    loop {
+        match g.play_round() {
+            Ok(Some(winner)) => {
+                println!("{}", winner);
+                println!("{:?}", winner);
+                if let Some(f) = &save_file {
+                    let _ = fs::remove_file(f);
+                }
+                break;
+            }
+            Ok(None) if g.waiting_for_players() => {
+                eprintln!("The game ended early: not enough players are dealt in");
+                break;
+            }
+            Ok(None) => {
+                if let Some(f) = &save_file
+                    && let Err(e) = fs::write(f, g.snapshot().to_json())
+                {
+                    eprintln!("Couldn't save the game: {}", e);
+                }
+            }
+            Err(e) => {
+                eprintln!("The game ended early: {}", e);
+                break;
+            }
+        }
+    }
 }
@@ -3,7 +3,7 @@ use crate::server::game::game_handler;
 use axum::{
     Router,
     extract::{
-        ConnectInfo,
+        ConnectInfo, State,
         ws::{Message, WebSocket, WebSocketUpgrade},
     },
     response::IntoResponse,
@@ -29,13 +29,14 @@ pub enum GameRequest {
 /// Extractor for establishing WebSocket connections.
 async fn websocket_handler(
     ws: WebSocketUpgrade,
+    State(settings): State<Settings>,
     ConnectInfo(remote_addr): ConnectInfo<SocketAddr>,
 ) -> impl IntoResponse {
-    ws.on_upgrade(move |socket| handle_socket(socket, remote_addr))
+    ws.on_upgrade(move |socket| handle_socket(socket, remote_addr, settings))
 }
 
 /// A stream of WebSocket messages.
-async fn handle_socket(mut socket: WebSocket, remote_addr: SocketAddr) {
+async fn handle_socket(mut socket: WebSocket, remote_addr: SocketAddr, settings: Settings) {
     // Returns `None` if the stream has closed.
     if let Some(msg) = socket.recv().await {
         if let Ok(msg) = msg {
@@ -47,7 +48,7 @@ async fn handle_socket(mut socket: WebSocket, remote_addr: SocketAddr) {
                 let runtime_handle = tokio::runtime::Handle::current();
                 match game_request {
                     GameRequest::NewGame { name } => {
-                        game_handler(name, socket, runtime_handle).await;
+                        game_handler(name, socket, runtime_handle, settings).await;
                     }
                     GameRequest::JoinGame { .. } => {}
                     GameRequest::HandShake(..) => {}
@@ -67,8 +68,10 @@ async fn main() -> anyhow::Result<()> {
     // Load config.
     Ok(match Settings::load(args) {
         Ok(settings) => {
-            let app = Router::new().route("/", get(websocket_handler));
-            let address = settings.server.host + ":" + &settings.server.port.to_string();
+            let address = settings.server.host.clone() + ":" + &settings.server.port.to_string();
+            let app = Router::new()
+                .route("/", get(websocket_handler))
+                .with_state(settings);
             info!("Starting server at address: {}", address);
             let listener = tokio::net::TcpListener::bind(address).await.unwrap();
 
@@ -1,25 +1,17 @@
-use crate::server::safe_deserialise;
+use crate::server::{safe_deserialise, send_close_message};
 use axum::extract::ws::{Message, Utf8Bytes, WebSocket};
 use log::error;
 use poker::poker::{
     betting_strategy::BetArgs,
     card::{BestHand, Card},
     compare::best_hand,
+    fair::ShuffleSeeds,
     game::{Bet, Stage},
-    player::{Actor, Msg, Winner},
+    player::{Actor, Msg, PlayerHand, Winner},
 };
 use serde::{Deserialize, Serialize};
-use std::sync::mpsc as std_mpsc;
-use tokio::{
-    runtime::Handle,
-    sync::{mpsc, oneshot},
-};
-
-// --- CONSTANTS ---
-// We use mpsc for Server Updates -> WebSocket
-const CHANNEL_CAPACITY: usize = 32;
-// This type bundles the synchronous input data with the asynchronous reply channel.
-type BetRequest = (BetArgs, (Card, Card), usize, oneshot::Sender<Option<Bet>>);
+use std::time::Duration;
+use tokio::{sync::mpsc, time};
 
 /// Enum for messages within a game.
 #[derive(Debug, Serialize, Deserialize)]
@@ -45,15 +37,83 @@ pub enum PokerMessage {
         dealer: String,
     },
     GameWinner {
-        winner: Winner,
+        winner: String,
     },
     RoundWinner {
         winner: Winner,
     },
+    HandShown {
+        hand: PlayerHand,
+    },
+    HandMucked {
+        player: String,
+    },
+    UncalledBet {
+        player: String,
+        amount: usize,
+    },
+    RoundWonUncontested {
+        player: String,
+        pot: usize,
+    },
+    RabbitHunt {
+        cards: Vec<Card>,
+    },
+    HandsRevealed {
+        hands: Vec<(String, (Card, Card))>,
+    },
+    SecondBoard {
+        cards: Vec<Card>,
+    },
+    SecondBoardWinner {
+        winner: Winner,
+    },
     StageDecl {
         stage: Stage,
         community_cards: Vec<Card>,
     },
+    Straddle {
+        player: String,
+        amount: usize,
+    },
+    BombPot {
+        ante: usize,
+    },
+    BlindLevel {
+        level: usize,
+        small_blind: usize,
+        big_blind: usize,
+        ante: usize,
+    },
+    PlayerJoined {
+        player: String,
+        bank_roll: usize,
+    },
+    PlayerLeft {
+        player: String,
+        bank_roll: usize,
+    },
+    SatOut {
+        player: String,
+    },
+    SatIn {
+        player: String,
+    },
+    Rebuy {
+        player: String,
+        amount: usize,
+    },
+    AddOn {
+        player: String,
+        amount: usize,
+    },
+    ShuffleCommitment {
+        hand: usize,
+        commitment: String,
+    },
+    ShuffleRevealed {
+        seeds: ShuffleSeeds,
+    },
     PlaceBet {
         args: BetArgs,
         hole_cards: (Card, Card),
@@ -62,144 +122,142 @@ pub enum PokerMessage {
     },
     Error(String),
 }
-/// The thread-safe, cloneable structure used by the synchronous facade
-/// to push messages into the asynchronous WebSocket task.
-#[derive(Debug, Clone)]
-struct RemoteActorHandle {
-    // Channel sender for sending updates (Msg) to the WebSocket loop.
-    update_tx: mpsc::Sender<PokerMessage>,
-    // Channel sender for requesting a bet (place_bet) and receiving the result
-    // The player thread sends a request to the loop, and the loop replies here.
-    bet_tx: mpsc::Sender<BetRequest>,
+/// The connection to a remote player's client. The game loop in `game_handler` passes
+/// the game's messages on to the socket with `flush` and asks the client for the
+/// player's bets with `request_bet`.
+#[derive(Debug)]
+pub struct RemoteClient {
+    socket: WebSocket,
+    // Messages from the game waiting to be sent to the client.
+    update_rx: mpsc::UnboundedReceiver<PokerMessage>,
+    // How long the client has to place a bet.
+    time_limit: Option<Duration>,
+    // The player's hole cards in the current round, sent with each bet request.
+    hole_cards: Option<(Card, Card)>,
 }
-/// The actual asynchronous loop that manages the single, non-cloneable WebSocket.
-async fn start_socket_loop(
-    mut socket: WebSocket,
-    mut update_rx: mpsc::Receiver<PokerMessage>,
-    mut bet_rx: mpsc::Receiver<BetRequest>,
-) {
-    // Run the loop until the socket closes or an error occurs.
-    loop {
-        tokio::select! {
-            // Handle incoming messages from the synchronous game loop (Updates and Bet Requests)
-            // This is how the game engine tells the player to do something.
-            Some((args, hole_cards, bank_roll, bet_responder)) = bet_rx.recv() => {
-                // When the game engine calls place_bet, it sends a oneshot channel here.
-
-                // Construct the bet request message.
-                let mut cards = args.community_cards.clone();
-                let (h1, h2) = (hole_cards.0, hole_cards.1);
-                cards.push(h1);
-                cards.push(h2);
-                let bh = best_hand(&cards);
-                let bet_msg = PokerMessage::PlaceBet {
-                    args,
-                    hole_cards,
-                    bank_roll,
-                    best_hand: bh,
-                };
-
-                // Send the request to the client.
-                let send_res = socket
-                    .send(Message::Text(Utf8Bytes::from(
-                        serde_json::to_string(&bet_msg).unwrap(),
-                    )))
-                    .await;
-
-                if send_res.is_err() {
-                    let _ = bet_responder.send(None);
-                    return; // Exit loop on send error.
-                }
+impl RemoteClient {
+    /// Builds a client for the socket, and the actor that passes the game's messages
+    /// to it. If there is a time limit, the player checks or folds when they take too
+    /// long to bet.
+    pub fn build(socket: WebSocket, time_limit: Option<Duration>) -> (RemoteClient, RemoteActor) {
+        let (update_tx, update_rx) = mpsc::unbounded_channel();
+        let client = RemoteClient {
+            socket,
+            update_rx,
+            time_limit,
+            hole_cards: None,
+        };
+        (client, RemoteActor { update_tx })
+    }
 
-                // Wait for the client's response (this is the actual blocking network IO).
-                if let Some(msg) = socket.recv().await {
-                    match msg {
-                        Ok(Message::Text(utf8_bytes)) => {
-                let bet = safe_deserialise::<PokerMessage>(&utf8_bytes);
-                            // Extract the bet action.
-                            let final_bet = match bet {
-                                Some(PokerMessage::PlayerBet(b)) => Some(b),
-                                _ => {
-                                    error!("Expected PlayerBet, got something else: {:?}", bet);
-                                    None
-                                }
-                            };
-                            let _ = bet_responder.send(final_bet);
-                        }
-                        _ => {
-                            eprintln!("Received non-text message or error during recv.");
-                            let _ = bet_responder.send(None);
-                        }
-                    }
-                } else {
-                    // Recv returned None, meaning socket closed.
-                    let _ = bet_responder.send(None);
-                    return;
-                }
+    /// Send a message to the client. Returns false if the socket is closed.
+    pub async fn send(&mut self, msg: &PokerMessage) -> bool {
+        let text = match serde_json::to_string(msg) {
+            Ok(text) => text,
+            Err(e) => {
+                error!("Couldn't serialise {:?}: {}", msg, e);
+                return true;
             }
+        };
+        self.socket
+            .send(Message::Text(Utf8Bytes::from(text)))
+            .await
+            .is_ok()
+    }
 
-            // Handle incoming updates from the synchronous game loop.
-            Some(msg) = update_rx.recv() => {
-                // General updates are sent to the client (fire-and-forget).
-                let send_res = socket
-                    .send(Message::Text(Utf8Bytes::from(
-                        serde_json::to_string(&msg).unwrap(),
-                    )))
-                    .await;
-
-                if send_res.is_err() {
-                    return; // Exit loop on send error.
-                }
+    /// Send the messages from the game that haven't been sent yet. Returns false if the
+    /// socket is closed.
+    pub async fn flush(&mut self) -> bool {
+        while let Ok(msg) = self.update_rx.try_recv() {
+            if let PokerMessage::HoleCards { cards } = msg {
+                self.hole_cards = Some(cards);
             }
+            if !self.send(&msg).await {
+                return false;
+            }
+        }
+        true
+    }
 
-            // Handle messages spontaneously sent from the client.
-            Some(result) = socket.recv() => {
-                match result {
-                    Ok(Message::Text(_utf8_bytes)) => {
-                        // Handle unsolicited messages here (e.g., chat or keep-alive).
-                        // Do nothing, since we only care about bet responses during place_bet.
-                    }
-                    Ok(Message::Close(_)) => {
-                        error!("WebSocket closed by client.");
-                        return;
+    /// Ask the client for a bet and wait for the reply. Returns None if the client
+    /// disconnected. Messages other than a bet are answered with an error, and the
+    /// client is asked again.
+    pub async fn request_bet(&mut self, args: BetArgs, bank_roll: usize) -> Option<Bet> {
+        let Some(hole_cards) = self.hole_cards else {
+            error!("Asked for a bet before the hole cards were dealt.");
+            return None;
+        };
+        let call = args.call;
+        let mut cards = args.community_cards.clone();
+        cards.push(hole_cards.0);
+        cards.push(hole_cards.1);
+        let bet_msg = PokerMessage::PlaceBet {
+            args,
+            hole_cards,
+            bank_roll,
+            best_hand: best_hand(&cards),
+        };
+        if !self.send(&bet_msg).await {
+            return None;
+        }
+        let deadline = self.time_limit.map(|limit| time::Instant::now() + limit);
+        loop {
+            let response = match deadline {
+                Some(deadline) => match time::timeout_at(deadline, self.socket.recv()).await {
+                    Ok(response) => response,
+                    Err(_) => {
+                        // Out of time: check if possible, otherwise fold.
+                        let bet = if call == 0 { Bet::Check } else { Bet::Fold };
+                        let timeout_msg = PokerMessage::Error(format!(
+                            "Ran out of time to place a bet, placed {} instead.",
+                            bet
+                        ));
+                        self.send(&timeout_msg).await;
+                        return Some(bet);
                     }
-                    Err(e) => {
-                        error!("WebSocket error: {}", e);
-                        return;
+                },
+                None => self.socket.recv().await,
+            };
+            match response {
+                Some(Ok(Message::Text(utf8_bytes))) => {
+                    match safe_deserialise::<PokerMessage>(&utf8_bytes) {
+                        Some(PokerMessage::PlayerBet(bet)) => return Some(bet),
+                        other => {
+                            error!("Expected PlayerBet, got something else: {:?}", other);
+                            let msg = PokerMessage::Error("Expected a PlayerBet.".to_string());
+                            if !self.send(&msg).await {
+                                return None;
+                            }
+                        }
                     }
-                    _ => {} // Ignore Binary, Ping, Pong, etc.
                 }
+                Some(Ok(Message::Close(_))) | None => {
+                    error!("WebSocket closed by client.");
+                    return None;
+                }
+                Some(Err(e)) => {
+                    error!("WebSocket error: {}", e);
+                    return None;
+                }
+                Some(Ok(_)) => {} // Ignore Binary, Ping, Pong, etc.
             }
+        }
+    }
 
-            // Exit if all senders are dropped.
-            else => {
-                break;
-            }
+    /// Send any remaining messages and close the connection.
+    pub async fn close(mut self) {
+        if self.flush().await {
+            send_close_message(self.socket, 1000, "Game over").await;
         }
     }
 }
 
-/// The Synchronous Facade struct that implements the Actor trait.
+/// The actor for a remote player, which passes the game's messages on to their
+/// `RemoteClient`. The player's bets are asked for by the game loop, which drives the
+/// game with `Game::advance`, so the actor is never asked for a bet itself.
 #[derive(Debug)]
 pub struct RemoteActor {
-    runtime_handle: Handle,
-    handle: RemoteActorHandle,
-}
-impl RemoteActor {
-    /// Builds a new RemoteActor, starts the asynchronous WebSocket loop, and returns the facade.
-    pub fn build(socket: WebSocket, runtime_handle: Handle) -> RemoteActor {
-        // Create channels for communication between the facade and the async loop.
-        let (update_tx, update_rx) = mpsc::channel(CHANNEL_CAPACITY);
-        let (bet_tx, bet_rx) = mpsc::channel(1); // Only need capacity 1 for blocking bets
-
-        // Start the continuous asynchronous task that owns the WebSocket.
-        runtime_handle.spawn(start_socket_loop(socket, update_rx, bet_rx));
-
-        RemoteActor {
-            runtime_handle,
-            handle: RemoteActorHandle { update_tx, bet_tx },
-        }
-    }
+    update_tx: mpsc::UnboundedSender<PokerMessage>,
 }
 /// Implementation of Actor for RemoteActor.
 impl Actor for RemoteActor {
@@ -217,57 +275,19 @@ impl Actor for RemoteActor {
         let hole_card_msg = Msg::HoleCards { cards: hole_cards };
         self.update(&hole_card_msg);
     }
-    /// Place a bet (Synchronous, Blocking).
+
+    /// Bets are read from the socket by the game loop, so there is no bet to place here.
     fn place_bet(
         &mut self,
-        args: BetArgs,
-        hole_cards: (Card, Card),
-        bank_roll: usize,
+        _args: BetArgs,
+        _hole_cards: (Card, Card),
+        _bank_roll: usize,
     ) -> Option<Bet> {
-        // Blocking MPSC channel for the final result.
-        let (sync_tx, sync_rx) = std_mpsc::channel();
-
-        // Clone necessary parts to move into the blocking thread
-        let tx_clone = self.handle.bet_tx.clone();
-        let runtime_handle_clone = self.runtime_handle.clone();
-
-        // Spawn the entire request/response sequence onto a dedicated blocking thread.
-        runtime_handle_clone.spawn_blocking(move || {
-            // Tokio oneshot channel for receiving from the socket loop
-            let (result_tx, result_rx) = oneshot::channel();
-            let request: BetRequest = (args, hole_cards, bank_roll, result_tx);
-
-            // Send the request to the async loop.
-            if tx_clone.blocking_send(request).is_err() {
-                error!("Failed to send bet request to socket loop from blocking thread.");
-                let _ = sync_tx.send(None); // Send None back to the caller
-                return;
-            }
-
-            // Block the current dedicated thread until the async loop replies.
-            let result = match result_rx.blocking_recv() {
-                Ok(bet) => bet,
-                Err(e) => {
-                    error!("Oneshot receiver error on blocking thread: {}", e);
-                    None
-                }
-            };
-
-            // Send the final result back to the original calling thread via std::mpsc.
-            let _ = sync_tx.send(result);
-        });
-
-        // The thread calling `place_bet` blocks on the standard library receiver.
-        match sync_rx.recv() {
-            Ok(bet) => bet, // The Option<Bet> result
-            Err(e) => {
-                error!("Standard MPSC channel error waiting for result: {}", e);
-                None
-            }
-        }
+        error!("RemoteActor was asked for a bet outside the game loop.");
+        None
     }
 
-    /// Update (Synchronous, Non-Blocking).
+    /// Queue the message to be sent to the client.
     fn update(&self, msg: &Msg) {
         // Convert (synchronous) Msg into (asynchronous) PokerMessage.
         let poker_msg = match msg {
@@ -291,16 +311,82 @@ impl Actor for RemoteActor {
             Msg::RoundWinner(winner) => PokerMessage::RoundWinner {
                 winner: winner.clone(),
             },
+            Msg::HandShown(hand) => PokerMessage::HandShown { hand: hand.clone() },
+            Msg::HandMucked { player } => PokerMessage::HandMucked {
+                player: player.clone(),
+            },
+            Msg::UncalledBet { player, amount } => PokerMessage::UncalledBet {
+                player: player.clone(),
+                amount: *amount,
+            },
+            Msg::RoundWonUncontested { player, pot } => PokerMessage::RoundWonUncontested {
+                player: player.clone(),
+                pot: *pot,
+            },
+            Msg::RabbitHunt(cards) => PokerMessage::RabbitHunt {
+                cards: cards.clone(),
+            },
+            Msg::HandsRevealed(hands) => PokerMessage::HandsRevealed {
+                hands: hands.clone(),
+            },
+            Msg::SecondBoard(cards) => PokerMessage::SecondBoard {
+                cards: cards.clone(),
+            },
+            Msg::SecondBoardWinner(winner) => PokerMessage::SecondBoardWinner {
+                winner: winner.clone(),
+            },
             Msg::StageDeclare(stage, community_cards) => PokerMessage::StageDecl {
                 stage: *stage,
                 community_cards: community_cards.clone(),
             },
+            Msg::Straddle { player, amount } => PokerMessage::Straddle {
+                player: player.clone(),
+                amount: *amount,
+            },
+            Msg::BombPot { ante } => PokerMessage::BombPot { ante: *ante },
+            Msg::BlindLevel {
+                level,
+                small_blind,
+                big_blind,
+                ante,
+            } => PokerMessage::BlindLevel {
+                level: *level,
+                small_blind: *small_blind,
+                big_blind: *big_blind,
+                ante: *ante,
+            },
+            Msg::PlayerJoined { player, bank_roll } => PokerMessage::PlayerJoined {
+                player: player.clone(),
+                bank_roll: *bank_roll,
+            },
+            Msg::PlayerLeft { player, bank_roll } => PokerMessage::PlayerLeft {
+                player: player.clone(),
+                bank_roll: *bank_roll,
+            },
+            Msg::SatOut { player } => PokerMessage::SatOut {
+                player: player.clone(),
+            },
+            Msg::SatIn { player } => PokerMessage::SatIn {
+                player: player.clone(),
+            },
+            Msg::Rebuy { player, amount } => PokerMessage::Rebuy {
+                player: player.clone(),
+                amount: *amount,
+            },
+            Msg::AddOn { player, amount } => PokerMessage::AddOn {
+                player: player.clone(),
+                amount: *amount,
+            },
+            Msg::ShuffleCommitment { hand, commitment } => PokerMessage::ShuffleCommitment {
+                hand: *hand,
+                commitment: commitment.clone(),
+            },
+            Msg::ShuffleRevealed(seeds) => PokerMessage::ShuffleRevealed {
+                seeds: seeds.clone(),
+            },
         };
-        let tx = self.handle.update_tx.clone();
-        self.runtime_handle.spawn(async move {
-            if let Err(e) = tx.send(poker_msg).await {
-                error!("Failed to send update message: {}", e);
-            }
-        });
+        if let Err(e) = self.update_tx.send(poker_msg) {
+            error!("Failed to send update message: {}", e);
+        }
     }
 }
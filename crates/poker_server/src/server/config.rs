@@ -2,6 +2,7 @@ use config::{Config, ConfigError, Environment, File};
 use getopts::{Matches, Options};
 use log::{info, warn};
 use log4rs;
+use poker::poker::blinds::BlindLevel;
 use serde::Deserialize;
 use std::{ffi::OsString, fs};
 
@@ -17,6 +18,18 @@ pub struct Settings {
 
     // Nested struct for the server settings
     pub server: ServerSettings,
+
+    // Blind schedule for new games. If empty, the blinds stay fixed.
+    #[serde(default)]
+    pub blind_levels: Vec<BlindLevel>,
+
+    // The number of seconds remote players have to place a bet.
+    #[serde(default)]
+    pub time_limit: Option<u64>,
+
+    // Whether new games shuffle each hand in a way that players can verify.
+    #[serde(default)]
+    pub provably_fair: bool,
 }
 /// Struct for the server config.
 #[derive(Debug, Deserialize, Clone)]
@@ -92,6 +105,7 @@ impl Settings {
         }
         info!("Config path: {}", config_path);
         info!("Log path: {}", log_path_clone3);
+        info!("Blind levels: {}", settings.blind_levels.len());
         Ok(settings)
     }
 }
@@ -1,11 +1,114 @@
-use crate::server::actor::RemoteActor;
+use crate::server::{
+    actor::{PokerMessage, RemoteClient},
+    config::Settings,
+};
 use axum::extract::ws::WebSocket;
-use poker::{player::Player, poker};
-use tokio::runtime::Handle;
+use log::error;
+use poker::{
+    blinds::BlindSchedule,
+    config::GameConfig,
+    error::GameError,
+    game::{Game, PendingDecision},
+    player::Player,
+    poker,
+};
+use std::time::Duration;
+use tokio::{runtime::Handle, task};
 
-pub async fn game_handler(player_name: String, socket: WebSocket, runtime_handle: Handle) {
-    let actor = RemoteActor::build(socket, runtime_handle);
+pub async fn game_handler(
+    player_name: String,
+    socket: WebSocket,
+    runtime_handle: Handle,
+    settings: Settings,
+) {
+    // A game can't be moved between threads while it waits on the socket, so it is
+    // played on a thread of its own.
+    let handle = runtime_handle.clone();
+    let result = task::spawn_blocking(move || {
+        handle.block_on(play_game(player_name, socket, settings));
+    })
+    .await;
+    if let Err(e) = result {
+        error!("Game task failed: {}", e);
+    }
+}
+
+/// Play a game for a remote player against auto players. The game is driven with
+/// `Game::advance`. The auto players bet with `Game::act`, and the remote player's bets
+/// are read from the socket and applied with `Game::apply`. An illegal bet is reported
+/// to the client, who is asked again.
+async fn play_game(player_name: String, socket: WebSocket, settings: Settings) {
+    let mut config = GameConfig::builder()
+        .big_blind(100)
+        .max_players(4)
+        .provably_fair(settings.provably_fair);
+    if let Some(secs) = settings.time_limit {
+        config = config.time_limit(Duration::from_secs(secs));
+    }
+    let config = match config.build() {
+        Ok(config) => config,
+        Err(e) => {
+            error!("Invalid game config: {}", e);
+            return;
+        }
+    };
+    let (mut client, actor) = RemoteClient::build(socket, config.time_limit);
     let p = Player::build(&player_name, actor);
-    let mut g = poker::new_game_one_player(p, 100, 3);
-    g.play();
+    let mut g = match poker::new_game_one_player(p, config) {
+        Ok(g) => g,
+        Err(e) => {
+            error!("Couldn't create game: {}", e);
+            return;
+        }
+    };
+    if !settings.blind_levels.is_empty() {
+        match BlindSchedule::build(settings.blind_levels) {
+            Ok(schedule) => g.set_blind_schedule(schedule),
+            Err(e) => error!("Ignoring blind schedule: {}", e),
+        }
+    }
+    if let Err(e) = drive_game(&mut g, &player_name, &mut client).await {
+        error!("Game for {} ended early: {}", player_name, e);
+    }
+    client.close().await;
+}
+
+/// Run the game until it is over or the remote player disconnects, sending the game's
+/// messages to the client as it goes.
+async fn drive_game(
+    g: &mut Game,
+    player_name: &str,
+    client: &mut RemoteClient,
+) -> Result<(), GameError> {
+    loop {
+        if !client.flush().await {
+            return Err(GameError::ActorFailed {
+                player: player_name.to_string(),
+            });
+        }
+        match g.advance()? {
+            // Nobody else can sit in, so there is no one left to play against.
+            PendingDecision::GameOver(_) | PendingDecision::Waiting => return Ok(()),
+            PendingDecision::Bet { player, args } if player == player_name => {
+                let bank_roll = g
+                    .bank_rolls()
+                    .into_iter()
+                    .find_map(|(name, bank_roll)| (name == player).then_some(bank_roll))
+                    .unwrap_or_default();
+                let Some(bet) = client.request_bet(args, bank_roll).await else {
+                    return Err(GameError::ActorFailed {
+                        player: player_name.to_string(),
+                    });
+                };
+                // The game is unchanged by an illegal bet, so the next call to
+                // `advance` asks the player again.
+                if let Err(e) = g.apply(&player, bet) {
+                    client.send(&PokerMessage::Error(e.to_string())).await;
+                }
+            }
+            PendingDecision::Bet { player, args } => {
+                g.act(&player, args)?;
+            }
+        }
+    }
 }
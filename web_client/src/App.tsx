@@ -95,8 +95,10 @@ function App() {
                     setBankRoll(message.bank_roll);
                     setCall(message.args.call);
                     setMinBet(message.args.min);
+                    const maxRaise = message.args.max;
                     let bets: string[] = ['Fold'];
-                    if (message.bank_roll > 0) {
+                    if (message.bank_roll > 0
+                        && (maxRaise === null || message.bank_roll <= message.args.call + maxRaise)) {
                         bets.push('AllIn');
                     }
                     if (message.args.call === 0) {
@@ -105,7 +107,7 @@ function App() {
                     if (message.args.call < bankRoll) {
                         bets.push('Call')
                     }
-                    if (message.args.call + message.args.min < message.bank_roll) {
+                    if (message.args.call + message.args.min < message.bank_roll && maxRaise !== 0) {
                         bets.push('Raise');
                     }
                     setPossibleBets(bets);
@@ -133,6 +135,54 @@ function App() {
                     enqueueMessage(msgStr);
                     break;
 
+                case 'BlindLevel':
+                    msgStr = `Blinds are now ${message.small_blind}/${message.big_blind}`;
+                    if (message.ante > 0) {
+                        msgStr += ` with an ante of ${message.ante}`;
+                    }
+                    enqueueMessage(msgStr);
+                    break;
+
+                case 'Straddle':
+                    enqueueMessage(`${message.player} straddled for ${message.amount}`);
+                    break;
+
+                case 'BombPot':
+                    enqueueMessage(`Bomb pot! Everyone antes ${message.ante}`);
+                    break;
+
+                case 'PlayerJoined':
+                    enqueueMessage(`${message.player} joined the table`);
+                    break;
+
+                case 'PlayerLeft':
+                    enqueueMessage(`${message.player} left the table`);
+                    break;
+
+                case 'SatOut':
+                    enqueueMessage(`${message.player} is sitting out`);
+                    break;
+
+                case 'SatIn':
+                    enqueueMessage(`${message.player} is back`);
+                    break;
+
+                case 'Rebuy':
+                    enqueueMessage(`${message.player} rebought ${message.amount} chips`);
+                    break;
+
+                case 'AddOn':
+                    enqueueMessage(`${message.player} took the add-on of ${message.amount} chips`);
+                    break;
+
+                case 'ShuffleCommitment':
+                    enqueueMessage(`Shuffle commitment for hand ${message.hand}: ${message.commitment}`);
+                    break;
+
+                case 'ShuffleRevealed':
+                    enqueueMessage(`Server seed for hand ${message.seeds.hand}: ${message.seeds.server_seed}`);
+                    break;
+
                 case 'RoundWinner':
                     const winnerType = Object.keys(message.winner)[0];
                     const winner = message.winner[winnerType];
@@ -146,14 +196,52 @@ function App() {
                     }
                     break;
 
-                case 'GameWinner':
-                    if (message.winner.type === 'SoleWinner') {
-                        msgStr = `${message.winner.name} won the game.`;
-                        enqueueMessage(msgStr);
+                case 'HandShown':
+                    enqueueMessage(`${message.hand.name} shows ${JSON.stringify(message.hand.hand)}`);
+                    break;
+
+                case 'HandMucked':
+                    enqueueMessage(`${message.player} mucks`);
+                    break;
+
+                case 'UncalledBet':
+                    enqueueMessage(`Uncalled bet of ${message.amount} returned to ${message.player}`);
+                    break;
+
+                case 'RoundWonUncontested':
+                    enqueueMessage(`${message.player} won the pot of ${message.pot} uncontested`);
+                    break;
+
+                case 'RabbitHunt':
+                    enqueueMessage(`The rest of the board would have been ${message.cards.map(parseCard).join(', ')}`);
+                    break;
+
+                case 'HandsRevealed':
+                    msgStr = message.hands
+                        .map(([name, cards]) => `${name} shows ${cards.map(parseCard).join(', ')}`)
+                        .join('; ');
+                    enqueueMessage(`All in: ${msgStr}`);
+                    break;
+
+                case 'SecondBoard':
+                    enqueueMessage(`Second board: ${message.cards.map(parseCard).join(', ')}`);
+                    break;
+
+                case 'SecondBoardWinner': {
+                    const boardWinnerType = Object.keys(message.winner)[0];
+                    const boardWinner = message.winner[boardWinnerType];
+                    if (boardWinnerType === 'SoleWinner') {
+                        enqueueMessage(`${boardWinner.name} won on the second board`);
                     } else {
-                        console.error(`Was expecting a single winner of the game, got ${message}`);
+                        enqueueMessage('The second board was a draw');
                     }
                     break;
+                }
+
+                case 'GameWinner':
+                    msgStr = `${message.winner} won the game.`;
+                    enqueueMessage(msgStr);
+                    break;
 
                 case 'Error':
                     console.error(`SERVER ERROR: ${message.msg}`);
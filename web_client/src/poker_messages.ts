@@ -10,6 +10,9 @@ export type Card = { rank: Rank; suit: Suit };
 
 export type BetArgs = {
     call: number;
+    min: number;
+    // The most the bet can be raised by, or null if there is no limit.
+    max: number | null;
     community_cards: Card[];
     cycle: number;
     stage: string;
@@ -101,6 +104,87 @@ interface StageDeclMessage {
     community_cards: Card[];
 }
 
+// A player straddled, posting a blind of twice the big blind.
+interface StraddleMessage {
+    type: 'Straddle';
+    player: string;
+    amount: number;
+}
+
+// The round is a bomb pot: everyone antes and the betting starts on the flop.
+interface BombPotMessage {
+    type: 'BombPot';
+    ante: number;
+}
+
+// Message announcing a new level of the blind schedule.
+interface BlindLevelMessage {
+    type: 'BlindLevel';
+    level: number;
+    small_blind: number;
+    big_blind: number;
+    ante: number;
+}
+
+// Message that a player joined the table.
+interface PlayerJoinedMessage {
+    type: 'PlayerJoined';
+    player: string;
+    bank_roll: number;
+}
+
+// Message that a player left the table with their chips.
+interface PlayerLeftMessage {
+    type: 'PlayerLeft';
+    player: string;
+    bank_roll: number;
+}
+
+// Message that a player is sitting out.
+interface SatOutMessage {
+    type: 'SatOut';
+    player: string;
+}
+
+// Message that a player who was sitting out is back.
+interface SatInMessage {
+    type: 'SatIn';
+    player: string;
+}
+
+// A player who went broke bought more chips.
+interface RebuyMessage {
+    type: 'Rebuy';
+    player: string;
+    amount: number;
+}
+
+// A player bought the add-on.
+interface AddOnMessage {
+    type: 'AddOn';
+    player: string;
+    amount: number;
+}
+
+// Commitment to the server seed for a hand, sent before the deal.
+interface ShuffleCommitmentMessage {
+    type: 'ShuffleCommitment';
+    hand: number;
+    commitment: string;
+}
+
+export type ShuffleSeeds = {
+    hand: number;
+    server_seed: string;
+    client_seeds: string[];
+};
+
+// The seeds for a hand, revealed after the hand.
+interface ShuffleRevealedMessage {
+    type: 'ShuffleRevealed';
+    seeds: ShuffleSeeds;
+}
+
 interface PlayerHand {
     type: 'PlayerHand';
     name: string;
@@ -126,10 +210,60 @@ interface RoundWinnerMessage {
     winner: Winner;
 }
 
+// A player showed their hand at the showdown.
+interface HandShownMessage {
+    type: 'HandShown';
+    hand: PlayerHand;
+}
+
+// A player mucked their hand at the showdown without showing it.
+interface HandMuckedMessage {
+    type: 'HandMucked';
+    player: string;
+}
+
+// The part of a bet that nobody called was returned to the player.
+interface UncalledBetMessage {
+    type: 'UncalledBet';
+    player: string;
+    amount: number;
+}
+
+// Everyone else folded, so the player won the pot without showing their cards.
+interface RoundWonUncontestedMessage {
+    type: 'RoundWonUncontested';
+    player: string;
+    pot: number;
+}
+
+// The community cards that would have come if the hand hadn't ended early.
+interface RabbitHuntMessage {
+    type: 'RabbitHunt';
+    cards: Card[];
+}
+
+// The hole cards of the players still in the round, shown when nobody can bet any more.
+interface HandsRevealedMessage {
+    type: 'HandsRevealed';
+    hands: [string, [Card, Card]][];
+}
+
+// The second board, when the players agreed to run it twice.
+interface SecondBoardMessage {
+    type: 'SecondBoard';
+    cards: Card[];
+}
+
+// The winner on the second board.
+interface SecondBoardWinnerMessage {
+    type: 'SecondBoardWinner';
+    winner: Winner;
+}
+
 // Message for the winner of the game.
 interface GameWinnerMessage {
     type: 'GameWinner';
-    winner: Winner;
+    winner: string;
 }
 
 // Error Message.
@@ -157,4 +291,11 @@ interface Player {
 // Union Type for incoming messages.
 export type IncomingPokerMessage = Player | PlaceBetMessage | BetPlacedMessage
     | PlayersInfoMessage | StageDeclMessage | RoundWinnerMessage
-    | GameWinnerMessage | ErrorMessage;
+    | HandShownMessage | HandMuckedMessage
+    | UncalledBetMessage | RoundWonUncontestedMessage | RabbitHuntMessage
+    | HandsRevealedMessage | SecondBoardMessage | SecondBoardWinnerMessage
+    | GameWinnerMessage | BlindLevelMessage | StraddleMessage | BombPotMessage
+    | PlayerJoinedMessage
+    | PlayerLeftMessage | SatOutMessage | SatInMessage | RebuyMessage | AddOnMessage
+    | ShuffleCommitmentMessage
+    | ShuffleRevealedMessage | ErrorMessage;